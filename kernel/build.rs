@@ -0,0 +1,64 @@
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Passes the build information to the kernel as environment variables.
+///
+/// # Notes
+///
+/// * `SOURCE_DATE_EPOCH` overrides the build time, for reproducible builds.
+/// * `KERNEL_CMDLINE` is embedded as the kernel command line, since the bootloader can't pass one.
+fn main() {
+    let timestamp = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_secs())
+        });
+    let target = env::var("TARGET").unwrap_or_else(|_| "unknown".into());
+    let cmdline = env::var("KERNEL_CMDLINE").unwrap_or_default();
+
+    println!(
+        "cargo:rustc-env=KERNEL_BUILD_TIMESTAMP={}",
+        format_timestamp(timestamp)
+    );
+    println!("cargo:rustc-env=KERNEL_TARGET={target}");
+    println!("cargo:rustc-env=KERNEL_CMDLINE={cmdline}");
+    // Rebuilt whenever the kernel changes, so the timestamp is that of the last kernel build.
+    println!("cargo:rerun-if-changed=src");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    println!("cargo:rerun-if-env-changed=KERNEL_CMDLINE");
+}
+
+/// Formats a Unix timestamp as a UTC date and time.
+///
+/// # Arguments
+///
+/// * `timestamp` - The seconds since the Unix epoch.
+///
+/// # Returns
+///
+/// * `String` - The date and time, like `2024-01-31 12:00:00 UTC`.
+fn format_timestamp(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+
+    // Converts the days since the epoch to a civil date, see http://howardhinnant.github.io/date_algorithms.html.
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month + 2) / 5 + 1;
+    let month = if month < 10 { month + 3 } else { month - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {hours:02}:{minutes:02}:{seconds:02} UTC",
+        hours = seconds / 3_600,
+        minutes = seconds / 60 % 60,
+        seconds = seconds % 60
+    )
+}
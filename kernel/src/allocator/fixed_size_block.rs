@@ -2,13 +2,20 @@ use core::alloc::{GlobalAlloc, Layout};
 use core::ptr::NonNull;
 use core::{mem, ptr};
 
-use crate::allocator::Locked;
+use crate::allocator::{self, Locked};
+use crate::kassert;
 
 /// The block sizes to use.
 ///
 /// The sizes must each be power of 2 because they are also used as
 /// the block alignment (alignments must be always powers of 2).
-const BLOCK_SIZES: &[usize] = &[8, 16, 32, 64, 128, 256, 512, 1024, 2048];
+pub(crate) const BLOCK_SIZES: &[usize] = &[8, 16, 32, 64, 128, 256, 512, 1024, 2048];
+
+/// The byte freshly allocated memory is filled with in debug builds.
+const ALLOC_POISON: u8 = 0xAA;
+
+/// The byte freed memory is filled with in debug builds.
+const FREE_POISON: u8 = 0xDD;
 
 /// A node in the linked list.
 ///
@@ -25,10 +32,14 @@ struct ListNode {
 ///
 /// * `list_heads`: The heads of the linked lists.
 /// * `fallback_allocator`: The fallback allocator.
+/// * `allocations`: The number of allocations made, excluding in-place reallocations.
+/// * `used`: The number of bytes in use, counting whole blocks.
 #[allow(clippy::module_name_repetitions)]
 pub struct FixedSizeBlockAllocator {
     list_heads: [Option<&'static mut ListNode>; BLOCK_SIZES.len()],
     fallback_allocator: linked_list_allocator::Heap,
+    allocations: usize,
+    used: usize,
 }
 
 impl FixedSizeBlockAllocator {
@@ -40,9 +51,31 @@ impl FixedSizeBlockAllocator {
         Self {
             list_heads: [EMPTY; BLOCK_SIZES.len()],
             fallback_allocator: linked_list_allocator::Heap::empty(),
+            allocations: 0,
+            used: 0,
         }
     }
 
+    /// Gets the number of allocations made.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of allocations made, excluding reallocations that were resized in place.
+    #[must_use]
+    pub const fn allocations(&self) -> usize {
+        self.allocations
+    }
+
+    /// Gets the number of bytes in use.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of bytes in use, counting whole blocks for small allocations.
+    #[must_use]
+    pub const fn used(&self) -> usize {
+        self.used
+    }
+
     /// Initialize the allocator with the given heap bounds.
     ///
     /// # Safety
@@ -69,6 +102,51 @@ impl FixedSizeBlockAllocator {
             .ok()
             .map_or(ptr::null_mut(), NonNull::as_ptr)
     }
+
+    /// Checks if the given block is already in the free list.
+    ///
+    /// # Arguments
+    ///
+    /// * `index`: The index of the free list to search.
+    /// * `ptr`: The pointer to the block.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether or not the block is in the free list.
+    fn is_free(&self, index: usize, ptr: *mut u8) -> bool {
+        let mut current = self.list_heads[index].as_deref();
+
+        while let Some(node) = current {
+            if ptr::eq(node, ptr.cast::<ListNode>()) {
+                return true;
+            }
+
+            current = node.next.as_deref();
+        }
+
+        false
+    }
+}
+
+/// Fills the given memory with the given byte.
+///
+/// # Arguments
+///
+/// * `ptr` - The pointer to the memory to fill.
+/// * `size` - The size of the memory in bytes.
+/// * `byte` - The byte to fill the memory with.
+///
+/// # Safety
+///
+/// * The caller must ensure that the given memory is valid for writes of `size` bytes.
+///
+/// # Notes
+///
+/// * This is a no-op in release builds.
+unsafe fn poison(ptr: *mut u8, size: usize, byte: u8) {
+    if cfg!(debug_assertions) && !ptr.is_null() {
+        ptr::write_bytes(ptr, byte, size);
+    }
 }
 
 /// Choose an appropriate block size for the given layout.
@@ -103,30 +181,51 @@ unsafe impl GlobalAlloc for Locked<FixedSizeBlockAllocator> {
     /// * The caller must ensure that the given memory range is unused.
     /// * The caller must ensure that the given layout is valid.
     /// * The caller must ensure that the allocation succeeds.
+    ///
+    /// # Notes
+    ///
+    /// * In debug builds, the allocated memory is filled with [`ALLOC_POISON`].
+    /// * If the heap is exhausted, the failure is reported before returning a null pointer.
     #[allow(clippy::expect_used)]
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         let mut allocator = self.lock();
+        allocator.allocations += 1;
 
-        match list_index(&layout) {
+        let (ptr, size) = match list_index(&layout) {
             Some(index) => {
-                if let Some(node) = allocator.list_heads[index].take() {
+                let block_size = BLOCK_SIZES[index];
+
+                let ptr = if let Some(node) = allocator.list_heads[index].take() {
                     allocator.list_heads[index] = node.next.take();
 
                     (node as *mut ListNode).cast::<u8>()
                 } else {
                     // No block exists in list => allocate new block.
-                    let block_size = BLOCK_SIZES[index];
-
                     // Only works if all block sizes are a power of 2.
                     let block_align = block_size;
                     let layout = Layout::from_size_align(block_size, block_align)
                         .expect("Wrong block size!");
 
                     allocator.fallback_alloc(layout)
-                }
+                };
+
+                (ptr, block_size)
             }
-            None => allocator.fallback_alloc(layout),
+            None => (allocator.fallback_alloc(layout), layout.size()),
+        };
+
+        if ptr.is_null() {
+            // Release the lock, so the report can read the heap usage.
+            drop(allocator);
+            allocator::report_out_of_memory(layout);
+
+            return ptr;
         }
+
+        allocator.used += size;
+        poison(ptr, size, ALLOC_POISON);
+
+        ptr
     }
 
     /// Deallocates the memory at the given pointer with the given layout.
@@ -141,27 +240,296 @@ unsafe impl GlobalAlloc for Locked<FixedSizeBlockAllocator> {
     /// * The caller must ensure that the given layout is valid.
     /// * The caller must ensure that the given pointer is valid.
     /// * The caller must ensure that the given pointer is allocated.
-    #[allow(clippy::expect_used, clippy::cast_ptr_alignment)]
+    ///
+    /// # Panics
+    ///
+    /// * In debug builds, if the given pointer has already been freed.
+    ///
+    /// # Notes
+    ///
+    /// * In debug builds, the freed memory is filled with [`FREE_POISON`].
+    /// * Halts with [`kassert!`] if the pointer is null, or a block is too small to hold a free list node.
+    #[allow(clippy::cast_ptr_alignment)]
     unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
         let mut allocator = self.lock();
 
         if let Some(index) = list_index(&layout) {
+            let block_size = BLOCK_SIZES[index];
+
+            if cfg!(debug_assertions) && allocator.is_free(index, ptr) {
+                // Release the lock, so the panic handler can still allocate.
+                drop(allocator);
+
+                panic!("Double free of {ptr:p} (Block Size: {block_size})!");
+            }
+
+            poison(ptr, block_size, FREE_POISON);
+            allocator.used -= block_size;
+
             let new_node = ListNode {
                 next: allocator.list_heads[index].take(),
             };
 
             // Verify that block has size and alignment required for storing node.
-            assert!(mem::size_of::<ListNode>() <= BLOCK_SIZES[index]);
-            assert!(mem::align_of::<ListNode>() <= BLOCK_SIZES[index]);
+            kassert!(
+                mem::size_of::<ListNode>() <= block_size,
+                "A block of {block_size} bytes is too small for a free list node!"
+            );
+            kassert!(
+                mem::align_of::<ListNode>() <= block_size,
+                "A block of {block_size} bytes is too loosely aligned for a free list node!"
+            );
 
             let new_node_ptr = ptr.cast::<ListNode>();
             new_node_ptr.write(new_node);
 
             allocator.list_heads[index] = Some(&mut *new_node_ptr);
         } else {
-            let ptr = NonNull::new(ptr).expect("Null pointer passed to deallocate!");
+            kassert!(!ptr.is_null(), "Null pointer passed to deallocate!");
+            let ptr = NonNull::new_unchecked(ptr);
+            poison(ptr.as_ptr(), layout.size(), FREE_POISON);
+            allocator.used -= layout.size();
 
             allocator.fallback_allocator.deallocate(ptr, layout);
         }
     }
+
+    /// Resizes the memory at the given pointer to the given size.
+    ///
+    /// If the old and new sizes map to the same block size, the block is reused as is.
+    /// Otherwise, a new block is allocated, the contents are copied over, and the old block is freed.
+    ///
+    /// # Arguments
+    ///
+    /// * `ptr` - The pointer to the memory to resize.
+    /// * `layout` - The current layout of the memory.
+    /// * `new_size` - The new size of the memory.
+    ///
+    /// # Returns
+    ///
+    /// * `*mut u8` - A pointer to the resized memory, or a null pointer if the allocation failed.
+    ///
+    /// # Safety
+    ///
+    /// * The caller must ensure that the given pointer was allocated with the given layout.
+    /// * The caller must ensure that `new_size` is non-zero and doesn't overflow when rounded up to the alignment.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = Layout::from_size_align_unchecked(new_size, layout.align());
+
+        // Both sizes fit in the same block => resize in place.
+        if let (Some(old_index), Some(new_index)) = (list_index(&layout), list_index(&new_layout)) {
+            if old_index == new_index {
+                return ptr;
+            }
+        }
+
+        let new_ptr = self.alloc(new_layout);
+        if !new_ptr.is_null() {
+            ptr::copy_nonoverlapping(ptr, new_ptr, layout.size().min(new_size));
+
+            self.dealloc(ptr, layout);
+        }
+
+        new_ptr
+    }
+}
+
+/// Tests that growing a `Vec` within a block size doesn't allocate.
+///
+/// # Panics
+///
+/// * If every push resulted in a new allocation.
+#[test_case]
+fn test_realloc_in_place() {
+    use alloc::vec::Vec;
+
+    const PUSHES: usize = 2_048;
+
+    let mut vec: Vec<u8> = Vec::with_capacity(1);
+    let before = super::ALLOCATOR.lock().allocations();
+
+    // Grow one element at a time, so each push is a separate reallocation.
+    for _ in 0..PUSHES {
+        vec.reserve_exact(1);
+        vec.push(0);
+    }
+
+    let after = super::ALLOCATOR.lock().allocations();
+
+    assert_eq!(vec.len(), PUSHES);
+    assert!(after - before < PUSHES);
+}
+
+/// Tests that freshly allocated memory is poisoned in debug builds.
+///
+/// # Panics
+///
+/// * If the allocated memory isn't filled with [`ALLOC_POISON`].
+#[test_case]
+fn test_alloc_poison() {
+    use alloc::alloc::{alloc, dealloc};
+
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let layout = Layout::new::<[u8; 32]>();
+    unsafe {
+        let ptr = alloc(layout);
+        let bytes = core::slice::from_raw_parts(ptr, layout.size());
+
+        assert!(bytes.iter().all(|&byte| byte == ALLOC_POISON));
+
+        dealloc(ptr, layout);
+    }
+}
+
+/// Tests that the bytes in use follow allocations and deallocations.
+///
+/// # Panics
+///
+/// * If an allocation isn't counted.
+/// * If a deallocation isn't subtracted again.
+#[test_case]
+fn test_used_bytes() {
+    use alloc::vec::Vec;
+
+    let before = super::heap_stats().used;
+    let vec: Vec<u8> = Vec::with_capacity(4_096);
+
+    assert!(super::heap_stats().used >= before + vec.capacity());
+
+    drop(vec);
+
+    assert_eq!(super::heap_stats().used, before);
+}
+
+/// Tests that an allocation larger than the heap fails without being counted.
+///
+/// # Panics
+///
+/// * If the allocation succeeds.
+/// * If the failed allocation changes the bytes in use.
+#[test_case]
+fn test_out_of_memory() {
+    use alloc::vec::Vec;
+
+    let before = super::heap_stats().used;
+    let mut vec: Vec<u8> = Vec::new();
+
+    assert!(vec.try_reserve(super::HEAP_SIZE * 2).is_err());
+    assert_eq!(super::heap_stats().used, before);
+}
+
+/// Tests that many small allocations can be live at once.
+///
+/// # Panics
+///
+/// * If the boxes don't hold their values.
+#[test_case]
+fn test_many_boxes() {
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+
+    const BOXES: u64 = 1_000;
+
+    let boxes: Vec<Box<u64>> = (0..BOXES).map(Box::new).collect();
+    let sum: u64 = boxes.iter().map(|value| **value).sum();
+
+    assert_eq!(sum, (BOXES - 1) * BOXES / 2);
+}
+
+/// Tests that freed blocks are reused by later allocations.
+///
+/// # Panics
+///
+/// * If a freed block isn't handed out again.
+/// * If the heap runs out, which it would without reuse.
+#[test_case]
+fn test_free_list_reuse() {
+    use alloc::boxed::Box;
+
+    let first = Box::new(0_u64);
+    let addr = ptr::addr_of!(*first) as usize;
+    drop(first);
+
+    // The free list is last in, first out.
+    let second = Box::new(1_u64);
+    assert_eq!(ptr::addr_of!(*second) as usize, addr);
+    drop(second);
+
+    // More than the whole heap in total, so this only succeeds if blocks are reused.
+    for i in 0..super::HEAP_SIZE {
+        let value = Box::new(i);
+        assert_eq!(*value, i);
+    }
+}
+
+/// Tests allocating from every block size and from the fallback allocator.
+///
+/// # Panics
+///
+/// * If an allocation fails.
+/// * If an allocation isn't aligned to its size.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_every_block_size() {
+    use alloc::alloc::{alloc, dealloc};
+
+    let fallback = BLOCK_SIZES[BLOCK_SIZES.len() - 1] * 2;
+    for &size in BLOCK_SIZES.iter().chain([fallback].iter()) {
+        let layout = Layout::from_size_align(size, size).expect("Invalid layout!");
+        unsafe {
+            let ptr = alloc(layout);
+
+            assert!(!ptr.is_null());
+            assert_eq!(ptr as usize % size, 0);
+
+            ptr.write_bytes(0, size);
+            dealloc(ptr, layout);
+        }
+    }
+}
+
+/// Tests that interleaving large and small allocations doesn't exhaust the heap.
+///
+/// The small allocations stay alive while the large ones are freed, leaving holes between them.
+///
+/// # Panics
+///
+/// * If an allocation fails before the heap is genuinely exhausted.
+/// * If a large allocation fails after the fragmenting allocations.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_fragmentation() {
+    use alloc::alloc::{alloc, dealloc};
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+
+    const ROUNDS: usize = 100;
+    const LARGE_SIZE: usize = 4_096;
+
+    let large = Layout::from_size_align(LARGE_SIZE, 8).expect("Invalid layout!");
+    let mut small = Vec::with_capacity(ROUNDS);
+    for i in 0..ROUNDS {
+        unsafe {
+            let ptr = alloc(large);
+            assert!(!ptr.is_null());
+
+            small.push(Box::new(i));
+
+            dealloc(ptr, large);
+        }
+    }
+
+    assert!(small.iter().enumerate().all(|(i, value)| **value == i));
+
+    // The space the large allocations used must still be usable in one piece.
+    let layout = Layout::from_size_align(super::HEAP_SIZE / 4, 8).expect("Invalid layout!");
+    unsafe {
+        let ptr = alloc(layout);
+        assert!(!ptr.is_null());
+
+        dealloc(ptr, layout);
+    }
 }
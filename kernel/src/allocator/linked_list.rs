@@ -3,6 +3,7 @@ use core::{mem, ptr};
 
 use crate::allocator::Locked;
 use crate::errors::Error;
+use crate::kassert;
 
 use super::align_up;
 
@@ -218,7 +219,10 @@ unsafe impl GlobalAlloc for Locked<LinkedListAllocator> {
     /// # Panics
     ///
     /// * If the allocation fails due to invalid layout.
-    /// * If the allocation fails due to overflow.
+    ///
+    /// # Notes
+    ///
+    /// * Halts with [`kassert!`] if the end of the allocation overflows.
     #[allow(clippy::expect_used)]
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         // Perform layout adjustments.
@@ -228,9 +232,11 @@ unsafe impl GlobalAlloc for Locked<LinkedListAllocator> {
 
         // Look for a suitable region and allocate it.
         if let Some((region, alloc_start)) = allocator.find_region(size, align) {
-            let alloc_end = alloc_start
-                .checked_add(size)
-                .expect("Allocation failed due to overflow!");
+            kassert!(
+                size <= usize::MAX - alloc_start,
+                "Allocation failed due to overflow!"
+            );
+            let alloc_end = alloc_start + size;
             let excess_size = region.end_addr() - alloc_end;
             if excess_size > 0 {
                 allocator.find_region(alloc_end, excess_size);
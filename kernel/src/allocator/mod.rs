@@ -10,6 +10,9 @@ use x86_64::{
 
 use fixed_size_block::FixedSizeBlockAllocator;
 
+use crate::mem::layout;
+use crate::try_println;
+
 pub mod bump;
 pub mod fixed_size_block;
 pub mod linked_list;
@@ -18,8 +21,9 @@ pub mod linked_list;
 ///
 /// # Notes
 ///
-/// * This is 16 TiB.
-pub const HEAP_START: usize = 0x4000_0000_0000;
+/// * This is 64 TiB, see [`layout::HEAP`].
+#[allow(clippy::cast_possible_truncation)]
+pub const HEAP_START: usize = layout::HEAP.start as usize;
 
 /// The size of the heap in bytes.
 ///
@@ -28,6 +32,11 @@ pub const HEAP_START: usize = 0x4000_0000_0000;
 /// * This is 100 KiB.
 pub const HEAP_SIZE: usize = 100 * 1024;
 
+const _: () = assert!(
+    HEAP_SIZE as u64 <= layout::HEAP.size,
+    "The heap doesn't fit in its region!"
+);
+
 #[global_allocator]
 static ALLOCATOR: Locked<FixedSizeBlockAllocator> = Locked::new(FixedSizeBlockAllocator::new());
 
@@ -43,6 +52,58 @@ unsafe impl GlobalAlloc for Dummy {
     }
 }
 
+/// The usage of the heap.
+///
+/// # Fields
+///
+/// * `size` - The size of the heap, in bytes.
+/// * `used` - The number of bytes in use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapStats {
+    pub size: usize,
+    pub used: usize,
+}
+
+/// Gets the usage of the heap.
+///
+/// # Returns
+///
+/// * `HeapStats` - The heap usage.
+#[must_use]
+pub fn heap_stats() -> HeapStats {
+    HeapStats {
+        size: HEAP_SIZE,
+        used: ALLOCATOR.lock().used(),
+    }
+}
+
+/// Reports an allocation the heap couldn't satisfy.
+///
+/// Called by the global allocator right before it returns a null pointer. For infallible allocations, the
+/// allocation error handler then panics, which halts the kernel. The heap can't grow, so there's nothing to retry.
+///
+/// # Arguments
+///
+/// * `layout` - The layout of the failed allocation.
+///
+/// # Notes
+///
+/// * Fallible allocations, like `Vec::try_reserve`, are reported too, even though their caller handles the failure.
+/// * Must be called without holding the allocator lock.
+/// * Prints with `try_println!`, since the failed allocation may have been made while holding the writer, and
+///   formatting mustn't allocate either.
+pub(crate) fn report_out_of_memory(layout: Layout) {
+    let stats = heap_stats();
+
+    try_println!(
+        "[ERROR]: Out of memory, failed to allocate {size} bytes (Align: {align}), with {used} of {heap} heap bytes in use!",
+        size = layout.size(),
+        align = layout.align(),
+        used = stats.used,
+        heap = stats.size,
+    );
+}
+
 /// Initialize the heap allocator with the given heap bounds.
 ///
 /// # Arguments
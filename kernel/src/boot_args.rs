@@ -0,0 +1,229 @@
+use core::fmt;
+
+use conquer_once::spin::OnceCell;
+
+/// The kernel command line, embedded at build time from the `KERNEL_CMDLINE` environment variable.
+///
+/// # Notes
+///
+/// * The bootloader can't pass a command line, so this is the only source of boot arguments.
+pub const COMMAND_LINE: &str = env!("KERNEL_CMDLINE");
+
+/// The boot arguments, set once during initialization.
+static BOOT_ARGS: OnceCell<BootArgs> = OnceCell::uninit();
+
+/// The boot arguments used before they're parsed.
+static DEFAULT: BootArgs = BootArgs::new();
+
+/// How much the kernel logs.
+///
+/// # Variants
+///
+/// * `Error` - Only errors.
+/// * `Warn` - Errors and warnings.
+/// * `Info` - Everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+}
+
+impl LogLevel {
+    /// Parses a log level, either by name or number.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The log level, like `warn` or `1`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Self>` - The log level, if it's valid.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "error" | "0" => Some(Self::Error),
+            "warn" | "1" => Some(Self::Warn),
+            "info" | "2" => Some(Self::Info),
+            _ => None,
+        }
+    }
+}
+
+/// An error parsing the command line.
+///
+/// # Variants
+///
+/// * `Unknown` - An argument the kernel doesn't know.
+/// * `InvalidValue` - A known argument with a value it can't take.
+/// * `MissingValue` - A known argument without the value it needs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgumentError<'a> {
+    Unknown(&'a str),
+    InvalidValue { key: &'a str, value: &'a str },
+    MissingValue(&'a str),
+}
+
+impl fmt::Display for ArgumentError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unknown(argument) => write!(f, "Unknown argument `{argument}`"),
+            Self::InvalidValue { key, value } => write!(f, "Invalid value `{value}` for `{key}`"),
+            Self::MissingValue(key) => write!(f, "Missing value for `{key}`"),
+        }
+    }
+}
+
+/// The arguments the kernel was booted with.
+///
+/// # Fields
+///
+/// * `log_level` - How much the kernel logs, set with `loglevel=`.
+/// * `init` - The program to start once the kernel is up, set with `init=`.
+/// * `no_apic` - Whether to stay on the legacy PIC, set with `noapic`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BootArgs {
+    pub log_level: LogLevel,
+    pub init: &'static str,
+    pub no_apic: bool,
+}
+
+impl BootArgs {
+    /// Creates the default `BootArgs`.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            log_level: LogLevel::Info,
+            init: "shell",
+            no_apic: false,
+        }
+    }
+
+    /// Parses a command line of whitespace separated `key=value` pairs and flags.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The command line.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, ArgumentError>` - The boot arguments, with defaults for those not given.
+    ///
+    /// # Errors
+    ///
+    /// * If an argument is unknown, or has an invalid or missing value.
+    ///
+    /// # Notes
+    ///
+    /// * Doesn't allocate, since it runs before the heap is initialized.
+    pub fn parse(line: &'static str) -> Result<Self, ArgumentError<'static>> {
+        let mut args = Self::new();
+        for argument in line.split_whitespace() {
+            let (key, value) = match argument.split_once('=') {
+                Some((key, value)) => (key, Some(value)),
+                None => (argument, None),
+            };
+
+            match (key, value) {
+                ("loglevel", Some(value)) => {
+                    args.log_level =
+                        LogLevel::parse(value).ok_or(ArgumentError::InvalidValue { key, value })?;
+                }
+                ("init", Some("")) | ("loglevel" | "init", None) => {
+                    return Err(ArgumentError::MissingValue(key));
+                }
+                ("init", Some(value)) => args.init = value,
+                ("noapic", None) => args.no_apic = true,
+                ("noapic", Some(value)) => return Err(ArgumentError::InvalidValue { key, value }),
+                _ => return Err(ArgumentError::Unknown(argument)),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+impl Default for BootArgs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sets the boot arguments, if they haven't been set already.
+///
+/// # Arguments
+///
+/// * `args` - The boot arguments.
+pub fn init(args: BootArgs) {
+    BOOT_ARGS.init_once(|| args);
+}
+
+/// Gets the boot arguments.
+///
+/// # Returns
+///
+/// * `&'static BootArgs` - The boot arguments, or the defaults if they haven't been set yet.
+#[must_use]
+pub fn get() -> &'static BootArgs {
+    BOOT_ARGS.get().unwrap_or(&DEFAULT)
+}
+
+/// Checks if messages of a log level should be logged.
+///
+/// # Arguments
+///
+/// * `level` - The log level of the message.
+///
+/// # Returns
+///
+/// * `bool` - Whether the message should be logged.
+#[must_use]
+pub fn logs(level: LogLevel) -> bool {
+    level <= get().log_level
+}
+
+/// Tests that the arguments are parsed, and anything else is rejected.
+///
+/// # Panics
+///
+/// * If a valid command line isn't parsed as expected.
+/// * If an invalid command line is accepted.
+#[test_case]
+fn test_parse() {
+    assert_eq!(BootArgs::parse(""), Ok(BootArgs::new()));
+    assert_eq!(
+        BootArgs::parse("  loglevel=warn init=tests   noapic "),
+        Ok(BootArgs {
+            log_level: LogLevel::Warn,
+            init: "tests",
+            no_apic: true,
+        })
+    );
+    assert_eq!(
+        BootArgs::parse("loglevel=0").map(|args| args.log_level),
+        Ok(LogLevel::Error)
+    );
+
+    assert_eq!(
+        BootArgs::parse("quiet"),
+        Err(ArgumentError::Unknown("quiet"))
+    );
+    assert_eq!(
+        BootArgs::parse("loglevel=loud"),
+        Err(ArgumentError::InvalidValue {
+            key: "loglevel",
+            value: "loud"
+        })
+    );
+    assert_eq!(
+        BootArgs::parse("init="),
+        Err(ArgumentError::MissingValue("init"))
+    );
+    assert_eq!(
+        BootArgs::parse("noapic=1"),
+        Err(ArgumentError::InvalidValue {
+            key: "noapic",
+            value: "1"
+        })
+    );
+}
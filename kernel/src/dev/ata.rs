@@ -1,19 +1,55 @@
 use alloc::boxed::Box;
+use alloc::format;
 use alloc::{string::String, vec::Vec};
 use bit_field::BitField;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use core::task::{Context, Poll};
 use core::{convert::TryInto, hint::spin_loop};
+use futures_util::task::AtomicWaker;
 use lazy_static::lazy_static;
-use spin::Mutex;
+use spin::{Mutex, MutexGuard};
+use x86_64::instructions::interrupts;
 use x86_64::instructions::port::{Port, PortReadOnly, PortWriteOnly};
 
+use crate::dev::BlockDevice;
 use crate::errors::Error;
 use crate::println;
-use crate::sys::time::clock::uptime;
-use crate::sys::time::wait;
+use crate::sys::idt;
+use crate::sys::task::timer::{self, Sleep};
+use crate::sys::time::{self, wait};
 
 /// The maximum block size of the ATA bus.
 pub const BLOCK_SIZE: usize = 512;
 
+/// The first block that can't be addressed with 28-bit LBA, which is the 128 GiB mark.
+const LBA28_LIMIT: u64 = 1 << 28;
+/// The number of blocks that can be addressed with 48-bit LBA.
+const LBA48_LIMIT: u64 = 1 << 48;
+
+/// The I/O bases of the primary and secondary bus.
+const IO_BASES: [u16; 2] = [0x1F0, 0x170];
+/// The interrupt request lines of the primary and secondary bus.
+pub const IRQS: [u8; 2] = [14, 15];
+
+/// The default time to wait for a drive, in nanoseconds.
+///
+/// # Notes
+///
+/// * This is 30 seconds, which leaves room for drives spinning up.
+pub const DEFAULT_TIMEOUT_NS: u64 = 30_000_000_000;
+
+/// The time to wait for a drive before giving up, in nanoseconds.
+static TIMEOUT_NS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT_NS);
+
+/// Whether each bus has raised an interrupt since its last command.
+static IRQ_RECEIVED: [AtomicBool; 2] = [AtomicBool::new(false), AtomicBool::new(false)];
+/// The wakers of the tasks waiting for an interrupt from each bus.
+static IRQ_WAKERS: [AtomicWaker; 2] = [AtomicWaker::new(), AtomicWaker::new()];
+/// Whether each bus has an asynchronous command in flight, during which the bus is unlocked.
+static IN_FLIGHT: [AtomicBool; 2] = [AtomicBool::new(false), AtomicBool::new(false)];
+
 lazy_static! {
     /// The ATA buses.
     pub static ref BUSES: Mutex<Vec<Bus>> = Mutex::new(Vec::new());
@@ -24,13 +60,19 @@ lazy_static! {
 /// # Variants
 ///
 /// * `Identify` - The identify command.
-/// * `Read` - The read command.
-/// * `Write` - The write command.
+/// * `Read` - The read command, with 28-bit LBA.
+/// * `Write` - The write command, with 28-bit LBA.
+/// * `ReadExt` - The read command, with 48-bit LBA.
+/// * `WriteExt` - The write command, with 48-bit LBA.
+/// * `FlushCache` - The command flushing the write cache of the drive.
 #[derive(Debug)]
 enum Command {
     Identify = 0xEC,
     Read = 0x20,
     Write = 0x30,
+    ReadExt = 0x24,
+    WriteExt = 0x34,
+    FlushCache = 0xE7,
 }
 
 /// Represents a device type.
@@ -110,31 +152,35 @@ enum Register {
 }
 
 impl Register {
-    /// Reads from the register.
+    /// Reads a byte from an 8-bit register.
     ///
     /// # Returns
     ///
-    /// * `Result<u16, Error>` - The result of the operation.
+    /// * `Result<u8, Error>` - The result of the operation.
     ///
     /// # Errors
     ///
     /// * If the register is write-only.
-    fn read(&mut self) -> Result<u16, Error> {
+    /// * If the register is the 16-bit data register.
+    fn read_u8(&mut self) -> Result<u8, Error> {
         let value = unsafe {
             match self {
-                Self::Data(port) => port.read(),
-
                 Self::Error(port)
                 | Self::DeviceAddress(port)
                 | Self::Status(port)
-                | Self::AlternateStatus(port) => port.read().into(),
+                | Self::AlternateStatus(port) => port.read(),
 
                 Self::SectorCount(port)
                 | Self::Lba0(port)
                 | Self::Lba1(port)
                 | Self::Lba2(port)
-                | Self::Drive(port) => port.read().into(),
+                | Self::Drive(port) => port.read(),
 
+                Self::Data(_) => {
+                    return Err(Error::InvalidRegister(
+                        "Cannot read a byte from the 16-bit data port!".into(),
+                    ))
+                }
                 Self::Features(_) | Self::Command(_) | Self::DeviceControl(_) => {
                     return Err(Error::InvalidRegister(
                         "Cannot read from write-only port!".into(),
@@ -146,7 +192,23 @@ impl Register {
         Ok(value)
     }
 
-    /// Writes to the register.
+    /// Reads a word from the register, widening the value of 8-bit registers.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u16, Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the register is write-only.
+    fn read_u16(&mut self) -> Result<u16, Error> {
+        match self {
+            Self::Data(port) => Ok(unsafe { port.read() }),
+            _ => Ok(self.read_u8()?.into()),
+        }
+    }
+
+    /// Writes a byte to an 8-bit register.
     ///
     /// # Arguments
     ///
@@ -159,21 +221,25 @@ impl Register {
     /// # Errors
     ///
     /// * If the register is read-only.
-    fn write(&mut self, value: u16) -> Result<(), Error> {
+    /// * If the register is the 16-bit data register.
+    fn write_u8(&mut self, value: u8) -> Result<(), Error> {
         unsafe {
             match self {
-                Self::Data(port) => port.write(value),
-
                 Self::Features(port) | Self::Command(port) | Self::DeviceControl(port) => {
-                    port.write(u8::try_from(value)?)
+                    port.write(value);
                 }
 
                 Self::SectorCount(port)
                 | Self::Lba0(port)
                 | Self::Lba1(port)
                 | Self::Lba2(port)
-                | Self::Drive(port) => port.write(u8::try_from(value)?),
+                | Self::Drive(port) => port.write(value),
 
+                Self::Data(_) => {
+                    return Err(Error::InvalidRegister(
+                        "Cannot write a byte to the 16-bit data port!".into(),
+                    ))
+                }
                 Self::Error(_)
                 | Self::Status(_)
                 | Self::AlternateStatus(_)
@@ -187,6 +253,31 @@ impl Register {
 
         Ok(())
     }
+
+    /// Writes a word to the register.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to write.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the register is read-only.
+    /// * If the register is 8 bits wide and the value doesn't fit in a byte.
+    fn write_u16(&mut self, value: u16) -> Result<(), Error> {
+        match self {
+            Self::Data(port) => {
+                unsafe { port.write(value) };
+
+                Ok(())
+            }
+            _ => self.write_u8(u8::try_from(value)?),
+        }
+    }
 }
 
 /// The ATA bus.
@@ -277,7 +368,7 @@ impl Bus {
     ///
     /// * If the status register is invalid.
     fn floating_bus(&mut self) -> Result<bool, Error> {
-        let status = self.status.read()?;
+        let status = self.status.read_u8()?;
 
         Ok(status == 0xFF || status == 0x7F)
     }
@@ -291,8 +382,8 @@ impl Bus {
     /// # Errors
     ///
     /// * If the status register is invalid.
-    fn clear_interrupt(&mut self) -> Result<u16, Error> {
-        self.status.read()
+    fn clear_interrupt(&mut self) -> Result<u8, Error> {
+        self.status.read_u8()
     }
 
     /// Selects a drive.
@@ -314,7 +405,7 @@ impl Bus {
         self.poll(Status::Busy, false)?;
         self.poll(Status::DataRequest, false)?;
 
-        self.drive.write(u16::from(0xA0 | drive << 4))?;
+        self.drive.write_u8(0xA0 | drive << 4)?;
 
         // Wait for 400 nanoseconds.
         wait(400);
@@ -335,7 +426,7 @@ impl Bus {
     ///
     /// * If the status register is write-only.
     fn error(&mut self) -> Result<bool, Error> {
-        Ok(self.status.read()?.get_bit(Status::Error as usize))
+        Ok(self.status.read_u8()?.get_bit(Status::Error as usize))
     }
 
     /// Gets the ID of the bus.
@@ -353,6 +444,7 @@ impl Bus {
     /// * If the ATA drive does not exist.
     /// * If the ATA times out.
     /// * If the ATA drive is not a valid drive.
+    /// * If the ATA drive fails to identify itself.
     fn identify_drive(&mut self, drive: u8) -> Result<DeviceType, Error> {
         if self.floating_bus()? {
             return Ok(DeviceType::None);
@@ -363,8 +455,11 @@ impl Bus {
         // Clear the registers.
         self.write_cmd_params(drive, 0)?;
 
+        self.command.write_u8(Command::Identify as u8)?;
+        wait(400);
+
         // Read the status register.
-        let status = self.status.read()?;
+        let status = self.status.read_u8()?;
         // If the drive does not exist.
         if status == 0 {
             return Ok(DeviceType::None);
@@ -373,22 +468,26 @@ impl Bus {
         // Poll the status register until busy clears.
         self.poll(Status::Busy, false)?;
 
-        // Determine if the drive type.
-        let device_type = match (self.lba1.read()?, self.lba2.read()?) {
-            (0x00, 0x00) => DeviceType::Ata({
-                let mut buffer = Box::new([0; 256]);
-                for chunk in buffer.iter_mut() {
-                    *chunk = self.data.read()?;
-                }
-
-                buffer
-            }),
-            (0x14, 0xEB) => DeviceType::Atapi,
-            (0x3C, 0xC3) => DeviceType::Sata,
+        // Packet devices abort the command and leave their signature, so they must not be waited on for data.
+        match (self.lba1.read_u8()?, self.lba2.read_u8()?) {
+            (0x00, 0x00) => {}
+            (0x14, 0xEB) => return Ok(DeviceType::Atapi),
+            (0x3C, 0xC3) => return Ok(DeviceType::Sata),
             (_, _) => return Err(Error::Internal("Unknown ATA drive!".into())),
-        };
+        }
+
+        if self.error()? {
+            return Err(Error::Internal("ATA drive failed to identify!".into()));
+        }
+
+        self.poll(Status::DataRequest, true)?;
+
+        let mut buffer = Box::new([0; 256]);
+        for chunk in buffer.iter_mut() {
+            *chunk = self.data.read_u16()?;
+        }
 
-        Ok(device_type)
+        Ok(DeviceType::Ata(buffer))
     }
 
     /// Polls the status register.
@@ -407,11 +506,14 @@ impl Bus {
     /// * If the ATA times out.
     /// * If the status register is write-only.
     fn poll(&mut self, bit: Status, value: bool) -> Result<(), Error> {
-        let start = uptime();
-
-        while self.status.read()?.get_bit(bit as usize) != value {
-            if uptime() - start > 1.0 {
-                return Err(Error::Internal("ATA timeout.".into()));
+        let deadline = time::deadline(TIMEOUT_NS.load(Ordering::Relaxed));
+
+        while self.status.read_u8()?.get_bit(bit as usize) != value {
+            if time::read_tsc() > deadline {
+                return Err(Error::ATA(format!(
+                    "Timed out waiting for {bit:?} to be {value} (Bus: {id})!",
+                    id = self.id
+                )));
             }
 
             spin_loop();
@@ -436,12 +538,29 @@ impl Bus {
     ///
     /// * If PIO fails to setup for the given drive and block.
     /// * If the ATA read fails.
-    fn read(&mut self, drive: u8, block: u32, buffer: &mut [u8]) -> Result<(), Error> {
-        self.setup_pio(drive, block)?;
-        self.write_cmd(Command::Read)?;
+    fn read(&mut self, drive: u8, block: u64, buffer: &mut [u8]) -> Result<(), Error> {
+        self.start(drive, block, false)?;
+        self.wait_for_data()?;
+
+        self.read_data(buffer)
+    }
 
+    /// Reads the data of a block, once the drive is ready to send it.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - The buffer to read into.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the ATA read fails.
+    fn read_data(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
         for chunk in buffer.chunks_mut(2) {
-            let data = self.data.read()?.to_le_bytes();
+            let data = self.data.read_u16()?.to_le_bytes();
 
             chunk.clone_from_slice(&data);
         }
@@ -463,10 +582,10 @@ impl Bus {
     ///
     /// * If the device control register is write-only.
     fn reset(&mut self) -> Result<(), Error> {
-        self.device_control.write(4)?; // set SRST.
+        self.device_control.write_u8(4)?; // set SRST.
         wait(5); // Wait for 5 nanoseconds.
 
-        self.device_control.write(0)?; // Clear control register.
+        self.device_control.write_u8(0)?; // Clear control register.
         wait(2_000); // Wait for 2 microseconds.
 
         Ok(())
@@ -474,6 +593,8 @@ impl Bus {
 
     /// Sets up PIO.
     ///
+    /// Blocks past the 28-bit limit are addressed with 48-bit LBA.
+    ///
     /// # Arguments
     ///
     /// * `drive` - The drive to setup.
@@ -481,17 +602,27 @@ impl Bus {
     ///
     /// # Returns
     ///
-    /// * `Result<(), Error>` - The result of the operation.
+    /// * `Result<bool, Error>` - Whether the block is addressed with 48-bit LBA.
     ///
     /// # Errors
     ///
     /// * If the drive does not exist.
     /// * If the ATA times out.
-    fn setup_pio(&mut self, drive: u8, block: u32) -> Result<(), Error> {
+    /// * If the block is past the 48-bit limit.
+    fn setup_pio(&mut self, drive: u8, block: u64) -> Result<bool, Error> {
         self.select_drive(drive)?;
-        self.write_cmd_params(drive, block)?;
 
-        Ok(())
+        if let Ok(block) = u32::try_from(block) {
+            if u64::from(block) < LBA28_LIMIT {
+                self.write_cmd_params(drive, block)?;
+
+                return Ok(false);
+            }
+        }
+
+        self.write_cmd_params_lba48(drive, block)?;
+
+        Ok(true)
     }
 
     /// Writes to the bus.
@@ -512,16 +643,47 @@ impl Bus {
     /// * If the ATA write fails.
     /// * If the ATA returns an error.
     /// * If the chunk is not a valid u16.
-    fn write(&mut self, drive: u8, block: u32, buffer: &[u8]) -> Result<(), Error> {
-        self.setup_pio(drive, block)?;
-        self.write_cmd(Command::Write)?;
+    fn write(&mut self, drive: u8, block: u64, buffer: &[u8]) -> Result<(), Error> {
+        self.start(drive, block, true)?;
+        self.wait_for_data()?;
+        self.write_data(buffer)?;
 
+        self.check_write()
+    }
+
+    /// Writes the data of a block, once the drive is ready to receive it.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - The buffer to write from.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the chunk is not a valid u16.
+    fn write_data(&mut self, buffer: &[u8]) -> Result<(), Error> {
         for chunk in buffer.chunks(2) {
             let data = u16::from_le_bytes(chunk.try_into()?);
 
-            self.data.write(data)?;
+            self.data.write_u16(data)?;
         }
 
+        Ok(())
+    }
+
+    /// Checks that the drive accepted the written block.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the ATA returns an error.
+    fn check_write(&mut self) -> Result<(), Error> {
         if self.error()? {
             return Err(Error::Internal("ATA write error!".into()));
         }
@@ -529,11 +691,11 @@ impl Bus {
         Ok(())
     }
 
-    /// Writes a command to the bus.
+    /// Flushes the write cache of a drive.
     ///
     /// # Arguments
     ///
-    /// * `cmd` - The command to write.
+    /// * `drive` - The drive to flush.
     ///
     /// # Returns
     ///
@@ -543,18 +705,71 @@ impl Bus {
     ///
     /// * If the drive does not exist.
     /// * If the ATA times out.
-    fn write_cmd(&mut self, cmd: Command) -> Result<(), Error> {
-        self.command.write(cmd as u16)?;
+    /// * If the ATA returns an error.
+    fn flush(&mut self, drive: u8) -> Result<(), Error> {
+        self.select_drive(drive)?;
+        self.command.write_u8(Command::FlushCache as u8)?;
+
+        // Wait for 400 nanoseconds.
+        wait(400);
+
+        // Writing the cache back can take a while, but the deadline of `poll` leaves plenty of room.
+        self.poll(Status::Busy, false)?;
+        if self.error()? {
+            return Err(Error::ATA("ATA cache flush error!".into()));
+        }
+
+        Ok(())
+    }
+
+    /// Selects the drive and block, and issues the read or write command.
+    ///
+    /// # Arguments
+    ///
+    /// * `drive` - The drive to access.
+    /// * `block` - The block to access.
+    /// * `write` - Whether to write the block, rather than read it.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If PIO fails to setup for the given drive and block.
+    fn start(&mut self, drive: u8, block: u64, write: bool) -> Result<(), Error> {
+        let command = match (write, self.setup_pio(drive, block)?) {
+            (false, false) => Command::Read,
+            (false, true) => Command::ReadExt,
+            (true, false) => Command::Write,
+            (true, true) => Command::WriteExt,
+        };
+
+        self.command.write_u8(command as u8)?;
 
         // Wait for 400 nanoseconds.
         wait(400);
 
+        Ok(())
+    }
+
+    /// Waits for the drive to be ready to transfer data, after a command.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the drive does not exist.
+    /// * If the ATA times out.
+    fn wait_for_data(&mut self) -> Result<(), Error> {
         // Ignore first read (false positive).
-        self.status.read()?;
+        self.status.read_u8()?;
         self.clear_interrupt()?;
 
         // If drive does not exist.
-        if self.status.read()? == 0 {
+        if self.status.read_u8()? == 0 {
             return Err(Error::Internal("ATA drive does not exist!".into()));
         }
 
@@ -587,40 +802,118 @@ impl Bus {
         bytes[3].set_bit(6, lba);
         bytes[3].set_bit(7, true);
 
-        self.sector_count.write(1)?;
-        self.lba0.write(u16::from(bytes[0]))?;
-        self.lba1.write(u16::from(bytes[1]))?;
-        self.lba2.write(u16::from(bytes[2]))?;
-        self.drive.write(u16::from(bytes[3]))?;
+        self.sector_count.write_u8(1)?;
+        self.lba0.write_u8(bytes[0])?;
+        self.lba1.write_u8(bytes[1])?;
+        self.lba2.write_u8(bytes[2])?;
+        self.drive.write_u8(bytes[3])?;
+
+        Ok(())
+    }
+
+    /// Writes command parameters, with 48-bit LBA.
+    ///
+    /// The sector count and LBA registers are FIFOs of two bytes, so the high bytes are written before the low ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `drive` - The drive to write to.
+    /// * `block` - The block to write to.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the block is past the 48-bit limit.
+    /// * If the sector count register is read-only.
+    fn write_cmd_params_lba48(&mut self, drive: u8, block: u64) -> Result<(), Error> {
+        if block >= LBA48_LIMIT {
+            return Err(Error::Conversion(format!(
+                "Block {block} is past the 48-bit LBA limit!"
+            )));
+        }
+
+        let bytes = block.to_le_bytes();
+
+        // The high bytes of the sector count and the LBA.
+        self.sector_count.write_u8(0)?;
+        self.lba0.write_u8(bytes[3])?;
+        self.lba1.write_u8(bytes[4])?;
+        self.lba2.write_u8(bytes[5])?;
+
+        // The low bytes of the sector count and the LBA.
+        self.sector_count.write_u8(1)?;
+        self.lba0.write_u8(bytes[0])?;
+        self.lba1.write_u8(bytes[1])?;
+        self.lba2.write_u8(bytes[2])?;
+
+        // With 48-bit LBA, the drive register only holds the LBA bit and the drive.
+        self.drive.write_u8(0x40 | drive << 4)?;
 
         Ok(())
     }
 }
 
+/// Sets the time to wait for a drive before an operation fails.
+///
+/// # Arguments
+///
+/// * `ns` - The timeout, in nanoseconds.
+pub fn set_timeout(ns: u64) {
+    TIMEOUT_NS.store(ns, Ordering::Relaxed);
+}
+
 /// Initializes the ATA driver.
 pub fn init() {
     {
         let mut buses = BUSES.lock();
 
-        buses.push(Bus::new(0, 14, 0x1F0, 0x3F6));
-        buses.push(Bus::new(1, 15, 0x170, 0x376));
+        buses.push(Bus::new(0, IRQS[0], IO_BASES[0], 0x3F6));
+        buses.push(Bus::new(1, IRQS[1], IO_BASES[1], 0x376));
+    }
+
+    let handlers: [fn(); 2] = [|| handle_interrupt(0), || handle_interrupt(1)];
+    for (irq, handler) in IRQS.into_iter().zip(handlers) {
+        if let Err(why) = idt::set_interrupt_request_handler(irq, handler) {
+            println!("[WARN]: {why}");
+        }
     }
 
     for drive in list_drives() {
+        let kind = match drive.kind {
+            DriveKind::Ata => "ATA",
+            DriveKind::Atapi => "ATAPI, unsupported",
+        };
+
         println!(
-            "[INFO]: => ATA (Bus: {bus}, Disk: {disk})",
+            "[INFO]: => {kind} (Bus: {bus}, Disk: {disk})",
             bus = drive.bus,
             disk = drive.disk
         );
     }
 }
 
+/// The kind of a drive.
+///
+/// # Variants
+///
+/// * `Ata` - A hard disk, accessed with ATA commands.
+/// * `Atapi` - A packet device, like a CD-ROM drive, which can't be read yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DriveKind {
+    Ata,
+    Atapi,
+}
+
 /// Represents an ATA drive.
 ///
 /// # Fields
 ///
 /// * `bus` - The bus of the drive.
 /// * `disk` - The disk of the drive.
+/// * `kind` - The kind of the drive.
 ///
 /// * `block` - The block count of the drive.
 /// * `model` - The model of the drive.
@@ -629,8 +922,9 @@ pub fn init() {
 pub struct Drive {
     pub bus: u8,
     pub disk: u8,
+    pub kind: DriveKind,
 
-    block: u32,
+    block: u64,
     model: String,
     serial: String,
 }
@@ -640,9 +934,9 @@ impl Drive {
     ///
     /// # Returns
     ///
-    /// * `u32` - The block count of the drive.
+    /// * `u64` - The block count of the drive.
     #[must_use]
-    pub const fn block_count(&self) -> u32 {
+    pub const fn block_count(&self) -> u64 {
         self.block
     }
 
@@ -673,24 +967,59 @@ impl Drive {
         let mut buses = BUSES.lock();
 
         // Identify the drive.
-        let Ok(DeviceType::Ata(result)) = buses[bus as usize].identify_drive(disk) else {
-            return None;
+        let result = match buses[bus as usize].identify_drive(disk) {
+            Ok(DeviceType::Ata(result)) => result,
+            // Listed, so it's known why the drive can't be used.
+            Ok(DeviceType::Atapi) => {
+                return Some(Self {
+                    bus,
+                    disk,
+                    kind: DriveKind::Atapi,
+                    block: 0,
+                    model: String::new(),
+                    serial: String::new(),
+                })
+            }
+            _ => return None,
         };
 
         let buffer = result.map(u16::to_le_bytes).concat();
-        let block = u32::from_be_bytes(buffer[120..124].try_into().ok()?).rotate_left(16);
+
+        // Drives supporting 48-bit LBA report their full size separately, since it may not fit the 28-bit count.
+        let block = if result[83].get_bit(10) {
+            u64::from_le_bytes(buffer[200..208].try_into().ok()?)
+        } else {
+            u64::from(u32::from_le_bytes(buffer[120..124].try_into().ok()?))
+        };
         let model = String::from_utf8_lossy(&buffer[54..94]).trim().into();
         let serial = String::from_utf8_lossy(&buffer[20..40]).trim().into();
 
         Some(Self {
             bus,
             disk,
+            kind: DriveKind::Ata,
             block,
             model,
             serial,
         })
     }
 
+    /// Checks that the drive can be accessed with ATA commands.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the drive is an ATAPI drive.
+    fn check_supported(&self) -> Result<(), Error> {
+        match self.kind {
+            DriveKind::Ata => Ok(()),
+            DriveKind::Atapi => Err(Error::Internal("ATAPI not supported!".into())),
+        }
+    }
+
     /// Gets the formatted size of the drive.
     ///
     /// # Returns
@@ -701,7 +1030,7 @@ impl Drive {
     ///
     /// * If the block size is not a valid u32.
     fn formatted_size(&self) -> Result<(usize, String), Error> {
-        let count = self.block_count() as usize;
+        let count = usize::try_from(self.block_count())?;
         let size = self.block_size()? as usize;
 
         let bytes = size * count;
@@ -714,6 +1043,34 @@ impl Drive {
     }
 }
 
+impl BlockDevice for Drive {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn block_count(&self) -> u64 {
+        self.block
+    }
+
+    fn read_block(&mut self, lba: u64, buffer: &mut [u8]) -> Result<(), Error> {
+        self.check_supported()?;
+
+        read(self.bus, self.disk, lba, buffer)
+    }
+
+    fn write_block(&mut self, lba: u64, buffer: &[u8]) -> Result<(), Error> {
+        self.check_supported()?;
+
+        write(self.bus, self.disk, lba, buffer)
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        self.check_supported()?;
+
+        lock_idle(self.bus)?[self.bus as usize].flush(self.disk)
+    }
+}
+
 /// Lists the drives.
 ///
 /// # Returns
@@ -749,11 +1106,12 @@ pub fn list_drives() -> Vec<Drive> {
 /// # Errors
 ///
 /// * If the drive does not exist.
+/// * If the bus is busy with an asynchronous command.
 /// * If the ATA times out.
 /// * If the ATA read fails.
 /// * If the ATA returns an error.
-pub fn read(bus: u8, drive: u8, block: u32, buffer: &mut [u8]) -> Result<(), Error> {
-    let mut buses = BUSES.lock();
+pub fn read(bus: u8, drive: u8, block: u64, buffer: &mut [u8]) -> Result<(), Error> {
+    let mut buses = lock_idle(bus)?;
 
     buses[bus as usize].read(drive, block, buffer)
 }
@@ -774,11 +1132,344 @@ pub fn read(bus: u8, drive: u8, block: u32, buffer: &mut [u8]) -> Result<(), Err
 /// # Errors
 ///
 /// * If the drive does not exist.
+/// * If the bus is busy with an asynchronous command.
 /// * If the ATA times out.
 /// * If the ATA write fails.
 /// * If the ATA returns an error.
-pub fn write(bus: u8, drive: u8, block: u32, buffer: &[u8]) -> Result<(), Error> {
-    let mut buses = BUSES.lock();
+pub fn write(bus: u8, drive: u8, block: u64, buffer: &[u8]) -> Result<(), Error> {
+    let mut buses = lock_idle(bus)?;
 
     buses[bus as usize].write(drive, block, buffer)
 }
+
+/// Reads from a drive, waiting for the drive's interrupt instead of polling.
+///
+/// # Arguments
+///
+/// * `bus` - The bus of the drive.
+/// * `drive` - The drive to read from.
+/// * `block` - The block to read from.
+/// * `buffer` - The buffer to read into.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the drive does not exist.
+/// * If the bus is busy with another command.
+/// * If the ATA times out.
+/// * If the ATA read fails.
+///
+/// # Notes
+///
+/// * Falls back to polling while interrupts are disabled, like during early boot.
+/// * Only one command per bus may be in flight, since the bus is unlocked while waiting, so other commands on the bus
+///   fail until it's done.
+pub async fn read_async(bus: u8, drive: u8, block: u64, buffer: &mut [u8]) -> Result<(), Error> {
+    if !interrupts::are_enabled() {
+        return read(bus, drive, block, buffer);
+    }
+
+    let index = usize::from(bus);
+    let _in_flight = InFlight::acquire(index)?;
+    {
+        let mut buses = BUSES.lock();
+
+        IRQ_RECEIVED[index].store(false, Ordering::Relaxed);
+        buses[index].start(drive, block, false)?;
+    }
+
+    // The drive interrupts once the block is ready to be read.
+    Interrupt::new(index).await?;
+
+    let mut buses = BUSES.lock();
+    buses[index].wait_for_data()?;
+
+    buses[index].read_data(buffer)
+}
+
+/// Writes to a drive, waiting for the drive's interrupt instead of polling.
+///
+/// # Arguments
+///
+/// * `bus` - The bus of the drive.
+/// * `drive` - The drive to write to.
+/// * `block` - The block to write to.
+/// * `buffer` - The buffer to write from.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the drive does not exist.
+/// * If the bus is busy with another command.
+/// * If the ATA times out.
+/// * If the ATA returns an error.
+///
+/// # Notes
+///
+/// * Falls back to polling while interrupts are disabled, like during early boot.
+/// * Only one command per bus may be in flight, since the bus is unlocked while waiting, so other commands on the bus
+///   fail until it's done.
+pub async fn write_async(bus: u8, drive: u8, block: u64, buffer: &[u8]) -> Result<(), Error> {
+    if !interrupts::are_enabled() {
+        return write(bus, drive, block, buffer);
+    }
+
+    let index = usize::from(bus);
+    let _in_flight = InFlight::acquire(index)?;
+    {
+        let mut buses = BUSES.lock();
+
+        IRQ_RECEIVED[index].store(false, Ordering::Relaxed);
+        buses[index].start(drive, block, true)?;
+        buses[index].wait_for_data()?;
+        buses[index].write_data(buffer)?;
+    }
+
+    // The drive interrupts once it's done writing the block.
+    Interrupt::new(index).await?;
+
+    BUSES.lock()[index].check_write()
+}
+
+/// Locks the buses for a synchronous command on a bus.
+///
+/// # Arguments
+///
+/// * `bus` - The bus the command is for.
+///
+/// # Returns
+///
+/// * `Result<MutexGuard<'static, Vec<Bus>>, Error>` - The locked buses.
+///
+/// # Errors
+///
+/// * If the bus does not exist.
+/// * If the bus is busy with an asynchronous command, which a synchronous one would interfere with.
+///
+/// # Notes
+///
+/// * Fails instead of waiting, since the asynchronous command may belong to a task on the same executor.
+fn lock_idle(bus: u8) -> Result<MutexGuard<'static, Vec<Bus>>, Error> {
+    let in_flight = IN_FLIGHT
+        .get(usize::from(bus))
+        .ok_or_else(|| Error::ATA(format!("Bus {bus} does not exist!")))?;
+
+    let buses = BUSES.lock();
+    if in_flight.load(Ordering::Acquire) {
+        return Err(Error::ATA(format!(
+            "Bus {bus} is busy with another command!"
+        )));
+    }
+
+    Ok(buses)
+}
+
+/// Marks a bus as busy with an asynchronous command, until it's dropped.
+///
+/// # Fields
+///
+/// * `bus` - The bus.
+struct InFlight {
+    bus: usize,
+}
+
+impl InFlight {
+    /// Marks a bus as busy.
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - The bus.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The guard, which marks the bus as idle again once dropped.
+    ///
+    /// # Errors
+    ///
+    /// * If the bus does not exist.
+    /// * If the bus is already busy with another command.
+    fn acquire(bus: usize) -> Result<Self, Error> {
+        IN_FLIGHT
+            .get(bus)
+            .ok_or_else(|| Error::ATA(format!("Bus {bus} does not exist!")))?
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .map(|_| Self { bus })
+            .map_err(|_| Error::ATA(format!("Bus {bus} is busy with another command!")))
+    }
+}
+
+impl Drop for InFlight {
+    /// Marks the bus as idle again.
+    fn drop(&mut self) {
+        IN_FLIGHT[self.bus].store(false, Ordering::Release);
+    }
+}
+
+/// Handles an interrupt from a bus, called by its interrupt handler.
+///
+/// # Arguments
+///
+/// * `bus` - The bus that raised the interrupt.
+///
+/// # Notes
+///
+/// * Reads the status register straight from the port, since the bus may be locked by the interrupted code.
+pub(crate) fn handle_interrupt(bus: usize) {
+    // Reading the status acknowledges the interrupt.
+    let mut status: PortReadOnly<u8> = PortReadOnly::new(IO_BASES[bus] + 7);
+    unsafe { status.read() };
+
+    notify_interrupt(bus);
+}
+
+/// Marks that a bus raised an interrupt, and wakes the task waiting for it.
+///
+/// # Arguments
+///
+/// * `bus` - The bus that raised the interrupt.
+fn notify_interrupt(bus: usize) {
+    IRQ_RECEIVED[bus].store(true, Ordering::Release);
+    IRQ_WAKERS[bus].wake();
+}
+
+/// A future that resolves once a bus raises an interrupt, or the drive times out.
+///
+/// # Fields
+///
+/// * `bus` - The bus.
+/// * `timeout` - Completes once the drive has taken longer than the timeout set with [`set_timeout`].
+struct Interrupt {
+    bus: usize,
+    timeout: Sleep,
+}
+
+impl Interrupt {
+    /// Creates a new `Interrupt`, for the next interrupt of the given bus.
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - The bus.
+    #[allow(clippy::cast_precision_loss)]
+    fn new(bus: usize) -> Self {
+        Self {
+            bus,
+            timeout: timer::sleep(TIMEOUT_NS.load(Ordering::Relaxed) as f64 / 1_000_000_000.0),
+        }
+    }
+}
+
+impl Future for Interrupt {
+    type Output = Result<(), Error>;
+
+    /// Checks whether the bus raised an interrupt, or the drive timed out.
+    ///
+    /// # Arguments
+    ///
+    /// * `cx` - The context to use for polling.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<Result<(), Error>>` - Ready once the bus raised an interrupt, or with an error once the drive timed out.
+    ///
+    /// # Notes
+    ///
+    /// * The bus is reset on a timeout, so the abandoned command doesn't interfere with the next one.
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Result<(), Error>> {
+        if IRQ_RECEIVED[self.bus].swap(false, Ordering::Acquire) {
+            return Poll::Ready(Ok(()));
+        }
+
+        // Register first, so an interrupt between the check and the registration isn't missed.
+        IRQ_WAKERS[self.bus].register(cx.waker());
+        if IRQ_RECEIVED[self.bus].swap(false, Ordering::Acquire) {
+            IRQ_WAKERS[self.bus].take();
+
+            return Poll::Ready(Ok(()));
+        }
+
+        if Pin::new(&mut self.timeout).poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        IRQ_WAKERS[self.bus].take();
+        if let Some(bus) = BUSES.lock().get_mut(self.bus) {
+            let _ = bus.reset();
+        }
+
+        Poll::Ready(Err(Error::ATA(format!(
+            "Timed out waiting for an interrupt (Bus: {bus})!",
+            bus = self.bus
+        ))))
+    }
+}
+
+/// Tests that waiting for an interrupt resolves once the bus raises one.
+///
+/// # Panics
+///
+/// * If the future resolves before the interrupt.
+/// * If the future doesn't resolve after the interrupt.
+#[test_case]
+fn test_interrupt_future() {
+    use futures_util::task::noop_waker_ref;
+
+    let mut context = Context::from_waker(noop_waker_ref());
+    let mut interrupt = Interrupt {
+        bus: 1,
+        timeout: Sleep::until(usize::MAX),
+    };
+    IRQ_RECEIVED[1].store(false, Ordering::Relaxed);
+
+    assert!(Pin::new(&mut interrupt).poll(&mut context).is_pending());
+
+    notify_interrupt(1);
+    assert!(matches!(
+        Pin::new(&mut interrupt).poll(&mut context),
+        Poll::Ready(Ok(()))
+    ));
+}
+
+/// Tests that waiting for an interrupt fails once the drive times out.
+///
+/// # Panics
+///
+/// * If the future doesn't fail after the timeout.
+#[test_case]
+fn test_interrupt_timeout() {
+    use futures_util::task::noop_waker_ref;
+
+    let mut context = Context::from_waker(noop_waker_ref());
+    let mut interrupt = Interrupt {
+        bus: 1,
+        timeout: Sleep::until(0),
+    };
+    IRQ_RECEIVED[1].store(false, Ordering::Relaxed);
+
+    assert!(matches!(
+        Pin::new(&mut interrupt).poll(&mut context),
+        Poll::Ready(Err(Error::ATA(_)))
+    ));
+}
+
+/// Tests that synchronous commands fail while an asynchronous one is in flight on the bus.
+///
+/// # Panics
+///
+/// * If a second command can be started on a busy bus.
+/// * If the bus isn't idle again once the command is done.
+#[test_case]
+fn test_in_flight() {
+    let in_flight = InFlight::acquire(1);
+    assert!(in_flight.is_ok());
+    assert!(InFlight::acquire(1).is_err());
+    assert!(lock_idle(1).is_err());
+
+    drop(in_flight);
+    assert!(lock_idle(1).is_ok());
+}
@@ -0,0 +1,576 @@
+use alloc::boxed::Box;
+use alloc::format;
+use core::fmt;
+
+use bootloader::BootInfo;
+use conquer_once::spin::OnceCell;
+use spin::Mutex;
+
+use crate::errors::Error;
+use crate::vga_buffer::{self, Color, Font};
+
+/// The width of a character, in pixels.
+const GLYPH_WIDTH: usize = 8;
+/// The height of a character, in pixels.
+const GLYPH_HEIGHT: usize = 16;
+
+/// The framebuffer console, used by the `print!` and `println!` macros instead of the VGA text buffer once set up.
+pub static WRITER: OnceCell<Mutex<FramebufferWriter>> = OnceCell::uninit();
+
+/// The order of the color channels in a pixel.
+///
+/// # Variants
+///
+/// * `Rgb` - Red, green, then blue.
+/// * `Bgr` - Blue, green, then red.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Rgb,
+    Bgr,
+}
+
+/// The layout of a linear framebuffer.
+///
+/// # Fields
+///
+/// * `width` - The visible width, in pixels.
+/// * `height` - The visible height, in pixels.
+/// * `stride` - The distance between the starts of two lines, in pixels.
+/// * `bytes_per_pixel` - The size of a pixel, at least 3.
+/// * `format` - The order of the color channels in a pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FramebufferInfo {
+    pub width: usize,
+    pub height: usize,
+    pub stride: usize,
+    pub bytes_per_pixel: usize,
+    pub format: PixelFormat,
+}
+
+/// A 24-bit color.
+///
+/// # Fields
+///
+/// * `red` - The red channel.
+/// * `green` - The green channel.
+/// * `blue` - The blue channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Rgb {
+    /// Creates a new `Rgb`.
+    ///
+    /// # Arguments
+    ///
+    /// * `red` - The red channel.
+    /// * `green` - The green channel.
+    /// * `blue` - The blue channel.
+    #[must_use]
+    pub const fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+}
+
+impl From<Color> for Rgb {
+    /// Converts a VGA palette color, so the framebuffer console looks like the text mode one.
+    fn from(color: Color) -> Self {
+        match color {
+            Color::Black => Self::new(0x00, 0x00, 0x00),
+            Color::Blue => Self::new(0x00, 0x00, 0xAA),
+            Color::Green => Self::new(0x00, 0xAA, 0x00),
+            Color::Cyan => Self::new(0x00, 0xAA, 0xAA),
+            Color::Red => Self::new(0xAA, 0x00, 0x00),
+            Color::Magenta => Self::new(0xAA, 0x00, 0xAA),
+            Color::Brown => Self::new(0xAA, 0x55, 0x00),
+            Color::LightGray => Self::new(0xAA, 0xAA, 0xAA),
+            Color::DarkGray => Self::new(0x55, 0x55, 0x55),
+            Color::LightBlue => Self::new(0x55, 0x55, 0xFF),
+            Color::LightGreen => Self::new(0x55, 0xFF, 0x55),
+            Color::LightCyan => Self::new(0x55, 0xFF, 0xFF),
+            Color::LightRed => Self::new(0xFF, 0x55, 0x55),
+            Color::Pink => Self::new(0xFF, 0x55, 0xFF),
+            Color::Yellow => Self::new(0xFF, 0xFF, 0x55),
+            Color::White => Self::new(0xFF, 0xFF, 0xFF),
+        }
+    }
+}
+
+/// A linear framebuffer.
+///
+/// # Fields
+///
+/// * `buffer` - The pixels, line by line.
+/// * `info` - The layout of the pixels.
+pub struct Framebuffer {
+    buffer: &'static mut [u8],
+    info: FramebufferInfo,
+}
+
+impl Framebuffer {
+    /// Creates a new `Framebuffer`.
+    ///
+    /// # Arguments
+    ///
+    /// * `buffer` - The pixels, line by line.
+    /// * `info` - The layout of the pixels.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The framebuffer.
+    ///
+    /// # Errors
+    ///
+    /// * If a pixel is too small to hold a 24-bit color.
+    /// * If the buffer is too small for the layout.
+    pub fn new(buffer: &'static mut [u8], info: FramebufferInfo) -> Result<Self, Error> {
+        if info.bytes_per_pixel < 3 || info.stride < info.width {
+            return Err(Error::Internal(format!(
+                "Unsupported framebuffer layout {info:?}!"
+            )));
+        }
+
+        let size = info.stride * info.height * info.bytes_per_pixel;
+        if buffer.len() < size {
+            return Err(Error::Internal(format!(
+                "Framebuffer of {len} bytes is too small for {size} bytes of pixels!",
+                len = buffer.len()
+            )));
+        }
+
+        Ok(Self { buffer, info })
+    }
+
+    /// Gets the layout of the framebuffer.
+    ///
+    /// # Returns
+    ///
+    /// * `FramebufferInfo` - The layout.
+    #[must_use]
+    pub const fn info(&self) -> FramebufferInfo {
+        self.info
+    }
+
+    /// Sets the color of a pixel.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The column of the pixel.
+    /// * `y` - The line of the pixel.
+    /// * `color` - The color.
+    ///
+    /// # Notes
+    ///
+    /// * Pixels outside the visible area are ignored.
+    pub fn put_pixel(&mut self, x: usize, y: usize, color: Rgb) {
+        if x >= self.info.width || y >= self.info.height {
+            return;
+        }
+
+        let channels = match self.info.format {
+            PixelFormat::Rgb => [color.red, color.green, color.blue],
+            PixelFormat::Bgr => [color.blue, color.green, color.red],
+        };
+
+        let offset = (y * self.info.stride + x) * self.info.bytes_per_pixel;
+        for (byte, channel) in self.buffer[offset..offset + 3].iter_mut().zip(channels) {
+            // SAFETY: The byte is a valid reference into the buffer.
+            unsafe { core::ptr::write_volatile(byte, channel) };
+        }
+    }
+
+    /// Fills a rectangle with a color.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - The column of the top left corner.
+    /// * `y` - The line of the top left corner.
+    /// * `width` - The width, in pixels.
+    /// * `height` - The height, in pixels.
+    /// * `color` - The color.
+    ///
+    /// # Notes
+    ///
+    /// * The rectangle is clipped to the visible area.
+    pub fn fill_rect(&mut self, x: usize, y: usize, width: usize, height: usize, color: Rgb) {
+        let right = x.saturating_add(width).min(self.info.width);
+        let bottom = y.saturating_add(height).min(self.info.height);
+
+        for y in y..bottom {
+            for x in x..right {
+                self.put_pixel(x, y, color);
+            }
+        }
+    }
+
+    /// Moves the lines up, filling the freed lines at the bottom with a color.
+    ///
+    /// # Arguments
+    ///
+    /// * `lines` - The number of lines to move up by.
+    /// * `color` - The color of the freed lines.
+    fn scroll_up(&mut self, lines: usize, color: Rgb) {
+        let lines = lines.min(self.info.height);
+        let line_size = self.info.stride * self.info.bytes_per_pixel;
+
+        self.buffer
+            .copy_within(lines * line_size..self.info.height * line_size, 0);
+        self.fill_rect(0, self.info.height - lines, self.info.width, lines, color);
+    }
+}
+
+/// A text console on a framebuffer, drawing characters with a bitmap font.
+///
+/// Wraps lines at the right edge. Supports the `\n` newline and `\x08` backspace characters, like the VGA `Writer`.
+///
+/// # Fields
+///
+/// * `framebuffer` - The framebuffer to draw on.
+/// * `font` - The glyphs.
+/// * `row_position` - The current row position.
+/// * `column_position` - The current column position.
+/// * `foreground` - The color of the characters.
+/// * `background` - The color behind the characters.
+pub struct FramebufferWriter {
+    framebuffer: Framebuffer,
+    font: Box<Font>,
+    row_position: usize,
+    column_position: usize,
+    foreground: Rgb,
+    background: Rgb,
+}
+
+impl FramebufferWriter {
+    /// Creates a new `FramebufferWriter`, clearing the framebuffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `framebuffer` - The framebuffer to draw on, at least one character in size.
+    /// * `font` - The glyphs.
+    #[must_use]
+    pub fn new(framebuffer: Framebuffer, font: Box<Font>) -> Self {
+        let mut writer = Self {
+            framebuffer,
+            font,
+            row_position: 0,
+            column_position: 0,
+            foreground: Rgb::from(Color::White),
+            background: Rgb::from(Color::Black),
+        };
+
+        writer.clear();
+        writer
+    }
+
+    /// Gets the number of columns of characters.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of columns.
+    #[must_use]
+    pub const fn columns(&self) -> usize {
+        self.framebuffer.info.width / GLYPH_WIDTH
+    }
+
+    /// Gets the number of rows of characters.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of rows.
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.framebuffer.info.height / GLYPH_HEIGHT
+    }
+
+    /// Gets the position the next character is written at.
+    ///
+    /// # Returns
+    ///
+    /// * `(usize, usize)` - The row and the column.
+    #[must_use]
+    pub const fn position(&self) -> (usize, usize) {
+        (self.row_position, self.column_position)
+    }
+
+    /// Gets the color characters are written in.
+    ///
+    /// # Returns
+    ///
+    /// * `Rgb` - The foreground color.
+    #[must_use]
+    pub const fn foreground(&self) -> Rgb {
+        self.foreground
+    }
+
+    /// Sets the color characters are written in.
+    ///
+    /// # Arguments
+    ///
+    /// * `foreground` - The foreground color.
+    pub fn set_foreground(&mut self, foreground: Rgb) {
+        self.foreground = foreground;
+    }
+
+    /// Writes a byte, as a code page 437 character.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte` - The byte to write.
+    pub fn write_byte(&mut self, byte: u8) {
+        match byte {
+            b'\n' => self.new_line(),
+            b'\r' => self.column_position = 0,
+            0x08 => self.backspace(),
+            byte => {
+                if self.column_position >= self.columns() {
+                    self.new_line();
+                }
+
+                self.draw_glyph(self.row_position, self.column_position, byte);
+                self.column_position += 1;
+            }
+        }
+    }
+
+    /// Clears the framebuffer, moving the writer to the top left corner.
+    pub fn clear(&mut self) {
+        let info = self.framebuffer.info;
+        self.framebuffer
+            .fill_rect(0, 0, info.width, info.height, self.background);
+
+        self.row_position = 0;
+        self.column_position = 0;
+    }
+
+    /// Draws a character.
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - The row of the character.
+    /// * `column` - The column of the character.
+    /// * `byte` - The character.
+    fn draw_glyph(&mut self, row: usize, column: usize, byte: u8) {
+        let glyph = self.font[usize::from(byte)];
+        let (x, y) = (column * GLYPH_WIDTH, row * GLYPH_HEIGHT);
+
+        for (line, bits) in glyph.iter().enumerate() {
+            for pixel in 0..GLYPH_WIDTH {
+                let color = if bits & (0x80 >> pixel) == 0 {
+                    self.background
+                } else {
+                    self.foreground
+                };
+
+                self.framebuffer.put_pixel(x + pixel, y + line, color);
+            }
+        }
+    }
+
+    /// Moves to the next line.
+    ///
+    /// If already on the last row, the framebuffer is scrolled up one row.
+    fn new_line(&mut self) {
+        self.column_position = 0;
+
+        if self.row_position + 1 < self.rows() {
+            self.row_position += 1;
+
+            return;
+        }
+
+        self.framebuffer.scroll_up(GLYPH_HEIGHT, self.background);
+    }
+
+    /// Moves one column back and erases the character there.
+    ///
+    /// Does nothing at the start of a line.
+    fn backspace(&mut self) {
+        if self.column_position == 0 {
+            return;
+        }
+
+        self.column_position -= 1;
+        self.draw_glyph(self.row_position, self.column_position, b' ');
+    }
+}
+
+impl fmt::Write for FramebufferWriter {
+    /// Writes a string to the framebuffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The string to write.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - The result of the operation.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.chars().map(vga_buffer::to_cp437) {
+            match byte {
+                // Printable code page 437 byte, newline or backspace.
+                0x20..=0x7e | 0x80..=0xff | b'\n' | 0x08 => self.write_byte(byte),
+                // A control character it can't draw.
+                _ => self.write_byte(0xfe),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Gets the framebuffer the bootloader set up, to select the console at boot.
+///
+/// # Arguments
+///
+/// * `boot_info` - The boot information.
+///
+/// # Returns
+///
+/// * `Option<Framebuffer>` - The framebuffer, or `None` if the bootloader left the screen in VGA text mode.
+///
+/// # Notes
+///
+/// * `bootloader` 0.9 always hands over in VGA text mode, and its boot info has no framebuffer, so this is always
+///   `None` until the bootloader is upgraded.
+#[must_use]
+pub const fn from_boot_info(_boot_info: &BootInfo) -> Option<Framebuffer> {
+    None
+}
+
+/// Makes a framebuffer the console, so `print!` and `println!` draw on it instead of the VGA text buffer.
+///
+/// # Arguments
+///
+/// * `framebuffer` - The framebuffer to draw on.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the framebuffer is smaller than a character.
+/// * If the framebuffer console is already set up.
+///
+/// # Notes
+///
+/// * The glyphs are copied from the VGA font memory, so this must be called after `mem::init`.
+pub fn init(framebuffer: Framebuffer) -> Result<(), Error> {
+    let info = framebuffer.info();
+    if info.width < GLYPH_WIDTH || info.height < GLYPH_HEIGHT {
+        return Err(Error::Internal(format!(
+            "Framebuffer of {width}x{height} is smaller than a character!",
+            width = info.width,
+            height = info.height
+        )));
+    }
+
+    let writer = FramebufferWriter::new(framebuffer, vga_buffer::read_font());
+
+    WRITER
+        .try_init_once(|| Mutex::new(writer))
+        .map_err(|_| Error::Internal("Framebuffer console already initialized!".into()))
+}
+
+/// Creates a framebuffer in heap memory, for testing.
+///
+/// # Arguments
+///
+/// * `info` - The layout of the framebuffer.
+///
+/// # Returns
+///
+/// * `Framebuffer` - The framebuffer.
+///
+/// # Panics
+///
+/// * If the layout is unsupported.
+#[cfg(test)]
+#[allow(clippy::expect_used)]
+fn test_framebuffer(info: FramebufferInfo) -> Framebuffer {
+    use alloc::vec;
+
+    let buffer = vec![0; info.stride * info.height * info.bytes_per_pixel].leak();
+
+    Framebuffer::new(buffer, info).expect("Failed to create the framebuffer!")
+}
+
+/// Tests that pixels and rectangles are drawn in the pixel format, and clipped to the visible area.
+///
+/// # Panics
+///
+/// * If a pixel has the wrong bytes.
+/// * If a pixel outside the rectangle is drawn.
+#[test_case]
+fn test_fill_rect() {
+    let info = FramebufferInfo {
+        width: 4,
+        height: 4,
+        stride: 5,
+        bytes_per_pixel: 4,
+        format: PixelFormat::Bgr,
+    };
+    let mut framebuffer = test_framebuffer(info);
+    let color = Rgb::new(1, 2, 3);
+
+    framebuffer.put_pixel(0, 0, color);
+    assert_eq!(&framebuffer.buffer[0..4], &[3, 2, 1, 0]);
+
+    framebuffer.fill_rect(2, 2, 10, 10, color);
+    for y in 0..info.height {
+        for x in 0..info.width {
+            let offset = (y * info.stride + x) * info.bytes_per_pixel;
+            let filled = (x >= 2 && y >= 2) || (x, y) == (0, 0);
+
+            assert_eq!(framebuffer.buffer[offset] == 3, filled);
+        }
+    }
+
+    // The padding past the width is never drawn.
+    assert!(framebuffer.buffer[16..20].iter().all(|&byte| byte == 0));
+}
+
+/// Tests that characters are drawn from the font, and that the console scrolls at the last row.
+///
+/// # Panics
+///
+/// * If a pixel of a character doesn't match the font.
+/// * If the console doesn't scroll.
+#[test_case]
+fn test_framebuffer_writer() {
+    use core::fmt::Write;
+
+    let info = FramebufferInfo {
+        width: GLYPH_WIDTH * 4,
+        height: GLYPH_HEIGHT * 2,
+        stride: GLYPH_WIDTH * 4,
+        bytes_per_pixel: 3,
+        format: PixelFormat::Rgb,
+    };
+    let mut writer = FramebufferWriter::new(test_framebuffer(info), vga_buffer::read_font());
+    assert_eq!((writer.columns(), writer.rows()), (4, 2));
+
+    let pixel = |writer: &FramebufferWriter, x: usize, y: usize| {
+        writer.framebuffer.buffer[(y * info.stride + x) * info.bytes_per_pixel] != 0
+    };
+
+    // Drawn in the first row, then scrolled up to it by the second newline.
+    for text in ["A", "\nA\n"] {
+        writer
+            .write_str(text)
+            .expect("Failed to write to the framebuffer!");
+
+        let glyph = writer.font[usize::from(b'A')];
+        for (y, bits) in glyph.iter().enumerate() {
+            for x in 0..GLYPH_WIDTH {
+                assert_eq!(pixel(&writer, x, y), bits & (0x80 >> x) != 0);
+            }
+        }
+    }
+
+    assert_eq!(writer.row_position, 1);
+    assert!((0..GLYPH_HEIGHT).all(|y| !pixel(&writer, 0, GLYPH_HEIGHT + y)));
+}
@@ -1,9 +1,134 @@
+use alloc::format;
+
+use crate::errors::Error;
 use crate::println;
+use crate::util::crc32::crc32;
 
 pub mod ata;
+pub mod framebuffer;
+pub mod net;
+pub mod pci;
+pub mod ramdisk;
+
+/// A device that stores data in fixed size blocks, addressed by their logical block address.
+pub trait BlockDevice: Send {
+    /// Gets the size of a block.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The size of a block, in bytes.
+    fn block_size(&self) -> usize;
+
+    /// Gets the number of blocks.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The number of blocks.
+    fn block_count(&self) -> u64;
+
+    /// Reads a block.
+    ///
+    /// # Arguments
+    ///
+    /// * `lba` - The logical block address of the block.
+    /// * `buffer` - The buffer to read into, which must be exactly one block long.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the block doesn't exist.
+    /// * If the device fails to read the block.
+    fn read_block(&mut self, lba: u64, buffer: &mut [u8]) -> Result<(), Error>;
+
+    /// Writes a block.
+    ///
+    /// # Arguments
+    ///
+    /// * `lba` - The logical block address of the block.
+    /// * `buffer` - The buffer to write from, which must be exactly one block long.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the block doesn't exist.
+    /// * If the device fails to write the block.
+    fn write_block(&mut self, lba: u64, buffer: &[u8]) -> Result<(), Error>;
+
+    /// Flushes the blocks written so far from the device's caches to the medium.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the device fails to flush its caches.
+    ///
+    /// # Notes
+    ///
+    /// * Devices without caches, like RAM disks, have nothing to flush.
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Gets the stored checksum of a block.
+    ///
+    /// # Arguments
+    ///
+    /// * `lba` - The logical block address of the block.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<u32>` - The CRC-32 checksum of the block, if the device keeps one.
+    fn checksum(&self, _lba: u64) -> Option<u32> {
+        None
+    }
+
+    /// Reads a block, and verifies it against the stored checksum.
+    ///
+    /// # Arguments
+    ///
+    /// * `lba` - The logical block address of the block.
+    /// * `buffer` - The buffer to read into, which must be exactly one block long.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If reading the block fails.
+    /// * If the block doesn't match its stored checksum.
+    ///
+    /// # Notes
+    ///
+    /// * If the device doesn't keep checksums, this is the same as [`BlockDevice::read_block`].
+    fn read_block_checked(&mut self, lba: u64, buffer: &mut [u8]) -> Result<(), Error> {
+        self.read_block(lba, buffer)?;
+
+        match self.checksum(lba) {
+            Some(expected) if crc32(buffer) != expected => Err(Error::Integrity(format!(
+                "Block {lba} doesn't match its checksum!"
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
 
 /// Initializes the device drivers.
 pub fn init() {
     println!("[INFO]: Initializing the ATA driver...");
     ata::init();
+
+    println!("[INFO]: Scanning the PCI bus...");
+    pci::init();
+
+    // The network drivers find their cards through the PCI scan.
+    net::init();
 }
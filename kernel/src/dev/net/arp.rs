@@ -0,0 +1,311 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::dev::net::ethernet::{EtherType, EthernetFrame, MacAddr};
+use crate::dev::net::{self, Interface};
+use crate::errors::Error;
+use crate::sys::time::{self, clock};
+
+/// The size of an ARP packet for IPv4 over Ethernet, in bytes.
+const PACKET_SIZE: usize = 28;
+/// The hardware type of Ethernet.
+const HARDWARE_ETHERNET: u16 = 1;
+/// The protocol type of IPv4.
+const PROTOCOL_IPV4: u16 = 0x0800;
+
+/// The largest number of addresses kept in the cache.
+const CACHE_SIZE: usize = 32;
+/// The time to wait for a reply, in seconds.
+const TIMEOUT: f64 = 1.0;
+
+lazy_static! {
+    /// The cache mapping IPv4 addresses to MAC addresses.
+    static ref CACHE: Mutex<BTreeMap<Ipv4Addr, MacAddr>> = Mutex::new(BTreeMap::new());
+}
+
+/// The operation of an ARP packet.
+///
+/// # Variants
+///
+/// * `Request` - Asks who has the target IP address.
+/// * `Reply` - Answers a request with the MAC address of the sender.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Request = 1,
+    Reply = 2,
+}
+
+/// An ARP packet for IPv4 over Ethernet.
+///
+/// # Fields
+///
+/// * `operation` - The operation.
+/// * `sender_mac` - The MAC address of the sender.
+/// * `sender_ip` - The IP address of the sender.
+/// * `target_mac` - The MAC address of the target, which is zero in requests.
+/// * `target_ip` - The IP address of the target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ArpPacket {
+    pub operation: Operation,
+    pub sender_mac: MacAddr,
+    pub sender_ip: Ipv4Addr,
+    pub target_mac: MacAddr,
+    pub target_ip: Ipv4Addr,
+}
+
+impl ArpPacket {
+    /// Parses a packet.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The bytes of the packet.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The packet.
+    ///
+    /// # Errors
+    ///
+    /// * If the packet is too short.
+    /// * If the packet isn't for IPv4 over Ethernet.
+    /// * If the operation is unknown.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < PACKET_SIZE {
+            return Err(Error::Network(format!(
+                "ARP packet of {len} bytes is too short!",
+                len = bytes.len()
+            )));
+        }
+
+        let hardware = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let protocol = u16::from_be_bytes([bytes[2], bytes[3]]);
+        if hardware != HARDWARE_ETHERNET || protocol != PROTOCOL_IPV4 || bytes[4..6] != [6, 4] {
+            return Err(Error::Network(
+                "ARP packet isn't for IPv4 over Ethernet!".to_string(),
+            ));
+        }
+
+        let operation = match u16::from_be_bytes([bytes[6], bytes[7]]) {
+            1 => Operation::Request,
+            2 => Operation::Reply,
+            operation => {
+                return Err(Error::Network(format!(
+                    "Unknown ARP operation {operation}!"
+                )))
+            }
+        };
+
+        Ok(Self {
+            operation,
+            sender_mac: MacAddr(bytes[8..14].try_into()?),
+            sender_ip: Ipv4Addr::from(<[u8; 4]>::try_from(&bytes[14..18])?),
+            target_mac: MacAddr(bytes[18..24].try_into()?),
+            target_ip: Ipv4Addr::from(<[u8; 4]>::try_from(&bytes[24..28])?),
+        })
+    }
+
+    /// Builds the bytes of the packet.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<u8>` - The bytes of the packet.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(PACKET_SIZE);
+
+        bytes.extend_from_slice(&HARDWARE_ETHERNET.to_be_bytes());
+        bytes.extend_from_slice(&PROTOCOL_IPV4.to_be_bytes());
+        bytes.extend_from_slice(&[6, 4]);
+        bytes.extend_from_slice(&(self.operation as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.sender_mac.0);
+        bytes.extend_from_slice(&self.sender_ip.octets());
+        bytes.extend_from_slice(&self.target_mac.0);
+        bytes.extend_from_slice(&self.target_ip.octets());
+
+        bytes
+    }
+
+    /// Wraps the packet in an Ethernet frame.
+    ///
+    /// Requests are broadcast, replies are sent straight to the target.
+    ///
+    /// # Returns
+    ///
+    /// * `EthernetFrame` - The frame.
+    #[must_use]
+    pub fn to_frame(&self) -> EthernetFrame {
+        let destination = match self.operation {
+            Operation::Request => MacAddr::BROADCAST,
+            Operation::Reply => self.target_mac,
+        };
+
+        EthernetFrame {
+            destination,
+            source: self.sender_mac,
+            ether_type: EtherType::Arp,
+            payload: self.to_bytes(),
+        }
+    }
+}
+
+/// Looks up an IP address in the cache.
+///
+/// # Arguments
+///
+/// * `ip` - The IP address.
+///
+/// # Returns
+///
+/// * `Option<MacAddr>` - The MAC address, if it's cached.
+#[must_use]
+pub fn lookup(ip: Ipv4Addr) -> Option<MacAddr> {
+    CACHE.lock().get(&ip).copied()
+}
+
+/// Caches the MAC address of an IP address.
+///
+/// # Arguments
+///
+/// * `ip` - The IP address.
+/// * `mac` - The MAC address.
+///
+/// # Notes
+///
+/// * When the cache is full, the entry with the lowest IP address is evicted.
+pub fn insert(ip: Ipv4Addr, mac: MacAddr) {
+    let mut cache = CACHE.lock();
+    if cache.len() >= CACHE_SIZE && !cache.contains_key(&ip) {
+        cache.pop_first();
+    }
+
+    cache.insert(ip, mac);
+}
+
+/// Handles an ARP packet received on an interface.
+///
+/// Caches the sender, and replies to requests for the IP address of the interface.
+///
+/// # Arguments
+///
+/// * `interface` - The interface the packet was received on.
+/// * `payload` - The payload of the Ethernet frame.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the packet is malformed.
+/// * If sending the reply fails.
+pub fn handle(interface: &mut dyn Interface, payload: &[u8]) -> Result<(), Error> {
+    let packet = ArpPacket::parse(payload)?;
+    if !packet.sender_ip.is_unspecified() {
+        insert(packet.sender_ip, packet.sender_mac);
+    }
+
+    if packet.operation != Operation::Request || packet.target_ip != interface.ip_address() {
+        return Ok(());
+    }
+
+    let reply = ArpPacket {
+        operation: Operation::Reply,
+        sender_mac: interface.mac_address(),
+        sender_ip: interface.ip_address(),
+        target_mac: packet.sender_mac,
+        target_ip: packet.sender_ip,
+    };
+
+    interface.send(&reply.to_frame().to_bytes())
+}
+
+/// Broadcasts a request for the MAC address of an IP address.
+///
+/// # Arguments
+///
+/// * `interface` - The interface to send the request on.
+/// * `ip` - The IP address.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If sending the request fails.
+pub fn request(interface: &mut dyn Interface, ip: Ipv4Addr) -> Result<(), Error> {
+    let request = ArpPacket {
+        operation: Operation::Request,
+        sender_mac: interface.mac_address(),
+        sender_ip: interface.ip_address(),
+        target_mac: MacAddr::ZERO,
+        target_ip: ip,
+    };
+
+    interface.send(&request.to_frame().to_bytes())
+}
+
+/// Resolves the MAC address of an IP address.
+///
+/// Sends a request on every interface if the address isn't cached, and waits for a reply.
+///
+/// # Arguments
+///
+/// * `ip` - The IP address.
+///
+/// # Returns
+///
+/// * `Option<MacAddr>` - The MAC address, or `None` if no reply arrived in time.
+#[must_use]
+pub fn arp_resolve(ip: Ipv4Addr) -> Option<MacAddr> {
+    if let Some(mac) = lookup(ip) {
+        return Some(mac);
+    }
+
+    net::for_each_interface(|interface| {
+        // A failed request on one interface shouldn't stop the others.
+        let _ = request(interface, ip);
+    });
+
+    let start = clock::uptime();
+    while clock::uptime() - start < TIMEOUT {
+        net::poll();
+        if let Some(mac) = lookup(ip) {
+            return Some(mac);
+        }
+
+        time::halt();
+    }
+
+    None
+}
+
+/// Tests that a built packet parses back to the same packet.
+///
+/// # Panics
+///
+/// * If parsing the packet fails.
+/// * If the parsed packet doesn't match the built one.
+#[test_case]
+fn test_packet_round_trip() {
+    let packet = ArpPacket {
+        operation: Operation::Request,
+        sender_mac: MacAddr([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]),
+        sender_ip: Ipv4Addr::new(10, 0, 2, 15),
+        target_mac: MacAddr::ZERO,
+        target_ip: Ipv4Addr::new(10, 0, 2, 2),
+    };
+
+    let bytes = packet.to_bytes();
+    assert_eq!(bytes.len(), PACKET_SIZE);
+    assert_eq!(
+        ArpPacket::parse(&bytes).expect("Failed to parse the packet!"),
+        packet
+    );
+    assert_eq!(packet.to_frame().destination, MacAddr::BROADCAST);
+}
@@ -0,0 +1,169 @@
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::errors::Error;
+
+/// The size of the Ethernet header, in bytes.
+pub const HEADER_SIZE: usize = 14;
+
+/// A MAC address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+    /// The broadcast address, which every interface on the network accepts.
+    pub const BROADCAST: Self = Self([0xFF; 6]);
+    /// The unspecified address.
+    pub const ZERO: Self = Self([0x00; 6]);
+
+    /// Gets the bytes of the address.
+    ///
+    /// # Returns
+    ///
+    /// * `[u8; 6]` - The bytes, in network order.
+    #[must_use]
+    pub const fn octets(&self) -> [u8; 6] {
+        self.0
+    }
+}
+
+impl fmt::Display for MacAddr {
+    /// Formats the address as six colon separated hexadecimal bytes.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - The result of the operation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bytes = self.0.map(|byte| format!("{byte:02X}"));
+
+        write!(f, "{bytes}", bytes = bytes.join(":"))
+    }
+}
+
+/// The protocol of the payload of a frame.
+///
+/// # Variants
+///
+/// * `Ipv4` - An IPv4 packet.
+/// * `Arp` - An ARP packet.
+/// * `Unknown` - Any other protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EtherType {
+    Ipv4,
+    Arp,
+    Unknown(u16),
+}
+
+impl From<u16> for EtherType {
+    fn from(value: u16) -> Self {
+        match value {
+            0x0800 => Self::Ipv4,
+            0x0806 => Self::Arp,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<EtherType> for u16 {
+    fn from(value: EtherType) -> Self {
+        match value {
+            EtherType::Ipv4 => 0x0800,
+            EtherType::Arp => 0x0806,
+            EtherType::Unknown(value) => value,
+        }
+    }
+}
+
+/// An Ethernet frame, without the preamble and CRC.
+///
+/// # Fields
+///
+/// * `destination` - The MAC address of the receiver.
+/// * `source` - The MAC address of the sender.
+/// * `ether_type` - The protocol of the payload.
+/// * `payload` - The payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EthernetFrame {
+    pub destination: MacAddr,
+    pub source: MacAddr,
+    pub ether_type: EtherType,
+    pub payload: Vec<u8>,
+}
+
+impl EthernetFrame {
+    /// Parses a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The bytes of the frame.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The frame.
+    ///
+    /// # Errors
+    ///
+    /// * If the frame is shorter than the Ethernet header.
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(Error::Network(format!(
+                "Frame of {len} bytes is shorter than the Ethernet header!",
+                len = bytes.len()
+            )));
+        }
+
+        Ok(Self {
+            destination: MacAddr(bytes[0..6].try_into()?),
+            source: MacAddr(bytes[6..12].try_into()?),
+            ether_type: EtherType::from(u16::from_be_bytes([bytes[12], bytes[13]])),
+            payload: bytes[HEADER_SIZE..].to_vec(),
+        })
+    }
+
+    /// Builds the bytes of the frame.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<u8>` - The bytes of the frame.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.payload.len());
+
+        bytes.extend_from_slice(&self.destination.0);
+        bytes.extend_from_slice(&self.source.0);
+        bytes.extend_from_slice(&u16::from(self.ether_type).to_be_bytes());
+        bytes.extend_from_slice(&self.payload);
+
+        bytes
+    }
+}
+
+/// Tests that a built frame parses back to the same frame.
+///
+/// # Panics
+///
+/// * If parsing the frame fails.
+/// * If the parsed frame doesn't match the built one.
+#[test_case]
+fn test_frame_round_trip() {
+    let frame = EthernetFrame {
+        destination: MacAddr::BROADCAST,
+        source: MacAddr([0x52, 0x54, 0x00, 0x12, 0x34, 0x56]),
+        ether_type: EtherType::Arp,
+        payload: Vec::from(*b"payload"),
+    };
+
+    let bytes = frame.to_bytes();
+    assert_eq!(&bytes[12..14], &[0x08, 0x06]);
+    assert_eq!(
+        EthernetFrame::parse(&bytes).expect("Failed to parse the frame!"),
+        frame
+    );
+
+    assert!(EthernetFrame::parse(&bytes[..HEADER_SIZE - 1]).is_err());
+}
@@ -0,0 +1,95 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+
+use crate::dev::net::ethernet::MacAddr;
+use crate::dev::net::Interface;
+use crate::errors::Error;
+
+/// A network interface that receives every frame it sends.
+///
+/// Used to exercise the network stack without a network card.
+///
+/// # Fields
+///
+/// * `queue` - The frames sent but not yet received.
+#[derive(Debug, Default)]
+pub struct Loopback {
+    queue: VecDeque<Vec<u8>>,
+}
+
+impl Loopback {
+    /// Creates a new loopback interface.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The interface.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Interface for Loopback {
+    fn name(&self) -> &'static str {
+        "lo"
+    }
+
+    fn mac_address(&self) -> MacAddr {
+        MacAddr::ZERO
+    }
+
+    fn ip_address(&self) -> Ipv4Addr {
+        Ipv4Addr::LOCALHOST
+    }
+
+    fn send(&mut self, frame: &[u8]) -> Result<(), Error> {
+        self.queue.push_back(frame.to_vec());
+
+        Ok(())
+    }
+
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        self.queue.pop_front()
+    }
+}
+
+/// Tests that sent frames are received in order.
+///
+/// # Panics
+///
+/// * If sending a frame fails.
+/// * If the frames aren't received in the order they were sent.
+#[test_case]
+fn test_loopback_order() {
+    let mut lo = Loopback::new();
+
+    lo.send(b"first").expect("Failed to send the first frame!");
+    lo.send(b"second")
+        .expect("Failed to send the second frame!");
+
+    assert_eq!(lo.receive().as_deref(), Some(&b"first"[..]));
+    assert_eq!(lo.receive().as_deref(), Some(&b"second"[..]));
+    assert_eq!(lo.receive(), None);
+}
+
+/// Tests that an ARP request for the loopback address is answered by the interface itself.
+///
+/// # Panics
+///
+/// * If sending the request fails.
+/// * If the reply doesn't end up in the ARP cache.
+#[test_case]
+fn test_loopback_arp() {
+    use crate::dev::net::{self, arp};
+
+    let mut lo = Loopback::new();
+    arp::request(&mut lo, Ipv4Addr::LOCALHOST).expect("Failed to send the ARP request!");
+
+    // The request is answered, and the reply received, in the same poll.
+    net::poll_interface(&mut lo);
+
+    assert_eq!(arp::lookup(Ipv4Addr::LOCALHOST), Some(MacAddr::ZERO));
+}
@@ -0,0 +1,135 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+use lazy_static::lazy_static;
+use spin::Mutex;
+
+use crate::dev::net::ethernet::{EtherType, EthernetFrame, MacAddr};
+use crate::dev::net::loopback::Loopback;
+use crate::errors::Error;
+
+pub mod arp;
+pub mod ethernet;
+pub mod loopback;
+#[cfg(feature = "rtl8139")]
+pub mod rtl8139;
+
+lazy_static! {
+    /// The registered network interfaces.
+    static ref INTERFACES: Mutex<Vec<Box<dyn Interface>>> = Mutex::new(Vec::new());
+}
+
+/// A network interface, which sends and receives Ethernet frames.
+pub trait Interface: Send {
+    /// Gets the name of the interface.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name, e.g. `eth0`.
+    fn name(&self) -> &'static str;
+
+    /// Gets the MAC address of the interface.
+    ///
+    /// # Returns
+    ///
+    /// * `MacAddr` - The MAC address.
+    fn mac_address(&self) -> MacAddr;
+
+    /// Gets the IP address of the interface.
+    ///
+    /// # Returns
+    ///
+    /// * `Ipv4Addr` - The IP address.
+    fn ip_address(&self) -> Ipv4Addr;
+
+    /// Sends a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The Ethernet frame, without the CRC.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the interface fails to send the frame.
+    fn send(&mut self, frame: &[u8]) -> Result<(), Error>;
+
+    /// Takes the next received frame.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vec<u8>>` - The Ethernet frame without the CRC, or `None` if there's none.
+    fn receive(&mut self) -> Option<Vec<u8>>;
+}
+
+/// Registers a network interface.
+///
+/// # Arguments
+///
+/// * `interface` - The interface.
+pub fn register(interface: Box<dyn Interface>) {
+    INTERFACES.lock().push(interface);
+}
+
+/// Runs a function on every registered interface.
+///
+/// # Arguments
+///
+/// * `f` - The function to run.
+pub fn for_each_interface(mut f: impl FnMut(&mut dyn Interface)) {
+    for interface in INTERFACES.lock().iter_mut() {
+        f(interface.as_mut());
+    }
+}
+
+/// Receives the pending frames of every interface, and dispatches them by their ether type.
+pub fn poll() {
+    for_each_interface(poll_interface);
+}
+
+/// Receives the pending frames of an interface, and dispatches them by their ether type.
+///
+/// # Arguments
+///
+/// * `interface` - The interface.
+pub fn poll_interface(interface: &mut dyn Interface) {
+    while let Some(bytes) = interface.receive() {
+        // Malformed frames are dropped.
+        let Ok(frame) = EthernetFrame::parse(&bytes) else {
+            continue;
+        };
+
+        if frame.destination != interface.mac_address() && frame.destination != MacAddr::BROADCAST {
+            continue;
+        }
+
+        match frame.ether_type {
+            EtherType::Arp => {
+                let _ = arp::handle(interface, &frame.payload);
+            }
+            EtherType::Ipv4 | EtherType::Unknown(_) => {}
+        }
+    }
+}
+
+/// Initializes the network drivers.
+///
+/// # Notes
+///
+/// * Without any network driver features enabled, only the loopback interface is registered.
+pub fn init() {
+    register(Box::new(Loopback::new()));
+
+    #[cfg(feature = "rtl8139")]
+    {
+        use crate::println;
+
+        println!("[INFO]: Initializing the RTL8139 driver...");
+        if let Err(err) = rtl8139::init() {
+            println!("[WARN]: Failed to initialize the RTL8139 driver: {err}");
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
+use core::sync::atomic::{AtomicU16, Ordering};
+use x86_64::instructions::port::Port;
+use x86_64::VirtAddr;
+
+use crate::dev::net::ethernet::MacAddr;
+use crate::dev::net::{self, Interface};
+use crate::dev::pci::{self, PciDevice};
+use crate::errors::Error;
+use crate::mem::{self, PHYSICAL_MEMORY_OFFSET};
+use crate::println;
+use crate::sys::idt;
+
+/// The vendor ID of Realtek.
+const VENDOR_ID: u16 = 0x10EC;
+/// The device ID of the RTL8139.
+const DEVICE_ID: u16 = 0x8139;
+
+/// The IP address QEMU's user networking hands out, since there's no DHCP client yet.
+const IP_ADDRESS: Ipv4Addr = Ipv4Addr::new(10, 0, 2, 15);
+
+/// The size of the receive ring, without the padding needed for wrapping.
+const RX_RING_SIZE: usize = 8 * 1024;
+/// The size of the receive buffer, which leaves room for a full frame past the end of the ring.
+const RX_BUFFER_SIZE: usize = RX_RING_SIZE + 16 + 1500;
+/// The size of a transmit buffer, which is the largest frame the card can send.
+const TX_BUFFER_SIZE: usize = 1792;
+/// The number of transmit descriptors.
+const TX_DESCRIPTORS: usize = 4;
+/// The smallest frame the card will send, without the CRC.
+const MIN_FRAME_SIZE: usize = 60;
+
+/// The MAC address registers.
+const REG_MAC: u16 = 0x00;
+/// The transmit status registers, one per descriptor.
+const REG_TSD: u16 = 0x10;
+/// The transmit start address registers, one per descriptor.
+const REG_TSAD: u16 = 0x20;
+/// The receive buffer start address register.
+const REG_RBSTART: u16 = 0x30;
+/// The command register.
+const REG_CMD: u16 = 0x37;
+/// The current address of packet read register.
+const REG_CAPR: u16 = 0x38;
+/// The interrupt mask register.
+const REG_IMR: u16 = 0x3C;
+/// The interrupt status register.
+const REG_ISR: u16 = 0x3E;
+/// The receive configuration register.
+const REG_RCR: u16 = 0x44;
+/// The configuration register used to power the card on.
+const REG_CONFIG_1: u16 = 0x52;
+
+/// The command bit that resets the card.
+const CMD_RESET: u8 = 0x10;
+/// The command bit that enables the receiver.
+const CMD_RX_ENABLE: u8 = 0x08;
+/// The command bit that enables the transmitter.
+const CMD_TX_ENABLE: u8 = 0x04;
+/// The command bit that's set while the receive ring is empty.
+const CMD_RX_EMPTY: u8 = 0x01;
+
+/// The interrupt bit for a received frame.
+const INT_RX_OK: u16 = 0x01;
+/// The interrupt bit for a sent frame.
+const INT_TX_OK: u16 = 0x04;
+
+/// Accept all, physical match, multicast and broadcast frames, and let frames run past the end
+/// of the ring instead of wrapping them.
+const RCR_CONFIG: u32 = 0x0F | 1 << 7;
+
+/// The transmit status bit that's set once the card is done with the descriptor.
+const TSD_OWN: u32 = 1 << 13;
+/// The receive status bit of a frame that was received without errors.
+const RX_STATUS_OK: u16 = 0x01;
+
+/// The I/O port base of the card, used by the interrupt handler, or zero if there's no card.
+static IO_BASE: AtomicU16 = AtomicU16::new(0);
+
+/// The receive buffer, aligned to a page so it spans as few pages as possible.
+#[repr(C, align(4096))]
+struct RxBuffer([u8; RX_BUFFER_SIZE]);
+
+/// A transmit buffer, aligned so it never crosses a page boundary.
+#[repr(C, align(2048))]
+struct TxBuffer([u8; TX_BUFFER_SIZE]);
+
+/// A driver for the Realtek RTL8139 network card.
+///
+/// # Fields
+///
+/// * `io_base` - The base of the I/O ports of the card.
+/// * `mac_address` - The MAC address of the card.
+///
+/// * `rx_buffer` - The receive ring, which the card writes frames into.
+/// * `rx_offset` - The offset of the next frame in the receive ring.
+///
+/// * `tx_buffers` - The transmit buffers, one per descriptor.
+/// * `tx_index` - The index of the next transmit descriptor.
+pub struct Rtl8139 {
+    io_base: u16,
+    mac_address: MacAddr,
+
+    rx_buffer: Box<RxBuffer>,
+    rx_offset: usize,
+
+    tx_buffers: Box<[TxBuffer; TX_DESCRIPTORS]>,
+    tx_index: usize,
+}
+
+impl Rtl8139 {
+    /// Brings up the card.
+    ///
+    /// Enables bus mastering, powers the card on, resets it and starts the receiver and transmitter.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The PCI device of the card.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The driver.
+    ///
+    /// # Errors
+    ///
+    /// * If BAR0 isn't an I/O port base.
+    /// * If the buffers aren't usable for DMA.
+    pub fn new(device: &PciDevice) -> Result<Self, Error> {
+        // Enable I/O space access and bus mastering, so the card can DMA into memory.
+        let command = device.read_config(0x04);
+        device.write_config(0x04, command | 0x05);
+
+        let bar = device.read_config(0x10);
+        if bar & 0x01 == 0 {
+            return Err(Error::Network(
+                "BAR0 of the RTL8139 isn't an I/O port base!".to_string(),
+            ));
+        }
+
+        let mut driver = Self {
+            io_base: u16::try_from(bar & !0x03)?,
+            mac_address: MacAddr::ZERO,
+
+            rx_buffer: Box::new(RxBuffer([0; RX_BUFFER_SIZE])),
+            rx_offset: 0,
+
+            tx_buffers: Box::new([(); TX_DESCRIPTORS].map(|()| TxBuffer([0; TX_BUFFER_SIZE]))),
+            tx_index: 0,
+        };
+
+        // Power on the card, then reset it and wait for the reset to finish.
+        driver.write_u8(REG_CONFIG_1, 0x00);
+        driver.write_u8(REG_CMD, CMD_RESET);
+        while driver.read_u8(REG_CMD) & CMD_RESET != 0 {
+            core::hint::spin_loop();
+        }
+
+        for (offset, byte) in (REG_MAC..).zip(driver.mac_address.0.iter_mut()) {
+            *byte = unsafe { Port::new(driver.io_base + offset).read() };
+        }
+
+        let rx_address = dma_address(&driver.rx_buffer.0)?;
+        driver.write_u32(REG_RBSTART, rx_address);
+
+        for index in 0..TX_DESCRIPTORS {
+            let tx_address = dma_address(&driver.tx_buffers[index].0)?;
+            let register = REG_TSAD + u16::try_from(index * 4)?;
+
+            driver.write_u32(register, tx_address);
+        }
+
+        driver.write_u16(REG_IMR, INT_RX_OK | INT_TX_OK);
+        driver.write_u32(REG_RCR, RCR_CONFIG);
+        driver.write_u8(REG_CMD, CMD_RX_ENABLE | CMD_TX_ENABLE);
+
+        Ok(driver)
+    }
+
+    /// Reads a byte register.
+    fn read_u8(&self, register: u16) -> u8 {
+        unsafe { Port::new(self.io_base + register).read() }
+    }
+
+    /// Reads a 32-bit register.
+    fn read_u32(&self, register: u16) -> u32 {
+        unsafe { Port::new(self.io_base + register).read() }
+    }
+
+    /// Writes a byte register.
+    fn write_u8(&mut self, register: u16, value: u8) {
+        unsafe { Port::new(self.io_base + register).write(value) };
+    }
+
+    /// Writes a 16-bit register.
+    fn write_u16(&mut self, register: u16, value: u16) {
+        unsafe { Port::new(self.io_base + register).write(value) };
+    }
+
+    /// Writes a 32-bit register.
+    fn write_u32(&mut self, register: u16, value: u32) {
+        unsafe { Port::new(self.io_base + register).write(value) };
+    }
+}
+
+impl Interface for Rtl8139 {
+    fn name(&self) -> &'static str {
+        "eth0"
+    }
+
+    fn mac_address(&self) -> MacAddr {
+        self.mac_address
+    }
+
+    fn ip_address(&self) -> Ipv4Addr {
+        IP_ADDRESS
+    }
+
+    /// Sends a frame.
+    ///
+    /// Frames shorter than the minimum Ethernet frame size are padded with zeroes.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The Ethernet frame, without the CRC.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the frame is too large.
+    /// * If the next transmit descriptor is still busy.
+    fn send(&mut self, frame: &[u8]) -> Result<(), Error> {
+        if frame.len() > TX_BUFFER_SIZE {
+            return Err(Error::Network(format!(
+                "Frame of {len} bytes is too large!",
+                len = frame.len()
+            )));
+        }
+
+        // Each descriptor has a 4 byte wide status register.
+        let status = REG_TSD + u16::try_from(self.tx_index * 4)?;
+        if self.read_u32(status) & TSD_OWN == 0 {
+            return Err(Error::Network(
+                "The transmit descriptor is busy!".to_string(),
+            ));
+        }
+
+        let buffer = &mut self.tx_buffers[self.tx_index].0;
+        buffer[..frame.len()].copy_from_slice(frame);
+
+        let len = frame.len().max(MIN_FRAME_SIZE);
+        buffer[frame.len()..len].fill(0);
+
+        // Writing the size clears the own bit, which starts the transmission.
+        self.write_u32(status, u32::try_from(len)?);
+        self.tx_index = (self.tx_index + 1) % TX_DESCRIPTORS;
+
+        Ok(())
+    }
+
+    /// Takes the next frame from the receive ring.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vec<u8>>` - The Ethernet frame without the CRC, or `None` if there's none.
+    fn receive(&mut self) -> Option<Vec<u8>> {
+        loop {
+            if self.read_u8(REG_CMD) & CMD_RX_EMPTY != 0 {
+                return None;
+            }
+
+            // Every frame is preceded by a 16-bit status and a 16-bit length, which includes the CRC.
+            let offset = self.rx_offset;
+            let [status_low, status_high, len_low, len_high] =
+                *self.rx_buffer.0[offset..].first_chunk::<4>()?;
+            let status = u16::from_le_bytes([status_low, status_high]);
+            let len = usize::from(u16::from_le_bytes([len_low, len_high]));
+
+            // Frames are dword aligned in the ring, and the card lags the read pointer by 16 bytes.
+            self.rx_offset = ((offset + 4 + len + 3) & !0x03) % RX_RING_SIZE;
+            let capr = u16::try_from(self.rx_offset).ok()?.wrapping_sub(16);
+            self.write_u16(REG_CAPR, capr);
+
+            if status & RX_STATUS_OK != 0 && len >= 4 {
+                let start = offset + 4;
+
+                return self
+                    .rx_buffer
+                    .0
+                    .get(start..start + len - 4)
+                    .map(<[u8]>::to_vec);
+            }
+        }
+    }
+}
+
+/// Gets the physical address the card should use to access a buffer.
+///
+/// # Arguments
+///
+/// * `buffer` - The buffer.
+///
+/// # Returns
+///
+/// * `Result<u32, Error>` - The physical address of the start of the buffer.
+///
+/// # Errors
+///
+/// * If the buffer isn't mapped.
+/// * If the buffer isn't physically contiguous.
+/// * If the buffer lies above 4 GiB, which the card can't address.
+fn dma_address(buffer: &[u8]) -> Result<u32, Error> {
+    let translate = |addr: VirtAddr| unsafe {
+        mem::translate_addr(addr, VirtAddr::new(PHYSICAL_MEMORY_OFFSET))
+            .ok_or_else(|| Error::Network(format!("Buffer at {addr:?} isn't mapped!")))
+    };
+
+    let start = VirtAddr::from_ptr(buffer.as_ptr());
+    let physical_start = translate(start)?;
+
+    // Every page of the buffer must follow the previous one in physical memory too.
+    let mut page = start.align_down(4096u64) + 4096u64;
+    while page < start + buffer.len() {
+        if translate(page)? != physical_start + (page - start) {
+            return Err(Error::Network(
+                "Buffer isn't physically contiguous!".to_string(),
+            ));
+        }
+
+        page += 4096u64;
+    }
+
+    Ok(u32::try_from(physical_start.as_u64())?)
+}
+
+/// Finds the card on the PCI bus and brings it up.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If no card was found.
+/// * If the card fails to initialize.
+pub fn init() -> Result<(), Error> {
+    let device = pci::devices()
+        .into_iter()
+        .find(|device| device.vendor_id == VENDOR_ID && device.device_id == DEVICE_ID)
+        .ok_or_else(|| Error::Network("No RTL8139 found on the PCI bus!".to_string()))?;
+
+    let driver = Rtl8139::new(&device)?;
+    println!("[INFO]: => MAC {mac}", mac = driver.mac_address);
+
+    IO_BASE.store(driver.io_base, Ordering::Relaxed);
+    net::register(Box::new(driver));
+
+    // The interrupt line is the low byte of the register at 0x3C.
+    let [line, ..] = device.read_config(0x3C).to_le_bytes();
+    if let Err(why) = idt::set_interrupt_request_handler(line, handle_interrupt) {
+        println!("[WARN]: The RTL8139 can't use IRQ {line}, so it must be polled: {why}");
+    }
+
+    Ok(())
+}
+
+/// Handles an interrupt from the card, by acknowledging it.
+///
+/// # Notes
+///
+/// * Frames are left in the receive ring, since receiving is poll based.
+pub fn handle_interrupt() {
+    let io_base = IO_BASE.load(Ordering::Relaxed);
+    if io_base == 0 {
+        return;
+    }
+
+    let mut isr: Port<u16> = Port::new(io_base + REG_ISR);
+    unsafe {
+        // Interrupts are acknowledged by writing their bits back.
+        let status = isr.read();
+        isr.write(status);
+    }
+}
@@ -0,0 +1,274 @@
+use alloc::vec::Vec;
+use lazy_static::lazy_static;
+use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+use crate::println;
+
+/// The port used to select a configuration space register.
+const CONFIG_ADDRESS: u16 = 0xCF8;
+/// The port used to access the selected configuration space register.
+const CONFIG_DATA: u16 = 0xCFC;
+
+/// The vendor ID read back when no device is present.
+const NO_VENDOR: u16 = 0xFFFF;
+
+/// The number of devices on a bus.
+const DEVICES_PER_BUS: u8 = 32;
+/// The number of functions of a multi-function device.
+const FUNCTIONS_PER_DEVICE: u8 = 8;
+
+lazy_static! {
+    /// The devices found during enumeration.
+    pub static ref DEVICES: Mutex<Vec<PciDevice>> = Mutex::new(Vec::new());
+}
+
+/// A function of a device on the PCI bus.
+///
+/// # Fields
+///
+/// * `bus` - The bus of the device.
+/// * `device` - The device number on the bus.
+/// * `function` - The function number of the device.
+///
+/// * `vendor_id` - The vendor ID.
+/// * `device_id` - The device ID.
+/// * `class` - The class code.
+/// * `subclass` - The subclass code.
+/// * `prog_if` - The programming interface.
+/// * `header_type` - The header type, with the multi-function bit masked off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PciDevice {
+    pub bus: u8,
+    pub device: u8,
+    pub function: u8,
+
+    pub vendor_id: u16,
+    pub device_id: u16,
+    pub class: u8,
+    pub subclass: u8,
+    pub prog_if: u8,
+    pub header_type: u8,
+}
+
+impl PciDevice {
+    /// Reads the device at the given address.
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - The bus of the device.
+    /// * `device` - The device number on the bus.
+    /// * `function` - The function number of the device.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Self>` - The device, if one is present.
+    #[must_use]
+    pub fn read(bus: u8, device: u8, function: u8) -> Option<Self> {
+        let [vendor_low, vendor_high, device_low, device_high] =
+            read_config(bus, device, function, 0x00).to_le_bytes();
+
+        let vendor_id = u16::from_le_bytes([vendor_low, vendor_high]);
+        if vendor_id == NO_VENDOR {
+            return None;
+        }
+
+        let [_revision, prog_if, subclass, class] =
+            read_config(bus, device, function, 0x08).to_le_bytes();
+        let [_, _, header_type, _] = read_config(bus, device, function, 0x0C).to_le_bytes();
+
+        Some(Self {
+            bus,
+            device,
+            function,
+
+            vendor_id,
+            device_id: u16::from_le_bytes([device_low, device_high]),
+            class,
+            subclass,
+            prog_if,
+            header_type: header_type & 0x7F,
+        })
+    }
+
+    /// Reads a register from the configuration space of the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset of the register, which is aligned down to 4 bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `u32` - The value of the register.
+    #[must_use]
+    pub fn read_config(&self, offset: u8) -> u32 {
+        read_config(self.bus, self.device, self.function, offset)
+    }
+
+    /// Writes a register in the configuration space of the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset of the register, which is aligned down to 4 bytes.
+    /// * `value` - The value to write.
+    pub fn write_config(&self, offset: u8, value: u32) {
+        write_config(self.bus, self.device, self.function, offset, value);
+    }
+}
+
+/// Builds the configuration address of a register.
+///
+/// # Arguments
+///
+/// * `bus` - The bus of the device.
+/// * `device` - The device number on the bus.
+/// * `function` - The function number of the device.
+/// * `offset` - The offset of the register.
+///
+/// # Returns
+///
+/// * `u32` - The address to write to [`CONFIG_ADDRESS`].
+fn config_address(bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+    // The enable bit, followed by the bus, device, function and register offset.
+    1 << 31
+        | u32::from(bus) << 16
+        | u32::from(device & 0x1F) << 11
+        | u32::from(function & 0x07) << 8
+        | u32::from(offset & 0xFC)
+}
+
+/// Reads a register from the configuration space of a device.
+///
+/// # Arguments
+///
+/// * `bus` - The bus of the device.
+/// * `device` - The device number on the bus.
+/// * `function` - The function number of the device.
+/// * `offset` - The offset of the register, which is aligned down to 4 bytes.
+///
+/// # Returns
+///
+/// * `u32` - The value of the register.
+#[must_use]
+pub fn read_config(bus: u8, device: u8, function: u8, offset: u8) -> u32 {
+    let mut address: Port<u32> = Port::new(CONFIG_ADDRESS);
+    let mut data: Port<u32> = Port::new(CONFIG_DATA);
+
+    unsafe {
+        address.write(config_address(bus, device, function, offset));
+
+        data.read()
+    }
+}
+
+/// Writes a register in the configuration space of a device.
+///
+/// # Arguments
+///
+/// * `bus` - The bus of the device.
+/// * `device` - The device number on the bus.
+/// * `function` - The function number of the device.
+/// * `offset` - The offset of the register, which is aligned down to 4 bytes.
+/// * `value` - The value to write.
+pub fn write_config(bus: u8, device: u8, function: u8, offset: u8, value: u32) {
+    let mut address: Port<u32> = Port::new(CONFIG_ADDRESS);
+    let mut data: Port<u32> = Port::new(CONFIG_DATA);
+
+    unsafe {
+        address.write(config_address(bus, device, function, offset));
+        data.write(value);
+    }
+}
+
+/// Enumerates every function of every device on every bus.
+///
+/// # Returns
+///
+/// * `Vec<PciDevice>` - The devices found.
+#[must_use]
+pub fn scan() -> Vec<PciDevice> {
+    let mut devices = Vec::new();
+
+    for bus in 0..=u8::MAX {
+        for device in 0..DEVICES_PER_BUS {
+            let Some(first) = PciDevice::read(bus, device, 0) else {
+                continue;
+            };
+            devices.push(first);
+
+            // Only multi-function devices implement the other functions.
+            let [_, _, header_type, _] = first.read_config(0x0C).to_le_bytes();
+            if header_type & 0x80 == 0 {
+                continue;
+            }
+
+            devices.extend(
+                (1..FUNCTIONS_PER_DEVICE)
+                    .filter_map(|function| PciDevice::read(bus, device, function)),
+            );
+        }
+    }
+
+    devices
+}
+
+/// Initializes the PCI driver, enumerating the devices on the bus.
+pub fn init() {
+    let devices = scan();
+
+    for device in &devices {
+        println!(
+            "[INFO]: => PCI {bus:02X}:{dev:02X}.{function} ({vendor:04X}:{id:04X})",
+            bus = device.bus,
+            dev = device.device,
+            function = device.function,
+            vendor = device.vendor_id,
+            id = device.device_id,
+        );
+    }
+
+    *DEVICES.lock() = devices;
+}
+
+/// Gets the devices found during enumeration.
+///
+/// # Returns
+///
+/// * `Vec<PciDevice>` - The devices.
+#[must_use]
+pub fn devices() -> Vec<PciDevice> {
+    DEVICES.lock().clone()
+}
+
+/// Finds the devices with the given class and subclass.
+///
+/// # Arguments
+///
+/// * `class` - The class code, e.g. `0x02` for network controllers.
+/// * `subclass` - The subclass code, e.g. `0x00` for Ethernet controllers.
+///
+/// # Returns
+///
+/// * `Vec<PciDevice>` - The matching devices.
+#[must_use]
+pub fn find_by_class(class: u8, subclass: u8) -> Vec<PciDevice> {
+    DEVICES
+        .lock()
+        .iter()
+        .filter(|device| device.class == class && device.subclass == subclass)
+        .copied()
+        .collect()
+}
+
+/// Tests that enumeration finds the host bridge.
+///
+/// # Panics
+///
+/// * If no host bridge is found.
+#[test_case]
+fn test_scan_host_bridge() {
+    // Class `0x06` is bridges, subclass `0x00` is host bridges.
+    assert!(scan()
+        .iter()
+        .any(|device| device.class == 0x06 && device.subclass == 0x00));
+}
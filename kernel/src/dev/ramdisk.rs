@@ -0,0 +1,197 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::dev::BlockDevice;
+use crate::errors::Error;
+use crate::util::crc32::crc32;
+
+/// The block size of a RAM disk.
+pub const BLOCK_SIZE: usize = 512;
+
+/// A small FAT12 disk image, used to seed RAM disks for testing.
+///
+/// It contains `HELLO.TXT` in the root directory, and `DOCS/README.TXT`, which spans multiple clusters.
+pub static IMAGE: &[u8] = include_bytes!("../../assets/ramdisk.img");
+
+/// A block device backed by memory on the heap.
+///
+/// # Fields
+///
+/// * `data` - The contents of the disk.
+/// * `checksums` - The CRC-32 checksum of every block, updated on every write.
+#[derive(Debug, Clone)]
+pub struct RamDisk {
+    data: Vec<u8>,
+    checksums: Vec<u32>,
+}
+
+impl RamDisk {
+    /// Creates a new zeroed RAM disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `block_count` - The number of blocks.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The RAM disk.
+    #[must_use]
+    pub fn new(block_count: usize) -> Self {
+        Self::with_data(vec![0; block_count * BLOCK_SIZE])
+    }
+
+    /// Creates a new RAM disk with a copy of the given image.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The disk image, padded with zeroes to a whole number of blocks.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The RAM disk.
+    #[must_use]
+    pub fn from_image(image: &[u8]) -> Self {
+        let mut data = image.to_vec();
+        data.resize(image.len().div_ceil(BLOCK_SIZE) * BLOCK_SIZE, 0);
+
+        Self::with_data(data)
+    }
+
+    /// Creates a new RAM disk with the given contents, and checksums every block.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - The contents of the disk, which must be a whole number of blocks.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The RAM disk.
+    fn with_data(data: Vec<u8>) -> Self {
+        let checksums = data.chunks_exact(BLOCK_SIZE).map(crc32).collect();
+
+        Self { data, checksums }
+    }
+
+    /// Gets the byte range of the given block.
+    ///
+    /// # Arguments
+    ///
+    /// * `lba` - The logical block address of the block.
+    /// * `length` - The length of the buffer used to access the block.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<core::ops::Range<usize>, Error>` - The byte range.
+    ///
+    /// # Errors
+    ///
+    /// * If the buffer isn't exactly one block long.
+    /// * If the block doesn't exist.
+    fn block_range(&self, lba: u64, length: usize) -> Result<core::ops::Range<usize>, Error> {
+        if length != BLOCK_SIZE {
+            return Err(Error::Internal(alloc::format!(
+                "Buffer of {length} bytes doesn't match the block size!"
+            )));
+        }
+
+        let start = usize::try_from(lba)? * BLOCK_SIZE;
+        if start + BLOCK_SIZE > self.data.len() {
+            return Err(Error::Internal(alloc::format!(
+                "Block {lba} is out of range!"
+            )));
+        }
+
+        Ok(start..start + BLOCK_SIZE)
+    }
+}
+
+impl BlockDevice for RamDisk {
+    fn block_size(&self) -> usize {
+        BLOCK_SIZE
+    }
+
+    fn block_count(&self) -> u64 {
+        (self.data.len() / BLOCK_SIZE) as u64
+    }
+
+    fn read_block(&mut self, lba: u64, buffer: &mut [u8]) -> Result<(), Error> {
+        let range = self.block_range(lba, buffer.len())?;
+        buffer.copy_from_slice(&self.data[range]);
+
+        Ok(())
+    }
+
+    fn write_block(&mut self, lba: u64, buffer: &[u8]) -> Result<(), Error> {
+        let range = self.block_range(lba, buffer.len())?;
+        self.data[range].copy_from_slice(buffer);
+        self.checksums[usize::try_from(lba)?] = crc32(buffer);
+
+        Ok(())
+    }
+
+    fn checksum(&self, lba: u64) -> Option<u32> {
+        self.checksums.get(usize::try_from(lba).ok()?).copied()
+    }
+}
+
+/// Tests that a RAM disk seeded from [`IMAGE`] holds the image.
+///
+/// # Panics
+///
+/// * If the boot sector can't be read or has no boot signature.
+#[test_case]
+fn test_read_image() {
+    let mut disk = RamDisk::from_image(IMAGE);
+    let mut buffer = [0; BLOCK_SIZE];
+
+    disk.read_block(0, &mut buffer)
+        .expect("Failed to read the boot sector!");
+
+    assert_eq!(buffer[510..], [0x55, 0xAA]);
+}
+
+/// Tests that written blocks can be read back, and that out of range blocks are rejected.
+///
+/// # Panics
+///
+/// * If reading or writing an existing block fails.
+/// * If the block read back differs from the one written.
+/// * If accessing a block past the end succeeds.
+#[test_case]
+fn test_write_read_block() {
+    let mut disk = RamDisk::new(4);
+    let mut buffer = [0; BLOCK_SIZE];
+
+    disk.write_block(3, &[0x42; BLOCK_SIZE])
+        .expect("Failed to write block!");
+    disk.read_block(3, &mut buffer)
+        .expect("Failed to read block!");
+
+    assert_eq!(buffer, [0x42; BLOCK_SIZE]);
+    assert!(disk.read_block(4, &mut buffer).is_err());
+}
+
+/// Tests that checked reads catch blocks changed behind the disk's back.
+///
+/// # Panics
+///
+/// * If a checked read of an intact block fails.
+/// * If a checked read of a corrupted block succeeds.
+#[test_case]
+fn test_read_block_checked() {
+    let mut disk = RamDisk::new(2);
+    let mut buffer = [0; BLOCK_SIZE];
+
+    disk.write_block(1, &[0x42; BLOCK_SIZE])
+        .expect("Failed to write block!");
+    disk.read_block_checked(1, &mut buffer)
+        .expect("Intact block failed its checksum!");
+
+    // Flip a bit without updating the checksum.
+    disk.data[BLOCK_SIZE] ^= 1;
+
+    assert!(matches!(
+        disk.read_block_checked(1, &mut buffer),
+        Err(Error::Integrity(_))
+    ));
+}
@@ -1,4 +1,5 @@
 use crate::sys::task::Identifier;
+use alloc::boxed::Box;
 use alloc::format;
 use alloc::string::String;
 use core::alloc::LayoutError;
@@ -21,6 +22,9 @@ use x86_64::structures::paging::Size4KiB;
 /// * `Conversion` - A conversion error.
 /// * `Task` - A task error.
 /// * `FileSystem` - A file system error.
+/// * `Integrity` - A data integrity error.
+/// * `Network` - A network error.
+/// * `Init` - A kernel initialization stage failed.
 #[derive(Error, Debug, Clone)]
 pub enum Error {
     #[error("Internal Error: {0}")]
@@ -41,6 +45,15 @@ pub enum Error {
     Task(String),
     #[error("File System Error: {0}")]
     FileSystem(String),
+    #[error("Integrity Error: {0}")]
+    Integrity(String),
+    #[error("Network Error: {0}")]
+    Network(String),
+    #[error("Initialization Error: Stage `{stage}` failed: {source}")]
+    Init {
+        stage: &'static str,
+        source: Box<Error>,
+    },
 }
 
 impl From<MapToError<Size4KiB>> for Error {
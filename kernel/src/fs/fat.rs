@@ -1,5 +1,12 @@
 use alloc::string::{String, ToString};
 use alloc::vec::Vec;
+use alloc::{format, vec};
+use core::fmt;
+use core::ops::ControlFlow;
+
+use crate::dev::BlockDevice;
+use crate::errors::Error;
+use crate::util::crc32::crc32;
 
 /// Specifies the file is read only.
 pub const READ_ONLY: u8 = 0x01;
@@ -27,25 +34,36 @@ pub const ARCHIVE: u8 = 0x20;
 /// * They're defined by having the `READ_ONLY`, `HIDDEN`, `SYSTEM`, or `VOLUME_ID` flags set.
 pub const LFN: u8 = READ_ONLY | HIDDEN | SYSTEM | VOLUME_ID;
 
+/// The size of a directory entry on disk, in bytes.
+const DIRECTORY_ENTRY_SIZE: usize = 32;
+
+/// The end of chain marker, which every end of chain value in the file allocation table is normalized to.
+const END_OF_CHAIN: u32 = 0x0FFF_FFFF;
+
+/// The maximum number of clusters the file allocation table can hold, including the two reserved entries.
+const MAX_CLUSTERS: usize = 128;
+
 /// A FAT file system.
 ///
 /// # Fields
 ///
+/// * `device` - The block device the file system is stored on.
 /// * `boot_sector` - The boot sector.
 /// * `fat` - The file allocation table.
 /// * `root_dir` - The root directory.
-#[derive(Debug, Clone)]
-pub struct Fat {
+pub struct Fat<'a> {
+    device: &'a mut dyn BlockDevice,
     boot_sector: BootSector,
     fat: FatTable,
     root_dir: RootDirectory,
 }
 
-impl Fat {
+impl<'a> Fat<'a> {
     /// Creates a new FAT file system.
     ///
     /// # Arguments
     ///
+    /// * `device` - The block device the file system is stored on.
     /// * `boot_sector` - The boot sector.
     /// * `fat` - The file allocation table.
     /// * `root_dir` - The root directory.
@@ -54,14 +72,83 @@ impl Fat {
     ///
     /// * The new FAT file system.
     #[must_use]
-    pub const fn new(boot_sector: BootSector, fat: FatTable, root_dir: RootDirectory) -> Self {
+    pub fn new(
+        device: &'a mut dyn BlockDevice,
+        boot_sector: BootSector,
+        fat: FatTable,
+        root_dir: RootDirectory,
+    ) -> Self {
         Self {
+            device,
             boot_sector,
             fat,
             root_dir,
         }
     }
 
+    /// Mounts the FAT file system stored on the given block device.
+    ///
+    /// The boot sector, the first file allocation table and the root directory are read from the device.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The block device.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The FAT file system.
+    ///
+    /// # Errors
+    ///
+    /// * If reading from the device fails.
+    /// * If the boot sector isn't a valid FAT12 or FAT16 boot sector.
+    /// * If the volume has more than [`MAX_CLUSTERS`] clusters.
+    pub fn mount(device: &'a mut dyn BlockDevice) -> Result<Self, Error> {
+        let mut sector = vec![0; device.block_size()];
+
+        device.read_block(0, &mut sector)?;
+        let boot_sector = BootSector::from_bytes(&sector)?;
+
+        if boot_sector.bytes_per_sector as usize != device.block_size() {
+            return Err(Error::FileSystem(format!(
+                "Sector size {size} doesn't match the block size of the device!",
+                size = boot_sector.bytes_per_sector
+            )));
+        }
+
+        let fat_type = boot_sector.fat_type()?;
+
+        // Read the first file allocation table.
+        let mut table = Vec::new();
+        for offset in 0..u64::from(boot_sector.sectors_per_fat) {
+            device.read_block(
+                u64::from(boot_sector.reserved_sectors) + offset,
+                &mut sector,
+            )?;
+
+            table.extend_from_slice(&sector);
+        }
+        let count = usize::try_from(boot_sector.cluster_count() + 2)?;
+        let fat = FatTable::from_bytes(&table, fat_type, count)?;
+
+        // Read the root directory.
+        let mut entries = Vec::new();
+        let first_sector = boot_sector.root_dir_sector();
+        for offset in 0..boot_sector.root_dir_sectors() {
+            device.read_block(first_sector + offset, &mut sector)?;
+
+            entries.extend(
+                sector
+                    .chunks_exact(DIRECTORY_ENTRY_SIZE)
+                    .map(DirectoryEntry::from_bytes),
+            );
+        }
+        entries.truncate(boot_sector.root_dir_entries as usize);
+        let root_dir = RootDirectory::new(entries);
+
+        Ok(Self::new(device, boot_sector, fat, root_dir))
+    }
+
     /// Reads a file from the file system.
     ///
     /// # Arguments
@@ -95,42 +182,190 @@ impl Fat {
 
     /// Reads a directory from the file system.
     ///
+    /// The path is resolved one component at a time from the root directory, ignoring case.
+    ///
     /// # Arguments
     ///
-    /// * `path` - The path to the directory.
+    /// * `path` - The path to the directory, where an empty path or `/` is the root directory.
     ///
     /// # Returns
     ///
-    /// * If the directory exists, the directory.
+    /// * If the directory exists, its entries, including `.` and `..` for subdirectories.
     /// * Otherwise, `None`.
     #[must_use]
-    pub fn read_dir(&self, path: &str) -> Option<Vec<File>> {
-        // Get the directory name.
-        let dir_name = path.split('/').last()?;
+    pub fn read_dir(&mut self, path: &str) -> Option<Vec<Entry>> {
+        let cluster = self.find_dir(path)?;
 
-        // Get the directory.
-        let dir = path.trim_end_matches(dir_name);
+        let mut entries = Vec::new();
+        self.walk_entries(cluster, |entry| {
+            entries.push(entry);
 
-        // Get the directory entry.
-        let dir_entry = self.root_dir.get_entry(dir)?;
+            ControlFlow::Continue(())
+        })
+        .ok()?;
 
-        // Get the directory entry.
-        let dir_entry = dir_entry.get_entry(dir_name)?;
+        Some(entries)
+    }
 
-        // Check if the directory entry is a directory.
-        if dir_entry.attributes & DIRECTORY == 0 {
-            // Return `None`.
-            return None;
+    /// Calls a function with every entry of a directory, without collecting them.
+    ///
+    /// The directory is read a sector at a time, so only one sector and one entry are allocated at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the directory, where an empty path or `/` is the root directory.
+    /// * `f` - The function to call with every entry, including `.` and `..` for subdirectories.
+    ///
+    /// # Returns
+    ///
+    /// * If the directory exists and could be read, `Some(())`.
+    /// * Otherwise, `None`.
+    pub fn for_each_entry(&mut self, path: &str, mut f: impl FnMut(&Entry)) -> Option<()> {
+        let cluster = self.find_dir(path)?;
+
+        self.walk_entries(cluster, |entry| {
+            f(&entry);
+
+            ControlFlow::Continue(())
+        })
+        .ok()
+    }
+
+    /// Finds the first cluster of a directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the directory, resolved one component at a time from the root directory, ignoring case.
+    ///
+    /// # Returns
+    ///
+    /// * If the directory exists, its first cluster, where 0 is the root directory.
+    /// * Otherwise, `None`.
+    fn find_dir(&mut self, path: &str) -> Option<u32> {
+        let mut cluster = 0;
+
+        for component in path.split('/').filter(|component| !component.is_empty()) {
+            let mut found = None;
+            self.walk_entries(cluster, |entry| {
+                if entry.is_dir() && entry.name.eq_ignore_ascii_case(component) {
+                    found = Some(entry.first_cluster);
+
+                    return ControlFlow::Break(());
+                }
+
+                ControlFlow::Continue(())
+            })
+            .ok()?;
+
+            cluster = found?;
         }
 
-        // Get the first cluster.
-        let first_cluster = dir_entry.first_cluster;
+        Some(cluster)
+    }
+
+    /// Walks the entries of the directory starting at the given cluster.
+    ///
+    /// Unused entries, long file name entries and the volume label are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `cluster` - The first cluster of the directory, or 0 for the root directory, like `..` entries use.
+    /// * `f` - The function to call with every entry, which can break to stop the walk early.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If reading from the device fails.
+    fn walk_entries(
+        &mut self,
+        cluster: u32,
+        mut f: impl FnMut(Entry) -> ControlFlow<()>,
+    ) -> Result<(), Error> {
+        let mut sector = vec![0; self.device.block_size()];
+
+        // The root directory has a fixed location, while subdirectories are cluster chains.
+        if cluster == 0 {
+            let first_sector = self.boot_sector.root_dir_sector();
+            for lba in first_sector..first_sector + self.boot_sector.root_dir_sectors() {
+                if self.walk_sector(lba, &mut sector, &mut f)?.is_break() {
+                    break;
+                }
+            }
+
+            return Ok(());
+        }
+
+        let mut cluster = Some(cluster);
+
+        // Bound the walk, so a corrupt chain with a loop can't hang us.
+        for _ in 0..MAX_CLUSTERS {
+            let Some(current) = cluster else {
+                break;
+            };
+            let Some(first_sector) = self.boot_sector.cluster_sector(current) else {
+                break;
+            };
+
+            let sectors_per_cluster = u64::from(self.boot_sector.sectors_per_cluster);
+            for lba in first_sector..first_sector + sectors_per_cluster {
+                if self.walk_sector(lba, &mut sector, &mut f)?.is_break() {
+                    return Ok(());
+                }
+            }
+
+            cluster = self.fat.next_cluster(current);
+        }
+
+        Ok(())
+    }
 
-        // Get the files.
-        let files = self.get_files(first_cluster)?;
+    /// Walks the entries of a single directory sector.
+    ///
+    /// # Arguments
+    ///
+    /// * `lba` - The sector to read.
+    /// * `sector` - The buffer to read the sector into.
+    /// * `f` - The function to call with every entry.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<ControlFlow<()>, Error>` - Whether the walk should stop, at the end of the directory or when `f`
+    ///   breaks.
+    ///
+    /// # Errors
+    ///
+    /// * If reading from the device fails.
+    fn walk_sector(
+        &mut self,
+        lba: u64,
+        sector: &mut [u8],
+        f: &mut impl FnMut(Entry) -> ControlFlow<()>,
+    ) -> Result<ControlFlow<()>, Error> {
+        self.device.read_block(lba, sector)?;
+
+        for raw in sector.chunks_exact(DIRECTORY_ENTRY_SIZE) {
+            match raw[0] {
+                // The first free entry marks the end of the directory.
+                0x00 => return Ok(ControlFlow::Break(())),
+                // Deleted.
+                0xE5 => continue,
+                _ => {}
+            }
+
+            let entry = DirectoryEntry::from_bytes(raw);
+            if entry.attributes & LFN == LFN || entry.attributes & VOLUME_ID != 0 {
+                continue;
+            }
+
+            if f(Entry::new(entry.name(), &entry)).is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
 
-        // Return the files.
-        Some(files)
+        Ok(ControlFlow::Continue(()))
     }
 
     /// Gets the files in the specified cluster.
@@ -144,7 +379,7 @@ impl Fat {
     /// * If the cluster exists, the files.
     /// * Otherwise, `None`.
     #[must_use]
-    pub fn get_files(&self, cluster: u32) -> Option<Vec<File>> {
+    pub fn get_files(&mut self, cluster: u32) -> Option<Vec<File>> {
         // Get the first cluster.
         let mut cluster = cluster;
 
@@ -187,40 +422,15 @@ impl Fat {
     /// * If the cluster exists, the file entry.
     /// * Otherwise, `None`.
     #[must_use]
-    pub const fn get_file_entry(&self, cluster: u32) -> Option<DirectoryEntry> {
-        // Get the sector.
-        let sector = self.boot_sector.reserved_sectors as u32
-            + self.boot_sector.fat_count as u32 * self.boot_sector.sectors_per_fat as u32
-            + (cluster - 2) * self.boot_sector.sectors_per_cluster as u32;
-
-        // Get the sector.
-        let sector = sector as usize;
-
-        // Get the sector.
-        let sector = unsafe { &*(sector as *const [u8; 512]) };
+    pub fn get_file_entry(&mut self, cluster: u32) -> Option<DirectoryEntry> {
+        let lba = self.boot_sector.cluster_sector(cluster)?;
 
-        // Get the file entry.
-        let file_entry = DirectoryEntry::new(
-            "",
-            DIRECTORY,
-            [0; 10],
-            0,
-            0,
-            0,
-            0,
-            0,
-            0,
-            0,
-            sector[0x1A] as u16 | ((sector[0x1B] as u16) << 8),
-            sector[0x1C] as u32
-                | ((sector[0x1D] as u32) << 8)
-                | ((sector[0x1E] as u32) << 16)
-                | ((sector[0x1F] as u32) << 24),
-            cluster,
-        );
+        // Read the first sector of the cluster.
+        let mut sector = vec![0; self.device.block_size()];
+        self.device.read_block(lba, &mut sector).ok()?;
 
-        // Return the file entry.
-        Some(file_entry)
+        // Parse the first entry in it.
+        Some(DirectoryEntry::from_bytes(&sector[..DIRECTORY_ENTRY_SIZE]))
     }
 
     /// Gets the file entry for the specified path.
@@ -250,6 +460,230 @@ impl Fat {
         // Return the file entry.
         Some(file_entry)
     }
+
+    /// Reads the contents of the given file, following its cluster chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<u8>, Error>` - The contents of the file.
+    ///
+    /// # Errors
+    ///
+    /// * If the cluster chain ends before the end of the file.
+    /// * If reading from the device fails, or a block doesn't match its stored checksum.
+    pub fn read_contents(&mut self, file: &File) -> Result<Vec<u8>, Error> {
+        let size = file.size as usize;
+        let mut contents = Vec::with_capacity(size);
+        let mut sector = vec![0; self.device.block_size()];
+
+        let mut cluster = Some(file.first_cluster);
+        while contents.len() < size {
+            let lba = cluster
+                .and_then(|cluster| self.boot_sector.cluster_sector(cluster))
+                .ok_or_else(|| {
+                    Error::FileSystem(format!("Cluster chain of '{}' is too short!", file.name))
+                })?;
+
+            for offset in 0..u64::from(self.boot_sector.sectors_per_cluster) {
+                self.device.read_block_checked(lba + offset, &mut sector)?;
+
+                let remaining = size - contents.len();
+                contents.extend_from_slice(&sector[..remaining.min(sector.len())]);
+            }
+
+            cluster = cluster.and_then(|cluster| self.fat.next_cluster(cluster));
+        }
+
+        Ok(contents)
+    }
+
+    /// Reads part of the given file, only reading the clusters that cover it.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - The file.
+    /// * `offset` - The offset to read from.
+    /// * `buffer` - The buffer to read into.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<usize, Error>` - The number of bytes read, which is less than the length of the buffer near the end of
+    ///   the file, and zero past it.
+    ///
+    /// # Errors
+    ///
+    /// * If the cluster chain ends before the end of the file.
+    /// * If reading from the device fails, or a block doesn't match its stored checksum.
+    pub fn read_at(
+        &mut self,
+        file: &File,
+        offset: usize,
+        buffer: &mut [u8],
+    ) -> Result<usize, Error> {
+        let count = (file.size as usize)
+            .saturating_sub(offset)
+            .min(buffer.len());
+        if count == 0 {
+            return Ok(0);
+        }
+
+        let sector_size = self.device.block_size();
+        let sectors_per_cluster = usize::from(self.boot_sector.sectors_per_cluster);
+        let cluster_size = sector_size * sectors_per_cluster;
+
+        // Skip the clusters before the offset, which only takes the table.
+        let mut cluster = Some(file.first_cluster);
+        for _ in 0..offset / cluster_size {
+            cluster = cluster.and_then(|cluster| self.fat.next_cluster(cluster));
+        }
+
+        let mut sector = vec![0; sector_size];
+        let mut position = offset;
+        let mut read = 0;
+        while read < count {
+            let lba = cluster
+                .and_then(|cluster| self.boot_sector.cluster_sector(cluster))
+                .ok_or_else(|| {
+                    Error::FileSystem(format!("Cluster chain of '{}' is too short!", file.name))
+                })?;
+
+            let first = position % cluster_size / sector_size;
+            for index in first..sectors_per_cluster {
+                if read == count {
+                    break;
+                }
+
+                self.device
+                    .read_block_checked(lba + index as u64, &mut sector)?;
+
+                let start = position % sector_size;
+                let len = (sector_size - start).min(count - read);
+                buffer[read..read + len].copy_from_slice(&sector[start..start + len]);
+
+                read += len;
+                position += len;
+            }
+
+            cluster = cluster.and_then(|cluster| self.fat.next_cluster(cluster));
+        }
+
+        Ok(count)
+    }
+
+    /// Writes any changes back to the device, and flushes its caches.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If writing the file allocation table back fails.
+    /// * If flushing the device fails.
+    ///
+    /// # Notes
+    ///
+    /// * A changed file allocation table is written to every copy of it. Directories are never changed in memory, so
+    ///   there's nothing else to write back.
+    pub fn sync(&mut self) -> Result<(), Error> {
+        if self.fat.is_dirty() {
+            self.write_fat()?;
+        }
+
+        self.device.flush()
+    }
+
+    /// Writes the file allocation table to every copy of it on the device.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the FAT type of the boot sector is invalid.
+    /// * If reading from or writing to the device fails.
+    fn write_fat(&mut self) -> Result<(), Error> {
+        let fat_type = self.boot_sector.fat_type()?;
+        let sector_size = self.device.block_size();
+        let first_sector = u64::from(self.boot_sector.reserved_sectors);
+        let sectors = u64::from(self.boot_sector.sectors_per_fat);
+
+        // Start from the first copy, so the reserved entries and the bytes past the last cluster are kept.
+        let mut table = vec![0; sector_size * usize::from(self.boot_sector.sectors_per_fat)];
+        for (offset, sector) in (0..).zip(table.chunks_exact_mut(sector_size)) {
+            self.device.read_block(first_sector + offset, sector)?;
+        }
+        self.fat.to_bytes(&mut table, fat_type)?;
+
+        for copy in 0..u64::from(self.boot_sector.fat_count) {
+            for (offset, sector) in (0..).zip(table.chunks_exact(sector_size)) {
+                self.device
+                    .write_block(first_sector + copy * sectors + offset, sector)?;
+            }
+        }
+        self.fat.dirty = false;
+
+        Ok(())
+    }
+
+    /// Gets the free and total space of the data region.
+    ///
+    /// # Returns
+    ///
+    /// * `(u64, u64)` - The free and the total number of bytes, counted in whole clusters.
+    #[must_use]
+    pub fn free_space(&self) -> (u64, u64) {
+        let cluster_size = u64::from(self.boot_sector.bytes_per_sector)
+            * u64::from(self.boot_sector.sectors_per_cluster);
+
+        // The first two entries are reserved, so the data clusters are numbered from 2.
+        let clusters = self.boot_sector.cluster_count();
+        let free = (2..clusters + 2)
+            .filter(|&cluster| {
+                u32::try_from(cluster).is_ok_and(|cluster| self.fat.is_free(cluster))
+            })
+            .count() as u64;
+
+        (free * cluster_size, clusters * cluster_size)
+    }
+
+    /// Verifies the integrity of the file at the given path.
+    ///
+    /// The file is read twice with checked reads, and the CRC-32 checksums of both reads are compared.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the file.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, Error>` - Whether or not the file is intact.
+    ///
+    /// # Errors
+    ///
+    /// * If the file doesn't exist.
+    /// * If reading the file fails for any other reason than a checksum mismatch.
+    pub fn verify_file(&mut self, path: &str) -> Result<bool, Error> {
+        let file = self
+            .read_file(path)
+            .ok_or_else(|| Error::FileSystem(format!("No such file: '{path}'!")))?;
+
+        let mut checksums = [0; 2];
+        for checksum in &mut checksums {
+            *checksum = match self.read_contents(&file) {
+                Ok(contents) => crc32(&contents),
+                Err(Error::Integrity(_)) => return Ok(false),
+                Err(error) => return Err(error),
+            };
+        }
+
+        Ok(checksums[0] == checksums[1])
+    }
 }
 
 /// A FAT file system boot sector.
@@ -330,112 +764,518 @@ impl BootSector {
             total_sectors_long,
         }
     }
-}
-
-/// A FAT file system file allocation table.
-///
-/// # Fields
-///
-/// * `entries` - The entries.
-#[derive(Debug, Clone, Copy)]
-pub struct FatTable {
-    entries: [u32; 128],
-}
 
-impl FatTable {
-    /// Creates a new FAT file system file allocation table.
+    /// Parses a FAT file system boot sector.
     ///
     /// # Arguments
     ///
-    /// * `entries` - The entries.
+    /// * `bytes` - The raw boot sector.
     ///
     /// # Returns
     ///
-    /// * The new FAT file system file allocation table.
-    #[must_use]
-    pub const fn new(entries: [u32; 128]) -> Self {
-        Self { entries }
+    /// * `Result<Self, Error>` - The boot sector.
+    ///
+    /// # Errors
+    ///
+    /// * If the boot sector is too short or has no boot signature.
+    /// * If any of the fields the layout depends on are zero.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < 512 || bytes[510..512] != [0x55, 0xAA] {
+            return Err(Error::FileSystem("Missing boot signature!".into()));
+        }
+
+        let u16_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+
+        let boot_sector = Self::new(
+            u16_at(0x0B),
+            bytes[0x0D],
+            u16_at(0x0E),
+            bytes[0x10],
+            u16_at(0x11),
+            u16_at(0x13),
+            u16_at(0x16),
+            u16_at(0x18),
+            u16_at(0x1A),
+            u32_at(0x1C),
+            u32_at(0x20),
+        );
+
+        if boot_sector.bytes_per_sector == 0
+            || boot_sector.sectors_per_cluster == 0
+            || boot_sector.reserved_sectors == 0
+            || boot_sector.fat_count == 0
+        {
+            return Err(Error::FileSystem("Invalid boot sector!".into()));
+        }
+
+        Ok(boot_sector)
     }
 
-    /// Gets the next cluster in the chain.
+    /// Gets the total number of sectors, from whichever of the two fields is in use.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `cluster` - The current cluster.
+    /// * `u64` - The total number of sectors.
+    #[must_use]
+    pub fn sector_count(&self) -> u64 {
+        if self.total_sectors == 0 {
+            u64::from(self.total_sectors_long)
+        } else {
+            u64::from(self.total_sectors)
+        }
+    }
+
+    /// Gets the number of sectors used by the root directory.
     ///
     /// # Returns
     ///
-    /// * The next cluster in the chain.
+    /// * `u64` - The number of sectors used by the root directory.
     #[must_use]
-    pub const fn next_cluster(&self, cluster: u32) -> Option<u32> {
-        // Get the entry.
-        let entry = self.entries[cluster as usize];
-
-        // Check if the entry is valid.
-        if entry >= 0x0FFF_FFF8 {
-            // Return `None`.
-            return None;
-        }
+    pub fn root_dir_sectors(&self) -> u64 {
+        let bytes = u64::from(self.root_dir_entries) * DIRECTORY_ENTRY_SIZE as u64;
 
-        // Return the entry.
-        Some(entry)
+        bytes.div_ceil(u64::from(self.bytes_per_sector))
     }
-}
 
-/// A FAT file system root directory.
-///
-/// # Fields
-///
-/// * `entries` - The entries.
-#[derive(Debug, Clone)]
-pub struct RootDirectory {
-    entries: [DirectoryEntry; 512],
-}
+    /// Gets the first sector of the root directory.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The first sector of the root directory.
+    #[must_use]
+    pub fn root_dir_sector(&self) -> u64 {
+        u64::from(self.reserved_sectors)
+            + u64::from(self.fat_count) * u64::from(self.sectors_per_fat)
+    }
 
-impl RootDirectory {
-    /// Creates a new FAT file system root directory.
+    /// Gets the first sector of the data region, which holds cluster 2.
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `entries` - The entries.
+    /// * `u64` - The first sector of the data region.
+    #[must_use]
+    pub fn first_data_sector(&self) -> u64 {
+        self.root_dir_sector() + self.root_dir_sectors()
+    }
+
+    /// Gets the number of data clusters.
     ///
     /// # Returns
     ///
-    /// * The new FAT file system root directory.
+    /// * `u64` - The number of data clusters.
     #[must_use]
-    pub const fn new(entries: [DirectoryEntry; 512]) -> Self {
-        Self { entries }
+    pub fn cluster_count(&self) -> u64 {
+        let data_sectors = self.sector_count().saturating_sub(self.first_data_sector());
+
+        data_sectors / u64::from(self.sectors_per_cluster)
     }
 
-    /// Gets the directory entry for the specified path.
+    /// Gets the type of the FAT file system, which is determined by the number of clusters alone.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<FatType, Error>` - The type of the FAT file system.
+    ///
+    /// # Errors
+    ///
+    /// * If the file system is FAT32, which isn't supported.
+    pub fn fat_type(&self) -> Result<FatType, Error> {
+        match self.cluster_count() {
+            0..=4_084 => Ok(FatType::Fat12),
+            4_085..=65_524 => Ok(FatType::Fat16),
+            _ => Err(Error::FileSystem("FAT32 isn't supported!".into())),
+        }
+    }
+
+    /// Gets the first sector of the given cluster.
     ///
     /// # Arguments
     ///
-    /// * `path` - The path.
+    /// * `cluster` - The cluster.
     ///
     /// # Returns
     ///
-    /// * If the directory entry exists, the directory entry.
-    /// * Otherwise, `None`.
+    /// * `Option<u64>` - The first sector of the cluster, if it's a data cluster.
     #[must_use]
-    pub fn get_entry(&self, path: &str) -> Option<DirectoryEntry> {
+    pub fn cluster_sector(&self, cluster: u32) -> Option<u64> {
+        let index = u64::from(cluster.checked_sub(2)?);
+
+        Some(self.first_data_sector() + index * u64::from(self.sectors_per_cluster))
+    }
+}
+
+/// The type of a FAT file system.
+///
+/// # Variants
+///
+/// * `Fat12` - Uses 12 bit file allocation table entries.
+/// * `Fat16` - Uses 16 bit file allocation table entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FatType {
+    Fat12,
+    Fat16,
+}
+
+/// A FAT file system file allocation table.
+///
+/// # Fields
+///
+/// * `entries` - The entries, including the two reserved entries.
+/// * `dirty` - Whether the entries were changed since the table was read or last written back.
+#[derive(Debug, Clone)]
+pub struct FatTable {
+    entries: Vec<u32>,
+    dirty: bool,
+}
+
+impl FatTable {
+    /// Creates a new FAT file system file allocation table.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The entries, including the two reserved entries.
+    ///
+    /// # Returns
+    ///
+    /// * The new FAT file system file allocation table.
+    #[must_use]
+    pub const fn new(entries: Vec<u32>) -> Self {
+        Self {
+            entries,
+            dirty: false,
+        }
+    }
+
+    /// Decodes a FAT file system file allocation table.
+    ///
+    /// End of chain values are normalized to [`END_OF_CHAIN`].
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The raw file allocation table.
+    /// * `fat_type` - The type of the FAT file system.
+    /// * `count` - The number of entries, including the two reserved entries.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The file allocation table.
+    ///
+    /// # Errors
+    ///
+    /// * If there are more than [`MAX_CLUSTERS`] entries.
+    /// * If the raw file allocation table is too short.
+    pub fn from_bytes(bytes: &[u8], fat_type: FatType, count: usize) -> Result<Self, Error> {
+        if count > MAX_CLUSTERS {
+            return Err(Error::FileSystem(format!(
+                "Volumes with {count} clusters aren't supported, the limit is {MAX_CLUSTERS}!"
+            )));
+        }
+
+        let mut entries = vec![0; count];
+        for (cluster, entry) in entries.iter_mut().enumerate() {
+            let (value, end_of_chain) = match fat_type {
+                FatType::Fat12 => {
+                    // Two entries are packed into three bytes.
+                    let offset = cluster + cluster / 2;
+                    let bytes = bytes
+                        .get(offset..offset + 2)
+                        .ok_or_else(|| Error::FileSystem("Truncated FAT!".into()))?;
+                    let pair = u16::from_le_bytes([bytes[0], bytes[1]]);
+
+                    let value = if cluster % 2 == 0 {
+                        pair & 0x0FFF
+                    } else {
+                        pair >> 4
+                    };
+
+                    (u32::from(value), 0x0FF8)
+                }
+                FatType::Fat16 => {
+                    let offset = cluster * 2;
+                    let bytes = bytes
+                        .get(offset..offset + 2)
+                        .ok_or_else(|| Error::FileSystem("Truncated FAT!".into()))?;
+
+                    (u32::from(u16::from_le_bytes([bytes[0], bytes[1]])), 0xFFF8)
+                }
+            };
+
+            *entry = if value >= end_of_chain {
+                END_OF_CHAIN
+            } else {
+                value
+            };
+        }
+
+        Ok(Self {
+            entries,
+            dirty: false,
+        })
+    }
+
+    /// Encodes the data cluster entries into a raw file allocation table, the reverse of [`FatTable::from_bytes`].
+    ///
+    /// [`END_OF_CHAIN`] is written as the largest end of chain value of the FAT type.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The raw file allocation table, as read from the device.
+    /// * `fat_type` - The type of the FAT file system.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of the operation.
+    ///
+    /// # Errors
+    ///
+    /// * If the raw file allocation table is too short.
+    ///
+    /// # Notes
+    ///
+    /// * The two reserved entries are left as they are, since they hold the media descriptor and the volume flags.
+    pub fn to_bytes(&self, bytes: &mut [u8], fat_type: FatType) -> Result<(), Error> {
+        for (cluster, &entry) in self.entries.iter().enumerate().skip(2) {
+            match fat_type {
+                FatType::Fat12 => {
+                    // Two entries are packed into three bytes, so keep the half of the pair owned by the neighbor.
+                    let offset = cluster + cluster / 2;
+                    let bytes = bytes
+                        .get_mut(offset..offset + 2)
+                        .ok_or_else(|| Error::FileSystem("Truncated FAT!".into()))?;
+                    let pair = u16::from_le_bytes([bytes[0], bytes[1]]);
+                    let value = entry.min(0x0FFF) as u16;
+
+                    let pair = if cluster % 2 == 0 {
+                        pair & 0xF000 | value
+                    } else {
+                        pair & 0x000F | value << 4
+                    };
+                    bytes.copy_from_slice(&pair.to_le_bytes());
+                }
+                FatType::Fat16 => {
+                    let offset = cluster * 2;
+                    let bytes = bytes
+                        .get_mut(offset..offset + 2)
+                        .ok_or_else(|| Error::FileSystem("Truncated FAT!".into()))?;
+
+                    bytes.copy_from_slice(&(entry.min(0xFFFF) as u16).to_le_bytes());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks if the table was changed since it was read or last written back.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether or not the table has to be written back.
+    #[must_use]
+    pub const fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Gets the next cluster in the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `cluster` - The current cluster.
+    ///
+    /// # Returns
+    ///
+    /// * The next cluster in the chain.
+    #[must_use]
+    pub fn next_cluster(&self, cluster: u32) -> Option<u32> {
+        // Get the entry.
+        let entry = *self.entries.get(cluster as usize)?;
+
+        // Check if the entry is valid, free and reserved entries don't point anywhere.
+        if !(2..0x0FFF_FFF8).contains(&entry) {
+            // Return `None`.
+            return None;
+        }
+
+        // Return the entry.
+        Some(entry)
+    }
+
+    /// Checks if the given cluster is free.
+    ///
+    /// # Arguments
+    ///
+    /// * `cluster` - The cluster.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether or not the cluster exists and isn't in use.
+    #[must_use]
+    pub fn is_free(&self, cluster: u32) -> bool {
+        self.entries.get(cluster as usize) == Some(&0)
+    }
+
+    /// Allocates the first free cluster, marking it as the end of a chain.
+    ///
+    /// # Returns
+    ///
+    /// * If a cluster is free, the cluster.
+    /// * Otherwise, `None`.
+    ///
+    /// # Notes
+    ///
+    /// * This only changes the table in memory, it's written back by [`Fat::sync`].
+    pub fn alloc_cluster(&mut self) -> Option<u32> {
+        // The first two entries are reserved, so the data clusters are numbered from 2.
+        let (cluster, entry) = self
+            .entries
+            .iter_mut()
+            .enumerate()
+            .skip(2)
+            .find(|(_, entry)| **entry == 0)?;
+
+        *entry = END_OF_CHAIN;
+        self.dirty = true;
+
+        u32::try_from(cluster).ok()
+    }
+
+    /// Links two clusters, so the chain continues from one to the other.
+    ///
+    /// # Arguments
+    ///
+    /// * `prev` - The cluster to continue from.
+    /// * `next` - The cluster to continue to.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), Error>` - The result of linking the clusters.
+    ///
+    /// # Errors
+    ///
+    /// * If either cluster is reserved or out of range.
+    ///
+    /// # Notes
+    ///
+    /// * This only changes the table in memory, it's written back by [`Fat::sync`].
+    pub fn link(&mut self, prev: u32, next: u32) -> Result<(), Error> {
+        let count = self.entries.len();
+        if prev < 2 || next < 2 || next as usize >= count {
+            return Err(Error::FileSystem(format!(
+                "Can't link cluster {prev} to {next}, the table has {count} entries!"
+            )));
+        }
+
+        let entry = self
+            .entries
+            .get_mut(prev as usize)
+            .ok_or_else(|| Error::FileSystem(format!("Cluster {prev} is out of range!")))?;
+        *entry = next;
+        self.dirty = true;
+
+        Ok(())
+    }
+
+    /// Frees every cluster in a chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `start` - The first cluster of the chain.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of clusters freed.
+    ///
+    /// # Notes
+    ///
+    /// * This only changes the table in memory, it's written back by [`Fat::sync`].
+    pub fn free_chain(&mut self, start: u32) -> usize {
+        let mut cluster = Some(start);
+        let mut freed = 0;
+
+        // Bound the walk, so a corrupt chain with a loop can't hang us.
+        for _ in 0..self.entries.len() {
+            let Some(current) = cluster.filter(|&cluster| cluster >= 2) else {
+                break;
+            };
+            let next = self.next_cluster(current);
+            let Some(entry) = self.entries.get_mut(current as usize) else {
+                break;
+            };
+            if *entry == 0 {
+                break;
+            }
+
+            *entry = 0;
+            freed += 1;
+            cluster = next;
+        }
+
+        self.dirty |= freed > 0;
+
+        freed
+    }
+}
+
+/// A FAT file system root directory.
+///
+/// # Fields
+///
+/// * `entries` - The entries.
+#[derive(Debug, Clone)]
+pub struct RootDirectory {
+    entries: Vec<DirectoryEntry>,
+}
+
+impl RootDirectory {
+    /// Creates a new FAT file system root directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The entries.
+    ///
+    /// # Returns
+    ///
+    /// * The new FAT file system root directory.
+    #[must_use]
+    pub const fn new(entries: Vec<DirectoryEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// Gets the entries of the root directory.
+    ///
+    /// # Returns
+    ///
+    /// * `&[DirectoryEntry]` - The entries, including unused ones.
+    #[must_use]
+    pub fn entries(&self) -> &[DirectoryEntry] {
+        &self.entries
+    }
+
+    /// Gets the directory entry for the specified path.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path.
+    ///
+    /// # Returns
+    ///
+    /// * If the directory entry exists, the directory entry.
+    /// * Otherwise, `None`.
+    #[must_use]
+    pub fn get_entry(&self, path: &str) -> Option<DirectoryEntry> {
         // Check if the path is empty.
         if path.is_empty() {
             // Return the root directory.
             return Some(DirectoryEntry::new(
-                "",
-                DIRECTORY,
-                [0; 10],
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
-                0,
+                [b' '; 11], DIRECTORY, [0; 10], 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
             ));
         }
 
@@ -474,7 +1314,7 @@ impl RootDirectory {
         }
 
         // Get the file name.
-        let file_name = file_entry.name.trim_end_matches(' ');
+        let file_name = file_entry.name();
 
         // Get the file size.
         let file_size = file_entry.file_size;
@@ -483,7 +1323,7 @@ impl RootDirectory {
         let first_cluster = file_entry.first_cluster;
 
         // Return the file.
-        Some(File::new(file_name, file_size, first_cluster))
+        Some(File::new(&file_name, file_size, first_cluster))
     }
 }
 
@@ -491,7 +1331,7 @@ impl RootDirectory {
 ///
 /// # Fields
 ///
-/// * `name` - The name.
+/// * `name` - The space padded 8.3 name, as stored on disk.
 /// * `attributes` - The attributes.
 /// * `reserved` - The reserved bytes.
 /// * `creation_time_tenths` - The creation time tenths of a second.
@@ -506,7 +1346,7 @@ impl RootDirectory {
 /// * `first_cluster` - The first cluster.
 #[derive(Debug, Clone, Copy, Default)]
 pub struct DirectoryEntry {
-    pub name: &'static str,
+    pub name: [u8; 11],
     pub attributes: u8,
     pub reserved: [u8; 10],
     pub creation_time_tenths: u8,
@@ -526,7 +1366,7 @@ impl DirectoryEntry {
     ///
     /// # Arguments
     ///
-    /// * `name` - The name.
+    /// * `name` - The space padded 8.3 name, as stored on disk.
     /// * `attributes` - The attributes.
     /// * `reserved` - The reserved bytes.
     /// * `creation_time_tenths` - The creation time tenths of a second.
@@ -545,7 +1385,7 @@ impl DirectoryEntry {
     /// * The new FAT file system directory entry.
     #[must_use]
     pub const fn new(
-        name: &'static str,
+        name: [u8; 11],
         attributes: u8,
         reserved: [u8; 10],
         creation_time_tenths: u8,
@@ -576,6 +1416,55 @@ impl DirectoryEntry {
         }
     }
 
+    /// Parses a FAT file system directory entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The raw directory entry, which must be at least 32 bytes long.
+    ///
+    /// # Returns
+    ///
+    /// * The directory entry.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let u16_at = |offset: usize| u16::from_le_bytes([bytes[offset], bytes[offset + 1]]);
+
+        let mut name = [0; 11];
+        name.copy_from_slice(&bytes[..11]);
+
+        let mut reserved = [0; 10];
+        reserved.copy_from_slice(&bytes[0x0C..0x16]);
+
+        let first_cluster_high = u16_at(0x14);
+        let first_cluster_low = u16_at(0x1A);
+
+        Self::new(
+            name,
+            bytes[0x0B],
+            reserved,
+            bytes[0x0D],
+            u16_at(0x0E),
+            u16_at(0x10),
+            u16_at(0x12),
+            first_cluster_high,
+            u16_at(0x16),
+            u16_at(0x18),
+            first_cluster_low,
+            u32::from_le_bytes([bytes[0x1C], bytes[0x1D], bytes[0x1E], bytes[0x1F]]),
+            u32::from(first_cluster_high) << 16 | u32::from(first_cluster_low),
+        )
+    }
+
+    /// Gets the name.
+    ///
+    /// # Returns
+    ///
+    /// * `String` - The name, like `README.TXT`, see [`decode_83`].
+    #[must_use]
+    pub fn name(&self) -> String {
+        decode_83(&self.name)
+    }
+
     /// Gets the directory entry for the specified path.
     ///
     /// # Arguments
@@ -659,34 +1548,569 @@ impl File {
     }
 }
 
-/// Initializes the FAT file system.
+/// A date and time as stored in a FAT directory entry.
+///
+/// # Fields
+///
+/// * `year` - The year, from 1980.
+/// * `month` - The month, from 1.
+/// * `day` - The day of the month, from 1.
+/// * `hours` - The hours.
+/// * `minutes` - The minutes.
+/// * `seconds` - The seconds, with a resolution of two seconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+}
+
+impl Timestamp {
+    /// Decodes a FAT date and time.
+    ///
+    /// # Arguments
+    ///
+    /// * `date` - The date, packed as 7 bits of years since 1980, 4 bits of month and 5 bits of day.
+    /// * `time` - The time, packed as 5 bits of hours, 6 bits of minutes and 5 bits of seconds divided by two.
+    ///
+    /// # Returns
+    ///
+    /// * The decoded date and time.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn from_fat(date: u16, time: u16) -> Self {
+        Self {
+            year: 1980 + (date >> 9),
+            month: (date >> 5 & 0x0F) as u8,
+            day: (date & 0x1F) as u8,
+            hours: (time >> 11) as u8,
+            minutes: (time >> 5 & 0x3F) as u8,
+            seconds: (time & 0x1F) as u8 * 2,
+        }
+    }
+}
+
+impl fmt::Display for Timestamp {
+    /// Formats the date and time as `YYYY-MM-DD HH:MM`.
+    ///
+    /// # Arguments
+    ///
+    /// * `f` - The formatter.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - The result of the operation.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{year:04}-{month:02}-{day:02} {hours:02}:{minutes:02}",
+            year = self.year,
+            month = self.month,
+            day = self.day,
+            hours = self.hours,
+            minutes = self.minutes
+        )
+    }
+}
+
+/// An entry of a directory listing.
+///
+/// # Fields
+///
+/// * `name` - The name, like `README.TXT`.
+/// * `attributes` - The attributes, like [`DIRECTORY`].
+/// * `size` - The size, which is 0 for directories.
+/// * `first_cluster` - The first cluster, or 0 for the root directory.
+/// * `created` - When the entry was created.
+/// * `modified` - When the entry was last modified.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub name: String,
+    pub attributes: u8,
+    pub size: u32,
+    pub first_cluster: u32,
+    pub created: Timestamp,
+    pub modified: Timestamp,
+}
+
+impl Entry {
+    /// Creates a new directory listing entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name.
+    /// * `entry` - The directory entry on disk.
+    ///
+    /// # Returns
+    ///
+    /// * The new directory listing entry.
+    #[must_use]
+    pub const fn new(name: String, entry: &DirectoryEntry) -> Self {
+        Self {
+            name,
+            attributes: entry.attributes,
+            size: entry.file_size,
+            first_cluster: entry.first_cluster,
+            created: Timestamp::from_fat(entry.creation_date, entry.creation_time),
+            modified: Timestamp::from_fat(entry.last_modified_date, entry.last_modified_time),
+        }
+    }
+
+    /// Checks if the entry is a directory.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether or not the entry is a directory.
+    #[must_use]
+    pub const fn is_dir(&self) -> bool {
+        self.attributes & DIRECTORY != 0
+    }
+}
+
+/// The characters that aren't allowed in 8.3 names, besides control characters and the space.
+const INVALID_83_CHARACTERS: &[u8] = b"\"*+,./:;<=>?[\\]|";
+
+/// Encodes a name to the space padded 8.3 form stored in directory entries.
+///
+/// # Arguments
+///
+/// * `name` - The name, like `readme.txt`.
 ///
 /// # Returns
 ///
-/// * The FAT file system.
+/// * If the name fits the 8.3 form, the 11 name bytes, like `README  TXT`.
+/// * Otherwise, `None`.
+///
+/// # Notes
+///
+/// * Names are case insensitive, so they're stored in upper case.
+/// * The extension starts after the last dot, which isn't stored.
+/// * `.` and `..` are encoded as they are, since they name the current and parent directory.
 #[must_use]
-pub fn init() -> Fat {
-    // Get the boot sector.
-    let boot_sector = BootSector::new(
-        512,
-        1,
-        1,
-        2,
-        512,
-        0,
-        0,
-        0,
-        0,
-        0,
-        0,
+pub fn encode_83(name: &str) -> Option<[u8; 11]> {
+    let mut raw = [b' '; 11];
+    if name == "." || name == ".." {
+        raw[..name.len()].copy_from_slice(name.as_bytes());
+
+        return Some(raw);
+    }
+
+    let (base, extension) = name.rsplit_once('.').unwrap_or((name, ""));
+    if base.is_empty() || base.len() > 8 || extension.len() > 3 {
+        return None;
+    }
+
+    let (base_field, extension_field) = raw.split_at_mut(8);
+    for (part, field) in [(base, base_field), (extension, extension_field)] {
+        for (byte, slot) in part.bytes().zip(field.iter_mut()) {
+            if !byte.is_ascii_graphic() || INVALID_83_CHARACTERS.contains(&byte) {
+                return None;
+            }
+
+            *slot = byte.to_ascii_uppercase();
+        }
+    }
+
+    Some(raw)
+}
+
+/// Decodes the space padded 8.3 name of a directory entry.
+///
+/// # Arguments
+///
+/// * `raw` - The 11 name bytes, 8 for the base name and 3 for the extension.
+///
+/// # Returns
+///
+/// * `String` - The name, with a dot before the extension if there is one.
+///
+/// # Notes
+///
+/// * A leading `0x05` stands for `0xE5`, which would otherwise mark the entry as deleted.
+#[must_use]
+pub fn decode_83(raw: &[u8; 11]) -> String {
+    let decode = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .enumerate()
+            .map(|(i, &byte)| match (i, byte) {
+                (0, 0x05) => char::from(0xE5),
+                _ => char::from(byte),
+            })
+            .collect::<String>()
+    };
+
+    let base = decode(&raw[..8]);
+    let extension = decode(&raw[8..]);
+
+    let (base, extension) = (base.trim_end(), extension.trim_end());
+    if extension.is_empty() {
+        base.to_string()
+    } else {
+        format!("{base}.{extension}")
+    }
+}
+
+/// Initializes the FAT file system.
+///
+/// # Arguments
+///
+/// * `device` - The block device the file system is stored on.
+///
+/// # Returns
+///
+/// * `Result<Fat, Error>` - The FAT file system.
+///
+/// # Errors
+///
+/// * If the file system can't be mounted.
+pub fn init(device: &mut dyn BlockDevice) -> Result<Fat<'_>, Error> {
+    Fat::mount(device)
+}
+
+/// Tests that the FAT12 test image is mounted from a RAM disk correctly.
+///
+/// # Panics
+///
+/// * If mounting fails.
+/// * If the boot sector, file allocation table or root directory don't match the image.
+#[test_case]
+fn test_mount_ramdisk() {
+    use crate::dev::ramdisk::{RamDisk, IMAGE};
+
+    let mut disk = RamDisk::from_image(IMAGE);
+    let fat = Fat::mount(&mut disk).expect("Failed to mount the test image!");
+
+    assert_eq!(fat.boot_sector.fat_type().ok(), Some(FatType::Fat12));
+    assert_eq!(fat.boot_sector.first_data_sector(), 4);
+
+    // `DOCS/README.TXT` spans clusters 4 and 5.
+    assert_eq!(fat.fat.next_cluster(4), Some(5));
+    assert_eq!(fat.fat.next_cluster(5), None);
+
+    // The volume label, `HELLO.TXT` and `DOCS`.
+    assert_eq!(fat.root_dir.entries[1].name(), "HELLO.TXT");
+    assert_eq!(fat.root_dir.entries[1].file_size, 14);
+    assert_eq!(fat.root_dir.entries[2].name(), "DOCS");
+    assert_eq!(fat.root_dir.entries[2].attributes, DIRECTORY);
+    assert_eq!(fat.root_dir.entries[2].first_cluster, 3);
+}
+
+/// Tests that files spanning multiple clusters are read in full.
+///
+/// # Panics
+///
+/// * If mounting or reading fails.
+/// * If the contents don't match the file in the image.
+#[test_case]
+fn test_read_contents() {
+    use crate::dev::ramdisk::{RamDisk, IMAGE};
+
+    let mut disk = RamDisk::from_image(IMAGE);
+    let mut fat = Fat::mount(&mut disk).expect("Failed to mount the test image!");
+
+    // `DOCS/README.TXT` is 800 bytes, spread over two clusters.
+    let file = File::new("README.TXT", 800, 4);
+    let contents = fat.read_contents(&file).expect("Failed to read the file!");
+
+    assert_eq!(contents.len(), 800);
+    assert!(contents.starts_with(b"This file spans more than one cluster"));
+    assert!(contents.ends_with(b"follows the cluster chain.\n"));
+}
+
+/// Tests that reading part of a file matches the same part of its whole contents, across sectors and clusters.
+///
+/// # Panics
+///
+/// * If mounting or reading fails.
+/// * If a part doesn't match the whole contents.
+/// * If reading past the end returns anything.
+#[test_case]
+fn test_read_at() {
+    use crate::dev::ramdisk::{RamDisk, IMAGE};
+
+    let mut disk = RamDisk::from_image(IMAGE);
+    let mut fat = Fat::mount(&mut disk).expect("Failed to mount the test image!");
+
+    // `DOCS/README.TXT` is 800 bytes, spread over two clusters.
+    let file = File::new("README.TXT", 800, 4);
+    let contents = fat.read_contents(&file).expect("Failed to read the file!");
+
+    let mut buffer = [0; 100];
+    for offset in [0, 1, 450, 510, 750] {
+        let count = fat
+            .read_at(&file, offset, &mut buffer)
+            .expect("Failed to read part of the file!");
+        let expected = &contents[offset..contents.len().min(offset + buffer.len())];
+
+        assert_eq!(&buffer[..count], expected);
+    }
+
+    assert_eq!(
+        fat.read_at(&file, 800, &mut buffer)
+            .expect("Failed to read at the end!"),
+        0
+    );
+}
+
+/// Tests that data written to the device survives a sync and a remount.
+///
+/// # Panics
+///
+/// * If mounting, writing, syncing or reading fails.
+/// * If the contents read back don't match the written data.
+#[test_case]
+fn test_sync_remount() {
+    use crate::dev::ramdisk::{RamDisk, IMAGE};
+
+    let mut disk = RamDisk::from_image(IMAGE);
+    let mut fat = Fat::mount(&mut disk).expect("Failed to mount the test image!");
+
+    // Overwrite the contents of `HELLO.TXT` in place, keeping its size.
+    let entry = fat.root_dir.entries[1];
+    let lba = fat
+        .boot_sector
+        .cluster_sector(entry.first_cluster)
+        .expect("`HELLO.TXT` has no data cluster!");
+
+    let mut sector = vec![0; fat.device.block_size()];
+    sector[..14].copy_from_slice(b"Synced, world!");
+    fat.device
+        .write_block(lba, &sector)
+        .expect("Failed to write the file!");
+    fat.sync().expect("Failed to sync the file system!");
+    drop(fat);
+
+    let mut fat = Fat::mount(&mut disk).expect("Failed to remount the test image!");
+    let file = File::new("HELLO.TXT", entry.file_size, entry.first_cluster);
+
+    assert_eq!(
+        fat.read_contents(&file).expect("Failed to read the file!"),
+        b"Synced, world!"
+    );
+}
+
+/// Tests that directories are listed with their names, sizes and timestamps.
+///
+/// # Panics
+///
+/// * If mounting fails.
+/// * If the root directory or `DOCS` aren't listed as in the image.
+#[test_case]
+fn test_read_dir() {
+    use crate::dev::ramdisk::{RamDisk, IMAGE};
+
+    let mut disk = RamDisk::from_image(IMAGE);
+    let mut fat = Fat::mount(&mut disk).expect("Failed to mount the test image!");
+
+    // The volume label isn't listed.
+    let root = fat
+        .read_dir("/")
+        .expect("Failed to read the root directory!");
+    let names = root
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["HELLO.TXT", "DOCS"]);
+    assert_eq!(root[0].size, 14);
+    assert!(root[1].is_dir());
+
+    // Every entry in the image was created at noon on the 1st of June 2023.
+    assert_eq!(
+        root[0].modified,
+        Timestamp {
+            year: 2023,
+            month: 6,
+            day: 1,
+            hours: 12,
+            minutes: 0,
+            seconds: 0,
+        }
+    );
+    assert_eq!(format!("{}", root[0].created), "2023-06-01 12:00");
+
+    // Lookups ignore case, and `..` leads back to the root directory.
+    let docs = fat.read_dir("docs").expect("Failed to read `DOCS`!");
+    let names = docs
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, [".", "..", "README.TXT"]);
+    assert_eq!(docs[2].size, 800);
+    assert_eq!(
+        fat.read_dir("DOCS/..").map(|entries| entries.len()),
+        Some(2)
+    );
+
+    assert!(fat.read_dir("HELLO.TXT").is_none());
+    assert!(fat.read_dir("MISSING").is_none());
+}
+
+/// Tests that walking a directory visits the same entries as listing it.
+///
+/// # Panics
+///
+/// * If mounting fails.
+/// * If the entries of `DOCS` aren't visited in order.
+/// * If a missing directory is walked.
+#[test_case]
+fn test_for_each_entry() {
+    use crate::dev::ramdisk::{RamDisk, IMAGE};
+
+    let mut disk = RamDisk::from_image(IMAGE);
+    let mut fat = Fat::mount(&mut disk).expect("Failed to mount the test image!");
+
+    let mut names = Vec::new();
+    let mut size = 0;
+    fat.for_each_entry("/DOCS", |entry| {
+        names.push(entry.name.clone());
+        size += entry.size;
+    })
+    .expect("Failed to walk `DOCS`!");
+    assert_eq!(names, [".", "..", "README.TXT"]);
+    assert_eq!(size, 800);
+
+    assert!(fat.for_each_entry("MISSING", |_| {}).is_none());
+}
+
+/// Tests that changes to the file allocation table survive a sync and a remount, in every copy of it.
+///
+/// # Panics
+///
+/// * If mounting, syncing or reading fails.
+/// * If the table read back doesn't match the changed one.
+/// * If the copies of the table differ.
+#[test_case]
+fn test_sync_fat() {
+    use crate::dev::ramdisk::{RamDisk, IMAGE};
+
+    let mut disk = RamDisk::from_image(IMAGE);
+    let mut fat = Fat::mount(&mut disk).expect("Failed to mount the test image!");
+    let fat_count = fat.boot_sector.fat_count;
+    let (first_sector, sectors) = (
+        u64::from(fat.boot_sector.reserved_sectors),
+        u64::from(fat.boot_sector.sectors_per_fat),
     );
 
-    // Get the FAT table.
-    let fat = FatTable::new([0; 128]);
+    // Free `DOCS/README.TXT`, and chain two new clusters.
+    assert_eq!(fat.fat.free_chain(4), 2);
+    let first = fat
+        .fat
+        .alloc_cluster()
+        .expect("Failed to allocate a cluster!");
+    let second = fat
+        .fat
+        .alloc_cluster()
+        .expect("Failed to allocate a cluster!");
+    fat.fat
+        .link(first, second)
+        .expect("Failed to link the clusters!");
+    let entries = fat.fat.entries.clone();
+
+    assert!(fat.fat.is_dirty());
+    fat.sync().expect("Failed to sync the file system!");
+    assert!(!fat.fat.is_dirty());
+    drop(fat);
+
+    let fat = Fat::mount(&mut disk).expect("Failed to remount the test image!");
+    assert_eq!(fat.fat.entries, entries);
+    drop(fat);
+
+    let mut copies = vec![vec![0; 512]; usize::from(fat_count)];
+    for (copy, sector) in (0..).zip(copies.iter_mut()) {
+        disk.read_block(first_sector + copy * sectors, sector)
+            .expect("Failed to read a copy of the table!");
+    }
+    assert!(copies.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+/// Tests that the free space is counted from the file allocation table.
+///
+/// # Panics
+///
+/// * If mounting fails.
+/// * If the free or total space doesn't match the image.
+#[test_case]
+fn test_free_space() {
+    use crate::dev::ramdisk::{RamDisk, IMAGE};
+
+    let mut disk = RamDisk::from_image(IMAGE);
+    let fat = Fat::mount(&mut disk).expect("Failed to mount the test image!");
 
-    // Get the root directory.
-    let root_dir = RootDirectory::new([DirectoryEntry::default(); 512]);
+    // 28 clusters of one sector, of which `HELLO.TXT`, `DOCS` and `DOCS/README.TXT` use 4.
+    assert_eq!(fat.free_space(), (24 * 512, 28 * 512));
+}
+
+/// Tests allocating, linking and freeing a chain of clusters.
+///
+/// # Panics
+///
+/// * If a cluster can't be allocated or linked.
+/// * If the chain isn't followed in order.
+/// * If freeing the chain doesn't leave every cluster free.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_fat_table_chain() {
+    let mut fat = FatTable::new(vec![END_OF_CHAIN, END_OF_CHAIN, 0, 0, 0, 0]);
+
+    let first = fat.alloc_cluster().expect("Failed to allocate a cluster!");
+    let second = fat.alloc_cluster().expect("Failed to allocate a cluster!");
+    let third = fat.alloc_cluster().expect("Failed to allocate a cluster!");
+    assert_eq!((first, second, third), (2, 3, 4));
+
+    fat.link(first, second)
+        .expect("Failed to link the clusters!");
+    fat.link(second, third)
+        .expect("Failed to link the clusters!");
+    assert!(fat.link(third, 6).is_err());
+
+    assert_eq!(fat.next_cluster(first), Some(second));
+    assert_eq!(fat.next_cluster(second), Some(third));
+    assert_eq!(fat.next_cluster(third), None);
+
+    // Only one cluster is left.
+    assert_eq!(fat.alloc_cluster(), Some(5));
+    assert_eq!(fat.alloc_cluster(), None);
+    assert_eq!(fat.free_chain(5), 1);
+
+    assert_eq!(fat.free_chain(first), 3);
+    assert!((2..6).all(|cluster| fat.is_free(cluster)));
+}
+
+/// Tests encoding and decoding 8.3 names.
+///
+/// # Panics
+///
+/// * If a name doesn't encode or decode to its known form.
+/// * If a name that doesn't fit is encoded.
+#[test_case]
+fn test_83_names() {
+    let pairs: [(&str, &[u8; 11], &str); 5] = [
+        ("readme.txt", b"README  TXT", "README.TXT"),
+        ("DOCS", b"DOCS       ", "DOCS"),
+        ("a.b", b"A       B  ", "A.B"),
+        (".", b".          ", "."),
+        ("..", b"..         ", ".."),
+    ];
+    for (name, raw, decoded) in pairs {
+        assert_eq!(encode_83(name).as_ref(), Some(raw));
+        assert_eq!(decode_83(raw), decoded);
+    }
+
+    for name in [
+        "",
+        ".hidden",
+        "toolongname.txt",
+        "file.text",
+        "a b.txt",
+        "a.b.c",
+        "what?",
+    ] {
+        assert_eq!(encode_83(name), None);
+    }
 
-    // Return the FAT file system.
-    Fat::new(boot_sector, fat, root_dir)
+    assert_eq!(decode_83(b"\x05BC     TXT"), "\u{E5}BC.TXT");
 }
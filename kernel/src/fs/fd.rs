@@ -0,0 +1,416 @@
+use alloc::string::String;
+use spin::Mutex;
+
+use crate::errors::Error;
+use crate::fs::fat::File;
+use crate::fs::FILE_SYSTEM;
+use crate::print;
+use crate::sys::task::keyboard;
+
+/// The maximum number of open file handles.
+pub const MAX_FILE_HANDLES: usize = 256;
+
+/// The file descriptor of the standard input.
+pub const STDIN: usize = 0;
+/// The file descriptor of the standard output.
+pub const STDOUT: usize = 1;
+/// The file descriptor of the standard error.
+pub const STDERR: usize = 2;
+
+/// The open file handles, indexed by file descriptor.
+///
+/// # Notes
+///
+/// * There are no processes yet, so the table is shared by everything instead of being per process.
+static HANDLES: Mutex<[Option<Handle>; MAX_FILE_HANDLES]> = Mutex::new(standard_handles());
+
+/// What a file descriptor refers to.
+///
+/// # Variants
+///
+/// * `Keyboard` - The keyboard, used for the standard input.
+/// * `Screen` - The VGA text buffer, used for the standard output and error.
+/// * `File` - A file on the mounted file system, with the offset the next read starts at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handle {
+    Keyboard,
+    Screen,
+    File {
+        first_cluster: u32,
+        size: u32,
+        offset: usize,
+    },
+}
+
+/// Where a seek is relative to.
+///
+/// # Variants
+///
+/// * `Set` - The start of the file.
+/// * `Current` - The current offset.
+/// * `End` - The end of the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Whence {
+    Set = 0,
+    Current = 1,
+    End = 2,
+}
+
+impl TryFrom<usize> for Whence {
+    type Error = Error;
+
+    /// Converts a `whence` argument, as passed to the `Seek` system call.
+    fn try_from(whence: usize) -> Result<Self, Error> {
+        match whence {
+            0 => Ok(Self::Set),
+            1 => Ok(Self::Current),
+            2 => Ok(Self::End),
+            _ => Err(Error::FileSystem(alloc::format!(
+                "Invalid whence: {whence}!"
+            ))),
+        }
+    }
+}
+
+/// Creates a handle table with only the standard streams open.
+///
+/// # Returns
+///
+/// * `[Option<Handle>; MAX_FILE_HANDLES]` - The handle table.
+const fn standard_handles() -> [Option<Handle>; MAX_FILE_HANDLES] {
+    let mut handles = [None; MAX_FILE_HANDLES];
+
+    handles[STDIN] = Some(Handle::Keyboard);
+    handles[STDOUT] = Some(Handle::Screen);
+    handles[STDERR] = Some(Handle::Screen);
+
+    handles
+}
+
+/// Gets the handle of the given file descriptor.
+///
+/// # Arguments
+///
+/// * `fd` - The file descriptor.
+///
+/// # Returns
+///
+/// * `Result<Handle, Error>` - The handle.
+///
+/// # Errors
+///
+/// * If the file descriptor isn't open.
+fn handle(fd: usize) -> Result<Handle, Error> {
+    HANDLES
+        .lock()
+        .get(fd)
+        .copied()
+        .flatten()
+        .ok_or_else(|| Error::FileSystem(alloc::format!("Bad file descriptor: {fd}!")))
+}
+
+/// Stores the given handle in the lowest free file descriptor.
+///
+/// # Arguments
+///
+/// * `handle` - The handle.
+///
+/// # Returns
+///
+/// * `Result<usize, Error>` - The file descriptor.
+///
+/// # Errors
+///
+/// * If all [`MAX_FILE_HANDLES`] file descriptors are in use.
+fn insert(handle: Handle) -> Result<usize, Error> {
+    let mut handles = HANDLES.lock();
+
+    let (fd, slot) = handles
+        .iter_mut()
+        .enumerate()
+        .find(|(_, slot)| slot.is_none())
+        .ok_or_else(|| Error::FileSystem("Too many open files!".into()))?;
+
+    *slot = Some(handle);
+
+    Ok(fd)
+}
+
+/// Opens the file at the given path.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file.
+///
+/// # Returns
+///
+/// * `Result<usize, Error>` - The file descriptor.
+///
+/// # Errors
+///
+/// * If no file system is mounted.
+/// * If the file doesn't exist.
+/// * If all [`MAX_FILE_HANDLES`] file descriptors are in use.
+///
+/// # Notes
+///
+/// * The FAT layer is read only, so files can only be read from.
+pub fn open(path: &str) -> Result<usize, Error> {
+    let file = FILE_SYSTEM
+        .lock()
+        .as_ref()
+        .ok_or_else(|| Error::FileSystem("No file system is mounted!".into()))?
+        .read_file(path)
+        .ok_or_else(|| Error::FileSystem(alloc::format!("No such file: '{path}'!")))?;
+
+    insert(Handle::File {
+        first_cluster: file.first_cluster,
+        size: file.size,
+        offset: 0,
+    })
+}
+
+/// Reads from the given file descriptor into the given buffer.
+///
+/// # Arguments
+///
+/// * `fd` - The file descriptor.
+/// * `buffer` - The buffer to read into.
+///
+/// # Returns
+///
+/// * `Result<usize, Error>` - The number of bytes read.
+///
+/// # Errors
+///
+/// * If the file descriptor isn't open.
+/// * If the file descriptor can't be read from.
+///
+/// # Notes
+///
+/// * Reading the keyboard blocks until a line is entered, and anything not fitting in the buffer is discarded.
+pub fn read(fd: usize, buffer: &mut [u8]) -> Result<usize, Error> {
+    match handle(fd)? {
+        Handle::Keyboard => {
+            // Interrupted with `Ctrl+C`, so nothing was read.
+            let Some(mut line) = keyboard::read_line_blocking() else {
+                return Ok(0);
+            };
+            line.push('\n');
+
+            let count = line.len().min(buffer.len());
+            buffer[..count].copy_from_slice(&line.as_bytes()[..count]);
+
+            Ok(count)
+        }
+        Handle::File {
+            first_cluster,
+            size,
+            offset,
+        } => {
+            // Only the clusters covering the buffer are read, and nothing past the end.
+            let count = FILE_SYSTEM
+                .lock()
+                .as_mut()
+                .ok_or_else(|| Error::FileSystem("No file system is mounted!".into()))?
+                .read_at(&File::new("", size, first_cluster), offset, buffer)?;
+
+            set_offset(fd, offset + count);
+
+            Ok(count)
+        }
+        Handle::Screen => Err(Error::FileSystem(alloc::format!(
+            "File descriptor {fd} isn't readable!"
+        ))),
+    }
+}
+
+/// Writes the given buffer to the given file descriptor.
+///
+/// # Arguments
+///
+/// * `fd` - The file descriptor.
+/// * `buffer` - The buffer to write from.
+///
+/// # Returns
+///
+/// * `Result<usize, Error>` - The number of bytes written.
+///
+/// # Errors
+///
+/// * If the file descriptor isn't open.
+/// * If the file descriptor can't be written to.
+///
+/// # Notes
+///
+/// * Writing files is deferred until the FAT layer can write file contents and directory entries, so only the screen
+///   can be written to for now.
+pub fn write(fd: usize, buffer: &[u8]) -> Result<usize, Error> {
+    let text = String::from_utf8_lossy(buffer);
+
+    match handle(fd)? {
+        Handle::Screen => {
+            print!("{text}");
+
+            Ok(buffer.len())
+        }
+        Handle::Keyboard | Handle::File { .. } => Err(Error::FileSystem(alloc::format!(
+            "File descriptor {fd} isn't writable!"
+        ))),
+    }
+}
+
+/// Moves the offset of the given file descriptor, where the next read starts.
+///
+/// # Arguments
+///
+/// * `fd` - The file descriptor.
+/// * `offset` - The offset, relative to `whence`.
+/// * `whence` - Where the offset is relative to.
+///
+/// # Returns
+///
+/// * `Result<usize, Error>` - The new offset, from the start of the file.
+///
+/// # Errors
+///
+/// * If the file descriptor isn't open.
+/// * If the file descriptor isn't a file.
+/// * If the new offset would be before the start of the file.
+///
+/// # Notes
+///
+/// * Seeking past the end is allowed, and reads there return nothing. The FAT layer is read only, so the file can't
+///   be extended.
+pub fn seek(fd: usize, offset: isize, whence: Whence) -> Result<usize, Error> {
+    let Handle::File {
+        size,
+        offset: current,
+        ..
+    } = handle(fd)?
+    else {
+        return Err(Error::FileSystem(alloc::format!(
+            "File descriptor {fd} isn't seekable!"
+        )));
+    };
+
+    let base = match whence {
+        Whence::Set => 0,
+        Whence::Current => current,
+        Whence::End => size as usize,
+    };
+
+    let offset = base.checked_add_signed(offset).ok_or_else(|| {
+        Error::FileSystem(alloc::format!("Invalid offset for file descriptor {fd}!"))
+    })?;
+    set_offset(fd, offset);
+
+    Ok(offset)
+}
+
+/// Sets the offset of the file behind the given file descriptor, if it still is one.
+///
+/// # Arguments
+///
+/// * `fd` - The file descriptor.
+/// * `offset` - The new offset.
+fn set_offset(fd: usize, offset: usize) {
+    if let Some(Some(Handle::File {
+        offset: current, ..
+    })) = HANDLES.lock().get_mut(fd)
+    {
+        *current = offset;
+    }
+}
+
+/// Closes the given file descriptor.
+///
+/// # Arguments
+///
+/// * `fd` - The file descriptor.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the file descriptor isn't open.
+pub fn close(fd: usize) -> Result<(), Error> {
+    handle(fd)?;
+
+    HANDLES.lock()[fd] = None;
+
+    Ok(())
+}
+
+/// Duplicates the given file descriptor into the lowest free one.
+///
+/// The duplicate starts at the same offset, but moves independently.
+///
+/// # Arguments
+///
+/// * `fd` - The file descriptor.
+///
+/// # Returns
+///
+/// * `Result<usize, Error>` - The new file descriptor.
+///
+/// # Errors
+///
+/// * If the file descriptor isn't open.
+/// * If all [`MAX_FILE_HANDLES`] file descriptors are in use.
+pub fn duplicate(fd: usize) -> Result<usize, Error> {
+    insert(handle(fd)?)
+}
+
+/// Tests that duplicated file descriptors can be closed independently.
+///
+/// # Panics
+///
+/// * If duplicating or closing fails.
+/// * If the closed file descriptor can still be written to.
+#[test_case]
+fn test_duplicate_and_close() {
+    let fd = duplicate(STDOUT).expect("Failed to duplicate stdout!");
+    assert_ne!(fd, STDOUT);
+
+    close(fd).expect("Failed to close the duplicate!");
+
+    assert!(write(fd, b"").is_err());
+    assert!(write(STDOUT, b"").is_ok());
+}
+
+/// Tests that reads advance the offset of a file, and that seeking moves it.
+///
+/// # Panics
+///
+/// * If opening, reading or seeking the test image's `HELLO.TXT` fails.
+/// * If a read doesn't continue where the previous one ended.
+/// * If seeking to an invalid offset, or seeking the screen, succeeds.
+#[test_case]
+fn test_seek() {
+    let Ok(fd) = open("HELLO.TXT") else {
+        // The test kernel may not have mounted the RAM disk.
+        return;
+    };
+
+    let mut all = [0; 64];
+    let size = read(fd, &mut all).expect("Failed to read the file!");
+    assert_eq!(
+        read(fd, &mut all[size..]).expect("Failed to read at the end!"),
+        0
+    );
+
+    assert_eq!(seek(fd, 1, Whence::Set).expect("Failed to seek!"), 1);
+    let mut first = [0; 2];
+    assert_eq!(read(fd, &mut first).expect("Failed to read!"), 2);
+    assert_eq!(first, all[1..3]);
+
+    assert_eq!(seek(fd, -1, Whence::Current).expect("Failed to seek!"), 2);
+    assert_eq!(seek(fd, 0, Whence::End).expect("Failed to seek!"), size);
+    assert!(seek(fd, -1, Whence::Set).is_err());
+    assert!(seek(STDOUT, 0, Whence::Set).is_err());
+
+    close(fd).expect("Failed to close the file!");
+}
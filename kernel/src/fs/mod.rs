@@ -1,15 +1,150 @@
-use crate::fs::fat::Fat;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use spin::Mutex;
+
+use crate::dev::BlockDevice;
+use crate::errors::Error;
+use crate::fs::fat::{Entry, Fat};
 use crate::println;
 
 pub mod fat;
+pub mod fd;
+
+/// The mounted file system, if any.
+pub static FILE_SYSTEM: Mutex<Option<Fat<'static>>> = Mutex::new(None);
 
-/// Initializes the file system.
-/// 
+/// Initializes the file system by mounting the given block device.
+///
+/// # Arguments
+///
+/// * `device` - The block device the file system is stored on.
+///
 /// # Returns
-/// 
-/// * The FAT file system.
-#[must_use]
-pub fn init() -> Fat {
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the file system can't be mounted.
+pub fn init(device: &'static mut dyn BlockDevice) -> Result<(), Error> {
     println!("[INFO]: Initializing the FAT file system...");
-    fat::init()
+    *FILE_SYSTEM.lock() = Some(fat::init(device)?);
+
+    Ok(())
+}
+
+/// Writes any changes to the mounted file system back to its device.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If syncing the file system fails.
+///
+/// # Notes
+///
+/// * If no file system is mounted, there's nothing to sync.
+pub fn sync() -> Result<(), Error> {
+    FILE_SYSTEM.lock().as_mut().map_or(Ok(()), Fat::sync)
+}
+
+/// Reads the contents of the file at the given path on the mounted file system.
+///
+/// # Arguments
+///
+/// * `path` - The path to the file.
+///
+/// # Returns
+///
+/// * `Result<Vec<u8>, Error>` - The contents of the file.
+///
+/// # Errors
+///
+/// * If no file system is mounted.
+/// * If the file doesn't exist.
+/// * If reading the file fails.
+pub fn read(path: &str) -> Result<Vec<u8>, Error> {
+    let mut file_system = FILE_SYSTEM.lock();
+    let file_system = file_system
+        .as_mut()
+        .ok_or_else(|| Error::FileSystem("No file system is mounted!".to_string()))?;
+
+    let file = file_system
+        .read_file(path)
+        .ok_or_else(|| Error::FileSystem(format!("No such file: '{path}'!")))?;
+
+    file_system.read_contents(&file)
+}
+
+/// Lists the directory at the given path on the mounted file system.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory.
+///
+/// # Returns
+///
+/// * `Result<Vec<Entry>, Error>` - The entries of the directory.
+///
+/// # Errors
+///
+/// * If no file system is mounted.
+/// * If the directory doesn't exist, or can't be read.
+pub fn read_dir(path: &str) -> Result<Vec<Entry>, Error> {
+    FILE_SYSTEM
+        .lock()
+        .as_mut()
+        .ok_or_else(|| Error::FileSystem("No file system is mounted!".to_string()))?
+        .read_dir(path)
+        .ok_or_else(|| Error::FileSystem(format!("No such directory: '{path}'!")))
+}
+
+/// Calls a function with every entry of the directory at the given path on the mounted file system.
+///
+/// Unlike [`read_dir`], the entries aren't collected, so listing a large directory doesn't pressure the heap.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory.
+/// * `f` - The function to call with every entry.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If no file system is mounted.
+/// * If the directory doesn't exist, or can't be read.
+///
+/// # Notes
+///
+/// * The file system stays locked while `f` runs, so it mustn't use the file system itself.
+pub fn for_each_entry(path: &str, f: impl FnMut(&Entry)) -> Result<(), Error> {
+    FILE_SYSTEM
+        .lock()
+        .as_mut()
+        .ok_or_else(|| Error::FileSystem("No file system is mounted!".to_string()))?
+        .for_each_entry(path, f)
+        .ok_or_else(|| Error::FileSystem(format!("No such directory: '{path}'!")))
+}
+
+/// Gets the free and total space of the mounted file system.
+///
+/// # Returns
+///
+/// * `Result<(u64, u64), Error>` - The free and the total number of bytes.
+///
+/// # Errors
+///
+/// * If no file system is mounted.
+pub fn free_space() -> Result<(u64, u64), Error> {
+    FILE_SYSTEM
+        .lock()
+        .as_ref()
+        .map(Fat::free_space)
+        .ok_or_else(|| Error::FileSystem("No file system is mounted!".to_string()))
 }
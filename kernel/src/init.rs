@@ -1,67 +1,335 @@
-use crate::dev::ata;
+use alloc::boxed::Box;
+
+use crate::boot_args::{self, BootArgs, LogLevel};
+use crate::dev::ramdisk::{self, RamDisk};
+use crate::dev::{ata, framebuffer};
 use crate::errors::Error;
 use crate::sys::task::executor::Executor;
-use crate::sys::task::{keyboard, Task};
-use crate::sys::{gdt, idt, pic, time};
-use crate::{dev, fs, KERNEL_VERSION};
-use crate::{mem, println};
+use crate::sys::task::keyboard;
+use crate::sys::{calls, fpu, gdt, idt, interrupt_controller, pic, time};
+use crate::vga_buffer::Color;
+use crate::{dev, fs, serial, vga_buffer, KERNEL_VERSION};
+use crate::{mem, print, println, serial_println};
 use bootloader::BootInfo;
 
+/// Prints an informational message, unless the log level hides it.
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if boot_args::logs(LogLevel::Info) {
+            println!("[INFO]: {}", format_args!($($arg)*));
+        }
+    };
+}
+
+/// The state of a stage, as shown on the boot screen.
+///
+/// # Variants
+///
+/// * `Running` - The stage has started.
+/// * `Ok` - The stage has finished.
+/// * `Failed` - The stage has failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Running,
+    Ok,
+    Failed,
+}
+
+impl Status {
+    /// Gets the label shown in front of the stage name.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The label, all of the same width so the stage names line up.
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Running => "[ .. ]",
+            Self::Ok => "[ OK ]",
+            Self::Failed => "[FAIL]",
+        }
+    }
+
+    /// Gets the color the label is shown in.
+    ///
+    /// # Returns
+    ///
+    /// * `Color` - The foreground color.
+    const fn color(self) -> Color {
+        match self {
+            Self::Running => Color::Yellow,
+            Self::Ok => Color::LightGreen,
+            Self::Failed => Color::LightRed,
+        }
+    }
+}
+
+/// Shows the status of a stage on the boot screen.
+///
+/// A running stage is left on the line, so a hung boot shows which stage is stuck. Once it's done, its line is
+/// overwritten in place, unless the stage printed something in the meantime.
+///
+/// # Arguments
+///
+/// * `name` - The name of the stage.
+/// * `status` - The status of the stage.
+/// * `line` - Where the running line of the stage ended, if it's still the last thing on screen.
+///
+/// # Returns
+///
+/// * `(usize, usize)` - Where the line ended.
+fn show_status(name: &str, status: Status, line: Option<(usize, usize)>) -> (usize, usize) {
+    if line.is_some_and(|line| line == vga_buffer::cursor()) {
+        print!("\r");
+    } else if status != Status::Running && line.is_some() {
+        // The stage printed over its running line, so start a fresh one.
+        println!();
+    }
+
+    vga_buffer::print_colored(
+        status.color(),
+        format_args!("{label}", label = status.label()),
+    );
+    print!(" {name}");
+
+    let end = vga_buffer::cursor();
+    if status != Status::Running {
+        println!();
+    }
+
+    end
+}
+
+/// A named step of the kernel initialization.
+///
+/// # Fields
+///
+/// * `name` - The name of the stage, used in logs and errors.
+/// * `message` - What the stage does, logged to serial when it starts.
+/// * `run` - Runs the stage.
+struct Stage {
+    name: &'static str,
+    message: &'static str,
+    run: fn(&'static BootInfo) -> Result<(), Error>,
+}
+
+/// The initialization stages, in the order they run.
+///
+/// # Notes
+///
+/// * The order matters. The FPU comes first since anything may use it, system calls need the GDT segments, the PIT
+///   needs interrupts, and everything that allocates needs memory management.
+const STAGES: &[Stage] = &[
+    Stage {
+        name: "fpu",
+        message: "Configuring FPU...",
+        run: |_| {
+            fpu::init();
+
+            Ok(())
+        },
+    },
+    Stage {
+        name: "gdt",
+        message: "Configuring GDT...",
+        run: |_| {
+            gdt::init();
+
+            Ok(())
+        },
+    },
+    Stage {
+        name: "syscall",
+        message: "Configuring system calls...",
+        run: |_| calls::syscall::init(),
+    },
+    Stage {
+        name: "idt",
+        message: "Configuring IDT...",
+        run: |_| {
+            idt::init();
+
+            Ok(())
+        },
+    },
+    Stage {
+        name: "pic",
+        message: "Configuring PIC...",
+        run: |_| {
+            unsafe { pic::PICS.lock().initialize() };
+
+            Ok(())
+        },
+    },
+    Stage {
+        name: "interrupts",
+        message: "Enabling interrupts...",
+        run: |_| {
+            x86_64::instructions::interrupts::enable();
+
+            Ok(())
+        },
+    },
+    Stage {
+        name: "time",
+        message: "Configuring PIT...",
+        run: |_| time::init(),
+    },
+    Stage {
+        name: "memory",
+        message: "Configuring memory management...",
+        run: mem::init,
+    },
+    Stage {
+        // Comes after memory management, since the APIC registers have to be mapped.
+        name: "apic",
+        message: "Selecting the interrupt controller...",
+        run: |_| interrupt_controller::init(),
+    },
+    Stage {
+        // Comes after memory management, since the framebuffer console copies the VGA font. Also allocates the buffer
+        // for output printed from interrupt handlers while the writer is busy.
+        name: "console",
+        message: "Selecting the console...",
+        run: |boot_info| {
+            vga_buffer::init_pending_output(vga_buffer::PENDING_OUTPUT_SIZE)?;
+
+            // Fall back on the VGA text buffer, which the bootloader hands over in, if there's no usable framebuffer.
+            let Some(framebuffer) = framebuffer::from_boot_info(boot_info) else {
+                return Ok(());
+            };
+            if let Err(err) = framebuffer::init(framebuffer) {
+                serial_println!(
+                    "[WARN]: Using the VGA text buffer, the framebuffer is unusable: {err}",
+                    err = err
+                );
+            }
+
+            Ok(())
+        },
+    },
+    Stage {
+        name: "keyboard",
+        message: "Configuring keyboard input...",
+        run: |_| {
+            keyboard::init(keyboard::SCANCODE_QUEUE_SIZE);
+
+            Ok(())
+        },
+    },
+    Stage {
+        name: "devices",
+        message: "Initializing device drivers...",
+        run: |_| {
+            dev::init();
+
+            Ok(())
+        },
+    },
+    Stage {
+        // Mounted from a RAM disk, since there's no FAT formatted drive to mount yet.
+        name: "filesystem",
+        message: "Initializing the file system...",
+        run: |_| {
+            let ramdisk = Box::leak(Box::new(RamDisk::from_image(ramdisk::IMAGE)));
+
+            fs::init(ramdisk)
+        },
+    },
+];
+
 /// Initializes the kernel.
 ///
+/// Runs the initialization stages in order, showing each one's progress on screen and logging its start and finish to
+/// serial.
+///
 /// # Arguments
 ///
 /// * `boot_info` - A reference to the boot information.
 ///
 /// # Returns
 ///
-/// * `Result<Executor, anyhow::Error>` - The executor.
+/// * `Result<Executor, Error>` - The executor.
 ///
 /// # Errors
 ///
-/// * If the heap memory allocator fails to initialize.
+/// * `Error::Init` - If a stage fails, naming the stage.
 pub fn start_kernel(boot_info: &'static BootInfo) -> Result<Executor, Error> {
-    println!(
-        "[INFO]: Initializing kernel v{version}...",
-        version = KERNEL_VERSION
-    );
+    // Bring up the serial port before anything else, so every stage can log to the host.
+    serial::init();
 
-    // Initialize the global descriptor table.
-    println!("[INFO]: Configuring GDT...");
-    gdt::init();
+    // Parse the boot arguments first, so they configure everything after.
+    let args = BootArgs::parse(boot_args::COMMAND_LINE).unwrap_or_else(|why| {
+        println!("[WARN]: Ignoring the command line: {why}!");
 
-    // Initialize the interrupt descriptor table.
-    println!("[INFO]: Configuring IDT...");
-    idt::init();
+        BootArgs::new()
+    });
+    boot_args::init(args);
 
-    // Initialize the programmable interrupt controller.
-    println!("[INFO]: Configuring PIC...");
-    unsafe { pic::PICS.lock().initialize() };
+    info!(
+        "Initializing kernel v{version}...",
+        version = KERNEL_VERSION
+    );
 
-    // Enable interrupts.
-    println!("[INFO]: Enabling interrupts...");
-    x86_64::instructions::interrupts::enable();
+    let splash = boot_args::logs(LogLevel::Info);
+    for stage in STAGES {
+        let line = splash.then(|| show_status(stage.name, Status::Running, None));
+        serial_println!(
+            "[INFO]: Stage `{name}` started: {message}",
+            name = stage.name,
+            message = stage.message
+        );
 
-    // Initialize the PIT.
-    println!("[INFO]: Configuring PIT...");
-    time::init()?;
+        if let Err(why) = (stage.run)(boot_info) {
+            show_status(stage.name, Status::Failed, line);
+            println!("{why}");
+            serial_println!(
+                "[ERROR]: Stage `{name}` failed: {err}",
+                name = stage.name,
+                err = why
+            );
 
-    // Initialize the memory management.
-    println!("[INFO]: Configuring memory management...");
-    mem::init(boot_info)?;
+            return Err(Error::Init {
+                stage: stage.name,
+                source: Box::new(why),
+            });
+        }
 
-    // Initialize the device drivers.
-    println!("[INFO]: Initializing device drivers...");
-    dev::init();
+        if splash {
+            show_status(stage.name, Status::Ok, line);
+        }
+        serial_println!("[INFO]: Stage `{name}` finished.", name = stage.name);
+    }
 
-    // Initialize the file system.
-    println!("[INFO]: Initializing the file system...");
-    let fs = fs::init();
-    
     // Initialize the task executor.
-    println!("[INFO]: Setting up the task executor...");
-    let mut executor = Executor::new();
-    executor.spawn(Task::new(keyboard::print_keypress()))?;
+    info!("Setting up the task executor...");
+    let executor = Executor::new();
 
     Ok(executor)
 }
+
+/// Tests that every stage has a unique name.
+///
+/// # Panics
+///
+/// * If two stages share a name.
+#[test_case]
+fn test_stage_names() {
+    for (index, stage) in STAGES.iter().enumerate() {
+        assert!(STAGES[index + 1..]
+            .iter()
+            .all(|other| other.name != stage.name));
+    }
+}
+
+/// Tests that the status labels have the same width, so overwriting a running stage's line leaves nothing behind.
+///
+/// # Panics
+///
+/// * If two labels differ in width.
+#[test_case]
+fn test_status_labels() {
+    let width = Status::Running.label().len();
+
+    assert_eq!(Status::Ok.label().len(), width);
+    assert_eq!(Status::Failed.label().len(), width);
+}
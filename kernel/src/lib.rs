@@ -9,6 +9,12 @@
 extern crate alloc;
 
 use core::panic::PanicInfo;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::sys::time;
 
 #[cfg(test)]
 use bootloader::{entry_point, BootInfo};
@@ -16,7 +22,25 @@ use bootloader::{entry_point, BootInfo};
 /// The version of the kernel.
 pub const KERNEL_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The time the kernel was built, in UTC.
+pub const BUILD_TIMESTAMP: &str = env!("KERNEL_BUILD_TIMESTAMP");
+
+/// The target triple the kernel was built for.
+pub const BUILD_TARGET: &str = env!("KERNEL_TARGET");
+
+/// The version string of the kernel, with the build information.
+pub const VERSION_STRING: &str = concat!(
+    "ROS Kernel v",
+    env!("CARGO_PKG_VERSION"),
+    " (Built: ",
+    env!("KERNEL_BUILD_TIMESTAMP"),
+    ", Target: ",
+    env!("KERNEL_TARGET"),
+    ")"
+);
+
 pub mod allocator;
+pub mod boot_args;
 pub mod dev;
 pub mod errors;
 pub mod fs;
@@ -24,6 +48,8 @@ pub mod init;
 pub mod mem;
 pub mod serial;
 pub mod sys;
+pub mod tui;
+pub mod util;
 pub mod vga_buffer;
 
 /// This function is called on panic.
@@ -44,15 +70,89 @@ where
     T: Fn(),
 {
     /// Runs the test.
+    ///
+    /// # Notes
+    ///
+    /// * Records the test as running, so the timeout can name it.
     fn run(&self) {
-        serial_print!("{}...\t", core::any::type_name::<T>());
+        let name = core::any::type_name::<T>();
+        serial_print!("{}...\t", name);
+
+        interrupts::without_interrupts(|| {
+            *CURRENT_TEST.lock() = Some(name);
+            TEST_START_TICK.store(time::tick(), Ordering::Relaxed);
+        });
 
         self();
 
+        interrupts::without_interrupts(|| *CURRENT_TEST.lock() = None);
+
         serial_println!("[OK]");
     }
 }
 
+/// The default time a test may run before the run is failed, in seconds.
+pub const DEFAULT_TEST_TIMEOUT: f64 = 30.0;
+
+/// The time a test may run before the run is failed, in PIT ticks.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+static TEST_TIMEOUT_TICKS: AtomicUsize =
+    AtomicUsize::new((DEFAULT_TEST_TIMEOUT / time::pit_interval()) as usize);
+/// The PIT tick at which the running test started.
+static TEST_START_TICK: AtomicUsize = AtomicUsize::new(0);
+/// The name of the running test, if any.
+static CURRENT_TEST: Mutex<Option<&'static str>> = Mutex::new(None);
+
+/// Sets the time a test may run before the run is failed.
+///
+/// # Arguments
+///
+/// * `seconds` - The timeout, in seconds.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn set_test_timeout(seconds: f64) {
+    let ticks = (seconds / time::pit_interval()) as usize;
+
+    TEST_TIMEOUT_TICKS.store(ticks.max(1), Ordering::Relaxed);
+}
+
+/// Checks the running test against the timeout, called by the timer interrupt handler on every tick.
+///
+/// Fails the run, naming the test, if it has been running for longer than the timeout, since it's likely hung.
+///
+/// # Notes
+///
+/// * The test name is only locked with interrupts disabled, and the serial macros disable them too, so neither lock
+///   can be held by the interrupted code.
+pub(crate) fn on_test_timer_tick() {
+    let Some(name) = *CURRENT_TEST.lock() else {
+        return;
+    };
+
+    let elapsed = time::tick().wrapping_sub(TEST_START_TICK.load(Ordering::Relaxed));
+    if elapsed < TEST_TIMEOUT_TICKS.load(Ordering::Relaxed) {
+        return;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let seconds = elapsed as f64 * time::pit_interval();
+    serial_println!(
+        "[ERROR]\
+        \nError: {name} timed out after {seconds:.1}s!",
+        name = name,
+        seconds = seconds
+    );
+
+    exit_qemu(QemuExitCode::Failed);
+    hlt_loop();
+}
+
+/// The address of the tests being run, so the run can continue after a test that panics as expected.
+static TESTS_ADDR: AtomicUsize = AtomicUsize::new(0);
+/// The number of tests being run.
+static TESTS_LEN: AtomicUsize = AtomicUsize::new(0);
+/// The index of the next test to run.
+static NEXT_TEST: AtomicUsize = AtomicUsize::new(0);
+
 /// Runs the given tests.
 ///
 /// # Arguments
@@ -60,11 +160,29 @@ where
 /// * `tests` - The tests to run.
 pub fn test_runner(tests: &[&dyn Testable]) {
     serial_println!("Running {} tests...", tests.len());
-    for test in tests {
+
+    TESTS_ADDR.store(tests.as_ptr() as usize, Ordering::Relaxed);
+    TESTS_LEN.store(tests.len(), Ordering::Relaxed);
+
+    run_tests(tests);
+}
+
+/// Runs the tests that haven't run yet, then exits QEMU.
+///
+/// # Arguments
+///
+/// * `tests` - The tests being run.
+///
+/// # Returns
+///
+/// * `!` - Never.
+fn run_tests(tests: &[&dyn Testable]) -> ! {
+    while let Some(test) = tests.get(NEXT_TEST.fetch_add(1, Ordering::Relaxed)) {
         test.run();
     }
 
     exit_qemu(QemuExitCode::Success);
+    hlt_loop();
 }
 
 /// Called on panic in `cargo test`
@@ -76,7 +194,32 @@ pub fn test_runner(tests: &[&dyn Testable]) {
 /// # Returns
 ///
 /// * `!` - Never.
+///
+/// # Notes
+///
+/// * If the test expected the panic, see [`util::assert::should_panic`], the run continues with the next test, on top
+///   of the stack of the panicking one.
 pub fn test_panic_handler(info: &PanicInfo) -> ! {
+    if util::assert::take_expected_panic() {
+        serial_println!("[OK]");
+
+        let addr = TESTS_ADDR.load(Ordering::Relaxed);
+        if addr != 0 {
+            // The tests are built by the test harness and live for the whole run.
+            let tests = unsafe {
+                core::slice::from_raw_parts(
+                    addr as *const &dyn Testable,
+                    TESTS_LEN.load(Ordering::Relaxed),
+                )
+            };
+
+            run_tests(tests);
+        }
+
+        exit_qemu(QemuExitCode::Success);
+        hlt_loop();
+    }
+
     serial_println!(
         "[ERROR]\
         \nError: {}",
@@ -149,5 +292,7 @@ entry_point!(test_kernel_main);
 #[no_mangle]
 fn test_kernel_main(boot_info: &'static BootInfo) -> ! {
     init::start_kernel(boot_info).expect("Failed to start kernel!");
+    test_main();
+
     hlt_loop();
 }
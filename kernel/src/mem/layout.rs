@@ -0,0 +1,119 @@
+/// A region of virtual memory.
+///
+/// # Fields
+///
+/// * `start` - The first address of the region.
+/// * `size` - The size of the region, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region {
+    pub start: u64,
+    pub size: u64,
+}
+
+impl Region {
+    /// Gets the end of the region.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The first address past the region.
+    #[must_use]
+    pub const fn end(&self) -> u64 {
+        self.start + self.size
+    }
+
+    /// Checks if an address is inside the region.
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - The address to check.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether or not the address is inside the region.
+    #[must_use]
+    pub const fn contains(&self, address: u64) -> bool {
+        address >= self.start && address < self.end()
+    }
+
+    /// Checks if the region shares any address with another region.
+    ///
+    /// # Arguments
+    ///
+    /// * `other` - The other region.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether or not the regions overlap.
+    #[must_use]
+    pub const fn overlaps(&self, other: &Self) -> bool {
+        self.start < other.end() && other.start < self.end()
+    }
+}
+
+/// The size of a tebibyte, which every region is a multiple of.
+const TIB: u64 = 1 << 40;
+
+/// The end of the lower half of the address space, past which addresses aren't canonical.
+const LOWER_HALF_END: u64 = 0x8000_0000_0000;
+
+/// The kernel heap, with room to grow far beyond its initial size.
+pub const HEAP: Region = Region {
+    start: 0x4000_0000_0000,
+    size: TIB,
+};
+
+/// Reserved for the code of loaded programs.
+pub const CODE: Region = Region {
+    start: 0x4800_0000_0000,
+    size: TIB,
+};
+
+/// The stacks, each in a slot with guard pages below it.
+pub const STACKS: Region = Region {
+    start: 0x5000_0000_0000,
+    size: TIB,
+};
+
+// Every region the kernel maps at a fixed address is checked here, so they can't silently collide at runtime.
+const _: () = assert!(!HEAP.overlaps(&CODE), "The heap overlaps the code region!");
+const _: () = assert!(
+    !HEAP.overlaps(&STACKS),
+    "The heap overlaps the stack region!"
+);
+const _: () = assert!(
+    !CODE.overlaps(&STACKS),
+    "The code region overlaps the stack region!"
+);
+const _: () = assert!(
+    STACKS.end() <= LOWER_HALF_END,
+    "The stack region isn't canonical!"
+);
+
+/// Tests that regions overlap only when they share an address.
+///
+/// # Panics
+///
+/// * If adjacent regions count as overlapping.
+/// * If overlapping regions don't.
+#[test_case]
+fn test_overlaps() {
+    let region = Region {
+        start: 0x1000,
+        size: 0x1000,
+    };
+    let after = Region {
+        start: 0x2000,
+        size: 0x1000,
+    };
+    let inside = Region {
+        start: 0x1800,
+        size: 0x10,
+    };
+
+    assert!(!region.overlaps(&after));
+    assert!(!after.overlaps(&region));
+    assert!(region.overlaps(&inside));
+    assert!(inside.overlaps(&region));
+    assert!(region.contains(0x1fff));
+    assert!(!region.contains(0x2000));
+}
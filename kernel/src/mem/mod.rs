@@ -0,0 +1,946 @@
+use alloc::format;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use crate::allocator::init_heap;
+use crate::errors::Error;
+use bootloader::bootinfo::{MemoryMap, MemoryRegionType};
+use bootloader::BootInfo;
+use spin::Mutex;
+use x86_64::{
+    registers::control::Cr3,
+    structures::paging::{
+        page_table::FrameError, FrameAllocator, Mapper, OffsetPageTable, Page, PageTable,
+        PageTableFlags, PhysFrame, Size4KiB,
+    },
+    PhysAddr, VirtAddr,
+};
+
+pub mod layout;
+
+/// The start of the virtual memory region stacks are allocated in.
+pub const STACK_REGION_START: u64 = layout::STACKS.start;
+
+/// The virtual memory reserved per stack, in pages, including the unmapped guard pages below it.
+pub const STACK_SLOT_PAGES: u64 = 64;
+
+/// The size of a page, in bytes.
+const PAGE_SIZE: u64 = 4096;
+
+/// The number of stack slots that fit in the stack region.
+const STACK_SLOTS: u64 = layout::STACKS.size / (STACK_SLOT_PAGES * PAGE_SIZE);
+
+/// The index of the next free slot in the stack region.
+static NEXT_STACK_SLOT: AtomicU64 = AtomicU64::new(0);
+
+/// The offset between physical and virtual memory.
+pub static mut PHYSICAL_MEMORY_OFFSET: u64 = 0x0;
+
+/// The memory map passed from the bootloader.
+pub static mut MEMORY_MAP: Option<&MemoryMap> = None;
+
+/// The frame allocator, shared so frames are never handed out twice.
+pub static FRAME_ALLOCATOR: Mutex<Option<BootInfoFrameAllocator>> = Mutex::new(None);
+
+/// A `FrameAllocator` that always returns `None`.
+pub struct EmptyFrameAllocator;
+
+/// A `FrameAllocator` that always returns `None`.
+///
+/// # Safety
+///
+/// * This struct is unsafe because the caller must guarantee that the passed memory map is valid. The main requirement is that all frames that are marked as `USABLE` in it are really unused.
+unsafe impl FrameAllocator<Size4KiB> for EmptyFrameAllocator {
+    /// Allocates a frame.
+    ///
+    /// # Returns
+    ///
+    /// * `None` - Always returns `None`.
+    fn allocate_frame(&mut self) -> Option<PhysFrame> {
+        None
+    }
+}
+
+/// A `FrameAllocator` that returns usable frames from the bootloader's memory map.
+///
+/// # Fields
+///
+/// * `memory_map`: The memory map passed from the bootloader.
+/// * `next`: The index of the next `memory_map` entry to use.
+pub struct BootInfoFrameAllocator {
+    memory_map: &'static MemoryMap,
+    next: usize,
+}
+
+impl BootInfoFrameAllocator {
+    /// Create a `FrameAllocator` from the passed memory map.
+    ///
+    /// # Safety
+    /// * This function is unsafe because the caller must guarantee that the passed memory map is valid. The main requirement is that all frames that are marke as `USABLE` in it are really unused.
+    #[must_use]
+    pub const unsafe fn init(memory_map: &'static MemoryMap) -> Self {
+        Self {
+            memory_map,
+            next: 0,
+        }
+    }
+
+    /// Returns an iterator over the usable frames specified in the memory map.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = PhysFrame>` - An iterator over the usable frames specified in the memory map.
+    fn usable_frames(&self) -> impl Iterator<Item = PhysFrame> {
+        // Get usable regions from memory map.
+        let regions = self.memory_map.iter();
+        let usable_regions = regions.filter(|r| r.region_type == MemoryRegionType::Usable);
+
+        // Map each region to its address range.
+        let addr_ranges = usable_regions.map(|r| r.range.start_addr()..r.range.end_addr());
+
+        // Transform to an iterator of frame start addresses.
+        let frame_addresses = addr_ranges.flat_map(|r| r.step_by(4096));
+
+        // Create `PhysFrame` types from the start addresses.
+        frame_addresses.map(|addr| PhysFrame::containing_address(PhysAddr::new(addr)))
+    }
+
+    /// Counts the usable frames, and how many of them have been allocated.
+    ///
+    /// # Returns
+    ///
+    /// * `FrameStats` - The frame counts.
+    #[must_use]
+    pub fn frame_stats(&self) -> FrameStats {
+        let total = self.usable_frames().count();
+
+        FrameStats {
+            total,
+            allocated: self.next.min(total),
+        }
+    }
+
+    /// Returns an iterator over the usable frames that haven't been allocated yet.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = PhysFrame>` - An iterator over the free frames.
+    pub fn free_frames(&self) -> impl Iterator<Item = PhysFrame> + '_ {
+        self.usable_frames().skip(self.next)
+    }
+}
+
+/// The usage of the physical frames.
+///
+/// # Fields
+///
+/// * `total`: The number of usable frames.
+/// * `allocated`: The number of frames that have been allocated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    pub total: usize,
+    pub allocated: usize,
+}
+
+/// A `FrameAllocator` that returns usable frames from the bootloader's memory map.
+///
+/// # Safety
+///
+/// * This struct is unsafe because the caller must guarantee that the passed memory map is valid. The main requirement is that all frames that are marked as `USABLE` in it are really unused.
+unsafe impl FrameAllocator<Size4KiB> for BootInfoFrameAllocator {
+    /// Allocates a frame.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(PhysFrame)` - If a free frame was found.
+    /// * `None` - If no free frame could be found.
+    fn allocate_frame(&mut self) -> Option<PhysFrame> {
+        let frame = self.usable_frames().nth(self.next);
+        self.next += 1;
+
+        frame
+    }
+}
+
+/// Initializes the memory system.
+///
+/// # Arguments
+///
+/// * `boot_info`: The boot information passed from the bootloader.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - A result indicating whether the initialization succeeded or failed.
+///
+/// # Errors
+///
+/// * If the heap memory allocator fails to initialize.
+pub fn init(boot_info: &'static BootInfo) -> Result<(), Error> {
+    // Initialize the physical memory offset, memory map, mapper, and frame allocator.
+    unsafe {
+        PHYSICAL_MEMORY_OFFSET = boot_info.physical_memory_offset;
+        MEMORY_MAP.replace(&boot_info.memory_map);
+
+        let mut mapper = mapper(VirtAddr::new(PHYSICAL_MEMORY_OFFSET));
+        let mut frame_allocator = BootInfoFrameAllocator::init(&boot_info.memory_map);
+
+        // Initialize the heap.
+        init_heap(&mut mapper, &mut frame_allocator)?;
+
+        // Keep the allocator, so later allocations continue after the heap frames.
+        FRAME_ALLOCATOR.lock().replace(frame_allocator);
+    };
+
+    Ok(())
+}
+
+/// Creates a new mapper.
+///
+/// # Arguments
+///
+/// * `physical_memory_offset`: The offset between physical and virtual memory.
+///
+/// # Returns
+///
+/// * `OffsetPageTable<'static>` - The new mapper.
+///
+/// # Safety
+///
+/// * This function is unsafe because the caller must guarantee that the complete physical memory is mapped to virtual memory at the passed `physical_memory_offset`.
+/// Also, this function must be only called once to avoid aliasing `&mut` references (which is undefined behavior).
+unsafe fn mapper(physical_memory_offset: VirtAddr) -> OffsetPageTable<'static> {
+    let level_4_table = activate_level_4_table(physical_memory_offset);
+
+    OffsetPageTable::new(level_4_table, physical_memory_offset)
+}
+
+/// Returns a mutable reference to the active level 4 table.
+///
+/// # Arguments
+///
+/// * `physical_memory_offset`: The offset between physical and virtual memory.
+///
+/// # Returns
+///
+/// * `&'static mut PageTable` - A mutable reference to the active level 4 table.
+///
+/// # Safety
+/// * This function is unsafe because the caller must guarantee that the complete physical memory is mapped to virtual memory at the passed `physical_memory_offset`. Also, this function must be only called once to avoid aliasing `&mut` references (which is undefined behavior).
+#[must_use]
+pub unsafe fn activate_level_4_table(physical_memory_offset: VirtAddr) -> &'static mut PageTable {
+    let (level_4_table_frame, _) = Cr3::read();
+
+    let phys_addr = level_4_table_frame.start_address();
+    let virt_addr = physical_memory_offset + phys_addr.as_u64();
+    let page_table_ptr: *mut PageTable = virt_addr.as_mut_ptr();
+
+    &mut *page_table_ptr // Unsafe!
+}
+
+/// Translates the given virtual address to the mapped physical address,
+/// or returns `None` if the address is not mapped.
+///
+/// # Arguments
+///
+/// * `addr`: The virtual address to translate.
+/// * `physical_memory_offset`: The offset between physical and virtual memory.
+///
+/// # Returns
+///
+/// * `Option<PhysAddr>` - The mapped physical address, or `None` if the address is not mapped.
+///
+/// # Safety
+/// * This function is unsafe because the caller must guarantee that the complete physical memory is mapped to virtual memory at the passed `physical_memory_offset`.
+///
+/// # Panics
+///
+/// * This function panics if the translation results in an unmapped frame.
+#[must_use]
+pub unsafe fn translate_addr(addr: VirtAddr, physical_memory_offset: VirtAddr) -> Option<PhysAddr> {
+    let (level_4_table_frame, _) = Cr3::read();
+
+    let table_indexes = [
+        addr.p4_index(),
+        addr.p3_index(),
+        addr.p2_index(),
+        addr.p1_index(),
+    ];
+
+    let mut frame = level_4_table_frame;
+
+    // Walk the page table hierarchy.
+    for &index in &table_indexes {
+        let virt = physical_memory_offset + frame.start_address().as_u64();
+        let table_ptr: *const PageTable = virt.as_ptr();
+        let table = &*table_ptr;
+
+        // Get the frame containing the next level of the table.
+        let entry = &table[index];
+
+        frame = match entry.frame() {
+            Ok(frame) => frame,
+            Err(FrameError::FrameNotPresent) => return None,
+            Err(FrameError::HugeFrame) => panic!("Huge pages not supported!"),
+        };
+    }
+
+    // Calculate the address by adding the page offset.
+    Some(frame.start_address() + u64::from(addr.page_offset()))
+}
+
+/// Checks if a range of virtual memory is mapped.
+///
+/// # Arguments
+///
+/// * `start` - The virtual address of the start of the range.
+/// * `len` - The length of the range, in bytes.
+///
+/// # Returns
+///
+/// * `bool` - Whether or not every page of the range is mapped.
+///
+/// # Notes
+///
+/// * Huge pages, which the physical memory is mapped with, count as mapped too, unlike with [`translate_addr`].
+#[must_use]
+pub fn is_mapped(start: u64, len: u64) -> bool {
+    if len == 0 {
+        return true;
+    }
+
+    let Some(end) = start.checked_add(len - 1) else {
+        return false;
+    };
+
+    let (Ok(start), Ok(end)) = (VirtAddr::try_new(start), VirtAddr::try_new(end)) else {
+        return false;
+    };
+
+    let physical_memory_offset = unsafe { VirtAddr::new(PHYSICAL_MEMORY_OFFSET) };
+    let pages = Page::<Size4KiB>::range_inclusive(
+        Page::containing_address(start),
+        Page::containing_address(end),
+    );
+
+    pages.into_iter().all(|page| {
+        unsafe { page_flags(page.start_address(), physical_memory_offset) }
+            .is_some_and(|flags| flags.contains(PageTableFlags::PRESENT))
+    })
+}
+
+/// The first address past the lower half of the address space, which user programs live in.
+pub const USER_SPACE_END: u64 = 0x0000_8000_0000_0000;
+
+/// Checks if a range of virtual memory can be accessed from user mode.
+///
+/// # Arguments
+///
+/// * `start` - The virtual address of the start of the range.
+/// * `len` - The length of the range, in bytes.
+/// * `writable` - Whether the range has to be writable too.
+///
+/// # Returns
+///
+/// * `bool` - Whether or not the range is non-null, in the lower half, and mapped with the `USER_ACCESSIBLE` flag, and
+///   the `WRITABLE` flag if asked for, at every level of the page tables.
+#[must_use]
+pub fn is_user_accessible(start: u64, len: u64, writable: bool) -> bool {
+    if start == 0 {
+        return false;
+    }
+
+    let Some(end) = start.checked_add(len.max(1) - 1) else {
+        return false;
+    };
+    if end >= USER_SPACE_END {
+        return false;
+    }
+
+    let mut required = PageTableFlags::PRESENT | PageTableFlags::USER_ACCESSIBLE;
+    if writable {
+        required |= PageTableFlags::WRITABLE;
+    }
+
+    let physical_memory_offset = unsafe { VirtAddr::new(PHYSICAL_MEMORY_OFFSET) };
+    let pages = Page::<Size4KiB>::range_inclusive(
+        Page::containing_address(VirtAddr::new(start)),
+        Page::containing_address(VirtAddr::new(end)),
+    );
+
+    pages.into_iter().all(|page| {
+        unsafe { page_flags(page.start_address(), physical_memory_offset) }
+            .is_some_and(|flags| flags.contains(required))
+    })
+}
+
+/// Gets the flags that apply to a virtual address, which are those set at every level of the page tables.
+///
+/// # Arguments
+///
+/// * `addr` - The virtual address.
+/// * `physical_memory_offset` - The offset between physical and virtual memory.
+///
+/// # Returns
+///
+/// * `Option<PageTableFlags>` - The flags set at every level, or `None` if the address isn't mapped.
+///
+/// # Safety
+///
+/// * The caller must guarantee that the complete physical memory is mapped to virtual memory at the passed
+///   `physical_memory_offset`.
+unsafe fn page_flags(addr: VirtAddr, physical_memory_offset: VirtAddr) -> Option<PageTableFlags> {
+    let (level_4_table_frame, _) = Cr3::read();
+
+    let table_indexes = [
+        addr.p4_index(),
+        addr.p3_index(),
+        addr.p2_index(),
+        addr.p1_index(),
+    ];
+
+    let mut frame = level_4_table_frame;
+    let mut flags = PageTableFlags::all();
+    for &index in &table_indexes {
+        let virt = physical_memory_offset + frame.start_address().as_u64();
+        let table = &*virt.as_ptr::<PageTable>();
+        let entry = &table[index];
+
+        flags &= entry.flags();
+        match entry.frame() {
+            Ok(next) => frame = next,
+            // A huge page ends the walk, its flags apply to the whole page.
+            Err(FrameError::HugeFrame) => return Some(flags | PageTableFlags::HUGE_PAGE),
+            Err(FrameError::FrameNotPresent) => return None,
+        }
+    }
+
+    Some(flags)
+}
+
+/// Creates an example mapping for the given page to frame '0xb8000'.
+///
+/// # Arguments
+///
+/// * `page`: The page to map.
+/// * `mapper`: A mutable reference to the active mapper.
+/// * `frame_allocator`: A mutable reference to the active frame allocator.
+///
+/// # Safety
+///
+/// * This function is unsafe because the caller must guarantee that the frame is unused.
+/// * Also, this function must be only called once to avoid aliasing `&mut` references (which is undefined behavior).
+///
+/// # Panics
+///
+/// * This function panics if the mapping fails.
+/// * This function panics if the frame is already mapped to another page.
+/// * This function panics if the frame is already mapped to the same page.
+/// * This function panics if the frame is not page aligned.
+#[allow(clippy::expect_used)]
+pub fn create_example_mapping(
+    page: Page,
+    mapper: &mut OffsetPageTable,
+    frame_allocator: &mut impl FrameAllocator<Size4KiB>,
+) {
+    let frame = PhysFrame::containing_address(PhysAddr::new(0xb8000)); // The VGA buffer page frame.
+    let flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE;
+
+    let map_to_result = unsafe { mapper.map_to(page, frame, flags, frame_allocator) };
+
+    map_to_result.expect("map_to failed!").flush();
+}
+
+/// Allocates a page of the given size.
+///
+/// # Arguments
+///
+/// * `addr` - The address to allocate the page at.
+/// * `size` - The size of the page to allocate.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - A result indicating whether the page allocation succeeded or failed.
+///
+/// # Errors
+///
+/// * If the memory map isn't initialized.
+/// * If the frame allocator fails to allocate a frame.
+/// * If the mapper fails to map the frame.
+pub fn alloc_page(addr: u64, size: u64) -> Result<(), Error> {
+    let mut mapper = unsafe { mapper(VirtAddr::new(PHYSICAL_MEMORY_OFFSET)) };
+
+    let mut framealloc = FRAME_ALLOCATOR.lock();
+    let Some(framealloc) = framealloc.as_mut() else {
+        return Err(Error::Internal("Memory map isn't initialized!".into()));
+    };
+
+    let flags =
+        PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::USER_ACCESSIBLE;
+
+    let pages = {
+        let start_page = Page::containing_address(VirtAddr::new(addr));
+        let end_page = Page::containing_address(VirtAddr::new(addr + size));
+
+        Page::range_inclusive(start_page, end_page)
+    };
+
+    for page in pages {
+        let Some(frame) = framealloc.allocate_frame() else {
+            return Err(Error::Internal("Unable to allocate frame!".into()));
+        };
+
+        unsafe {
+            if let Ok(mapping) = mapper.map_to(page, frame, flags, framealloc) {
+                mapping.flush();
+            } else {
+                return Err(Error::Internal("Unable to map frame!".into()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Maps a range of device memory at its place in the physical memory mapping, uncached.
+///
+/// # Arguments
+///
+/// * `physical` - The physical address of the start of the range.
+/// * `size` - The size of the range, in bytes.
+///
+/// # Returns
+///
+/// * `Result<VirtAddr, Error>` - The virtual address of the start of the range.
+///
+/// # Errors
+///
+/// * If the memory map isn't initialized.
+/// * If the mapper fails to map a frame.
+///
+/// # Notes
+///
+/// * The bootloader only maps physical memory up to the highest address in the memory map, so device memory past it
+///   isn't mapped yet. Pages that are already mapped are left alone.
+pub fn map_mmio(physical: u64, size: u64) -> Result<VirtAddr, Error> {
+    use x86_64::structures::paging::Translate;
+
+    let mut mapper = unsafe { mapper(VirtAddr::new(PHYSICAL_MEMORY_OFFSET)) };
+    let start = VirtAddr::new(unsafe { PHYSICAL_MEMORY_OFFSET } + physical);
+
+    let mut framealloc = FRAME_ALLOCATOR.lock();
+    let Some(framealloc) = framealloc.as_mut() else {
+        return Err(Error::Internal("Memory map isn't initialized!".into()));
+    };
+
+    let flags = PageTableFlags::PRESENT
+        | PageTableFlags::WRITABLE
+        | PageTableFlags::NO_CACHE
+        | PageTableFlags::NO_EXECUTE;
+    let pages = Page::<Size4KiB>::range_inclusive(
+        Page::containing_address(start),
+        Page::containing_address(start + size.max(1) - 1u64),
+    );
+    for page in pages {
+        if mapper.translate_addr(page.start_address()).is_some() {
+            continue;
+        }
+
+        let frame = PhysFrame::containing_address(PhysAddr::new(
+            page.start_address().as_u64() - unsafe { PHYSICAL_MEMORY_OFFSET },
+        ));
+        unsafe {
+            if let Ok(mapping) = mapper.map_to(page, frame, flags, framealloc) {
+                mapping.flush();
+            } else {
+                return Err(Error::Internal("Unable to map device memory!".into()));
+            }
+        }
+    }
+
+    Ok(start)
+}
+
+/// A set of page tables, with the kernel's mappings shared.
+///
+/// # Fields
+///
+/// * `level_4_frame` - The frame of the level 4 table.
+///
+/// # Notes
+///
+/// * The kernel isn't linked in the higher half, so every level 4 entry in use when the address space is created is
+///   shared, wherever it is. Mappings of its own have to go in the unused entries, or they'd show up in every address
+///   space.
+/// * The frame allocator can't free frames, so the tables are never freed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressSpace {
+    level_4_frame: PhysFrame,
+}
+
+impl AddressSpace {
+    /// Creates a new `AddressSpace`, sharing the kernel's mappings.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The address space.
+    ///
+    /// # Errors
+    ///
+    /// * If the memory map isn't initialized.
+    /// * If the frame allocator fails to allocate a frame.
+    pub fn new() -> Result<Self, Error> {
+        let mut frame_allocator = FRAME_ALLOCATOR.lock();
+        let Some(frame_allocator) = frame_allocator.as_mut() else {
+            return Err(Error::Internal("Memory map isn't initialized!".into()));
+        };
+
+        let Some(level_4_frame) = frame_allocator.allocate_frame() else {
+            return Err(Error::Internal("Unable to allocate frame!".into()));
+        };
+
+        unsafe {
+            let physical_memory_offset = VirtAddr::new(PHYSICAL_MEMORY_OFFSET);
+            let kernel_table = &*Self::table_ptr(Cr3::read().0, physical_memory_offset);
+            let table = &mut *Self::table_ptr(level_4_frame, physical_memory_offset);
+
+            table.zero();
+            for (entry, kernel_entry) in table.iter_mut().zip(kernel_table.iter()) {
+                if !kernel_entry.is_unused() {
+                    entry.set_addr(kernel_entry.addr(), kernel_entry.flags());
+                }
+            }
+        }
+
+        Ok(Self { level_4_frame })
+    }
+
+    /// Gets the address space that's currently active.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The active address space.
+    #[must_use]
+    pub fn active() -> Self {
+        Self {
+            level_4_frame: Cr3::read().0,
+        }
+    }
+
+    /// Gets the frame of the level 4 table.
+    ///
+    /// # Returns
+    ///
+    /// * `PhysFrame` - The frame, as loaded into `Cr3`.
+    #[must_use]
+    pub const fn level_4_frame(&self) -> PhysFrame {
+        self.level_4_frame
+    }
+
+    /// Creates a mapper for the address space, whether it's active or not.
+    ///
+    /// # Returns
+    ///
+    /// * `OffsetPageTable<'static>` - The mapper.
+    ///
+    /// # Safety
+    ///
+    /// * The caller must make sure there's no other mapper for the address space, to avoid aliasing `&mut` references.
+    #[must_use]
+    pub unsafe fn mapper(&self) -> OffsetPageTable<'static> {
+        let physical_memory_offset = VirtAddr::new(PHYSICAL_MEMORY_OFFSET);
+        let table = &mut *Self::table_ptr(self.level_4_frame, physical_memory_offset);
+
+        OffsetPageTable::new(table, physical_memory_offset)
+    }
+
+    /// Switches to the address space by loading it into `Cr3`, which flushes the TLB.
+    ///
+    /// # Safety
+    ///
+    /// * The caller must make sure everything the running code uses, like its stack, is mapped in the address space.
+    pub unsafe fn activate(&self) {
+        let (_, flags) = Cr3::read();
+
+        Cr3::write(self.level_4_frame, flags);
+    }
+
+    /// Gets a pointer to a page table, through the physical memory mapping.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The frame of the table.
+    /// * `physical_memory_offset` - The offset between physical and virtual memory.
+    ///
+    /// # Returns
+    ///
+    /// * `*mut PageTable` - The pointer.
+    fn table_ptr(frame: PhysFrame, physical_memory_offset: VirtAddr) -> *mut PageTable {
+        (physical_memory_offset + frame.start_address().as_u64()).as_mut_ptr()
+    }
+}
+
+/// A stack with unmapped guard pages below it.
+///
+/// # Fields
+///
+/// * `slot` - The index of the stack in the stack region.
+/// * `top` - The address just past the highest byte of the stack, where the stack pointer starts.
+/// * `pages` - The number of mapped pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stack {
+    pub slot: u64,
+    pub top: VirtAddr,
+    pub pages: u64,
+}
+
+impl Stack {
+    /// Gets the lowest address of the stack.
+    ///
+    /// # Returns
+    ///
+    /// * `VirtAddr` - The address, right above the guard pages.
+    #[must_use]
+    pub fn bottom(&self) -> VirtAddr {
+        self.top - self.pages * PAGE_SIZE
+    }
+}
+
+/// Allocates a stack, with unmapped guard pages below it, so an overflow page faults instead of corrupting memory.
+///
+/// # Arguments
+///
+/// * `pages` - The number of pages to map, at least one guard page less than [`STACK_SLOT_PAGES`].
+/// * `user` - Whether the stack is accessible from ring 3.
+///
+/// # Returns
+///
+/// * `Result<Stack, Error>` - The stack.
+///
+/// # Errors
+///
+/// * If the stack doesn't fit in a slot with a guard page.
+/// * If every slot in the stack region is taken.
+/// * If the memory map isn't initialized.
+/// * If the frame allocator fails to allocate a frame.
+/// * If the mapper fails to map the frame.
+///
+/// # Notes
+///
+/// * Stacks are never freed, since the frame allocator can't free frames.
+pub fn alloc_stack(pages: u64, user: bool) -> Result<Stack, Error> {
+    if pages == 0 || pages >= STACK_SLOT_PAGES {
+        return Err(Error::Internal(format!(
+            "A stack must be 1 to {max} pages!",
+            max = STACK_SLOT_PAGES - 1
+        )));
+    }
+
+    let mut mapper = unsafe { mapper(VirtAddr::new(PHYSICAL_MEMORY_OFFSET)) };
+
+    let mut framealloc = FRAME_ALLOCATOR.lock();
+    let Some(framealloc) = framealloc.as_mut() else {
+        return Err(Error::Internal("Memory map isn't initialized!".into()));
+    };
+
+    let mut flags = PageTableFlags::PRESENT | PageTableFlags::WRITABLE | PageTableFlags::NO_EXECUTE;
+    if user {
+        flags |= PageTableFlags::USER_ACCESSIBLE;
+    }
+
+    // The stack sits at the top of its slot, so the rest of the slot below it is the guard.
+    let slot = NEXT_STACK_SLOT.fetch_add(1, Ordering::Relaxed);
+    if slot >= STACK_SLOTS {
+        return Err(Error::Internal("The stack region is full!".into()));
+    }
+
+    let top = VirtAddr::new(STACK_REGION_START + (slot + 1) * STACK_SLOT_PAGES * PAGE_SIZE);
+    let stack = Stack { slot, top, pages };
+
+    let pages = Page::<Size4KiB>::range(
+        Page::containing_address(stack.bottom()),
+        Page::containing_address(top),
+    );
+    for page in pages {
+        let Some(frame) = framealloc.allocate_frame() else {
+            return Err(Error::Internal("Unable to allocate frame!".into()));
+        };
+
+        unsafe {
+            if let Ok(mapping) = mapper.map_to(page, frame, flags, framealloc) {
+                mapping.flush();
+            } else {
+                return Err(Error::Internal("Unable to map frame!".into()));
+            }
+        }
+    }
+
+    Ok(stack)
+}
+
+/// Finds the stack whose guard pages hold an address.
+///
+/// # Arguments
+///
+/// * `address` - The address that caused a page fault.
+///
+/// # Returns
+///
+/// * `Option<u64>` - The slot of the stack that overflowed, or `None` if the address isn't in the stack region.
+///
+/// # Notes
+///
+/// * Only the stacks themselves are mapped in the stack region, so a page fault anywhere else in it is an overflow.
+#[must_use]
+pub fn stack_overflow_slot(address: VirtAddr) -> Option<u64> {
+    let offset = address.as_u64().checked_sub(STACK_REGION_START)?;
+    let slot = offset / (STACK_SLOT_PAGES * PAGE_SIZE);
+
+    (slot < NEXT_STACK_SLOT.load(Ordering::Relaxed)).then_some(slot)
+}
+
+/// Tests the free frames, by writing walking ones and walking zeros patterns to them and reading them back.
+///
+/// The frames are reached through the physical memory mapping, and the frame allocator is locked throughout, so no
+/// frame in use is ever touched.
+///
+/// # Arguments
+///
+/// * `progress` - Called before each frame with the number of frames tested so far and the total.
+///
+/// # Returns
+///
+/// * `Result<Vec<PhysAddr>, Error>` - The address of the first bad word of every frame that failed.
+///
+/// # Errors
+///
+/// * If the memory map isn't initialized.
+///
+/// # Notes
+///
+/// * Whatever was in the free frames is overwritten.
+pub fn test_free_frames(mut progress: impl FnMut(usize, usize)) -> Result<Vec<PhysAddr>, Error> {
+    const WORDS: usize = 4096 / 8;
+
+    let framealloc = FRAME_ALLOCATOR.lock();
+    let Some(framealloc) = framealloc.as_ref() else {
+        return Err(Error::Internal("Memory map isn't initialized!".into()));
+    };
+
+    let physical_memory_offset = unsafe { VirtAddr::new(PHYSICAL_MEMORY_OFFSET) };
+    let total = framealloc.free_frames().count();
+    let mut bad = Vec::new();
+
+    for (tested, frame) in framealloc.free_frames().enumerate() {
+        progress(tested, total);
+
+        let start = frame.start_address();
+        let words: *mut u64 = (physical_memory_offset + start.as_u64()).as_mut_ptr();
+        let patterns = [
+            |bit: usize| 1 << (bit % 64),
+            |bit: usize| !(1 << (bit % 64)),
+        ];
+
+        // SAFETY: The frame is usable and free, and stays free while the allocator is locked.
+        let mismatch = patterns.iter().find_map(|pattern| unsafe {
+            for index in 0..WORDS {
+                words.add(index).write_volatile(pattern(index));
+            }
+
+            (0..WORDS).find(|&index| words.add(index).read_volatile() != pattern(index))
+        });
+
+        if let Some(index) = mismatch {
+            bad.push(start + index as u64 * 8);
+        }
+    }
+
+    progress(total, total);
+
+    Ok(bad)
+}
+
+/// Gets the usage of the physical frames.
+///
+/// # Returns
+///
+/// * `Option<FrameStats>` - The frame counts, or `None` if the memory system isn't initialized.
+#[must_use]
+pub fn frame_stats() -> Option<FrameStats> {
+    FRAME_ALLOCATOR
+        .lock()
+        .as_ref()
+        .map(BootInfoFrameAllocator::frame_stats)
+}
+
+/// Tests that the heap and the physical memory are mapped, and that unmapped and non-canonical ranges aren't.
+///
+/// # Panics
+///
+/// * If the heap or the physical memory, which is mapped with huge pages, isn't reported as mapped.
+/// * If a range past the heap or a non-canonical range is reported as mapped.
+#[test_case]
+fn test_is_mapped() {
+    use crate::allocator::{HEAP_SIZE, HEAP_START};
+
+    let start = HEAP_START as u64;
+    let size = HEAP_SIZE as u64;
+
+    assert!(is_mapped(start, size));
+    assert!(!is_mapped(start, size + 4096));
+    assert!(is_mapped(unsafe { PHYSICAL_MEMORY_OFFSET } + 0xB8000, 16));
+    assert!(!is_mapped(0x8000_0000_0000, 1));
+}
+
+/// Tests that a new address space shares the kernel's mappings, and can be switched to and back.
+///
+/// # Panics
+///
+/// * If creating the address space fails.
+/// * If a kernel mapping is missing from it.
+/// * If the heap reads differently in it.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_address_space() {
+    use alloc::boxed::Box;
+
+    let kernel = AddressSpace::active();
+    let space = AddressSpace::new().expect("Failed to create an address space!");
+    assert_ne!(space.level_4_frame(), kernel.level_4_frame());
+
+    let value = Box::new(0xDEAD_BEEF_u64);
+    x86_64::instructions::interrupts::without_interrupts(|| unsafe {
+        space.activate();
+        assert_eq!(AddressSpace::active(), space);
+        assert_eq!(*value, 0xDEAD_BEEF);
+
+        kernel.activate();
+    });
+    assert_eq!(AddressSpace::active(), kernel);
+}
+
+/// Tests that a stack is mapped, with its guard page below it unmapped and reported as an overflow.
+///
+/// # Panics
+///
+/// * If allocating the stack fails.
+/// * If the stack isn't mapped, or the guard page is.
+/// * If the guard page isn't attributed to the stack.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_alloc_stack() {
+    let stack = alloc_stack(4, false).expect("Failed to allocate a stack!");
+    let bottom = stack.bottom().as_u64();
+
+    assert!(is_mapped(bottom, 4 * PAGE_SIZE));
+    assert!(!is_mapped(bottom - PAGE_SIZE, 1));
+    assert_eq!(
+        stack_overflow_slot(VirtAddr::new(bottom - 8)),
+        Some(stack.slot)
+    );
+    assert_eq!(
+        stack_overflow_slot(VirtAddr::new(STACK_REGION_START - 8)),
+        None
+    );
+
+    assert!(alloc_stack(STACK_SLOT_PAGES, false).is_err());
+}
@@ -1,14 +1,46 @@
-use lazy_static::lazy_static;
-use spin::Mutex;
-use uart_16550::SerialPort;
+use spin::{Mutex, MutexGuard, Once};
 
-lazy_static! {
-    pub static ref SERIAL1: Mutex<SerialPort> = {
-        let mut serial_port = unsafe { SerialPort::new(0x3F8) };
+pub use uart_16550::SerialPort;
 
-        serial_port.init();
-        Mutex::new(serial_port)
-    };
+/// The I/O port base of the first serial port.
+pub const COM1_BASE: u16 = 0x3F8;
+
+/// The first serial port, which the serial macros print to.
+///
+/// # Notes
+///
+/// * Lock it through [`serial`], which makes sure the UART is initialized first.
+pub static COM1: Mutex<SerialPort> = Mutex::new(unsafe { SerialPort::new(COM1_BASE) });
+
+/// Marks that the UART of [`COM1`] has been initialized.
+static INITIALIZED: Once = Once::new();
+
+/// Initializes the UART of the first serial port.
+///
+/// Sets the line control to 8 data bits, the baud divisor for 38400 baud, and enables and clears the FIFOs.
+///
+/// # Notes
+///
+/// * Only the first call initializes the UART, so it's safe to call more than once.
+pub fn init() {
+    INITIALIZED.call_once(|| COM1.lock().init());
+}
+
+/// Locks the first serial port, for formatting into it directly.
+///
+/// # Returns
+///
+/// * `MutexGuard<'static, SerialPort>` - The serial port, implementing `core::fmt::Write`.
+///
+/// # Notes
+///
+/// * Initializes the UART first, if it hasn't been.
+/// * Don't hold the guard with interrupts enabled, since an interrupt handler printing to serial would deadlock.
+///   Wrap the use in `without_interrupts`, like the serial macros do.
+pub fn serial() -> MutexGuard<'static, SerialPort> {
+    init();
+
+    COM1.lock()
 }
 
 /// Prints to the host through the serial interface.
@@ -27,8 +59,7 @@ pub fn _print(args: core::fmt::Arguments) {
     use x86_64::instructions::interrupts;
 
     interrupts::without_interrupts(|| {
-        SERIAL1
-            .lock()
+        serial()
             .write_fmt(args)
             .expect("Printing to serial failed!");
     });
@@ -0,0 +1,271 @@
+use core::ptr;
+
+use x86_64::registers::model_specific::Msr;
+use x86_64::VirtAddr;
+
+use crate::errors::Error;
+use crate::mem;
+use crate::sys::interrupt_controller::InterruptController;
+use crate::sys::pic::PIC_1_OFFSET;
+
+/// The vector the local APIC raises spurious interrupts on.
+pub const SPURIOUS_VECTOR: u8 = 0xFF;
+
+/// The model specific register holding the base address of the local APIC.
+const APIC_BASE_MSR: u32 = 0x1B;
+
+/// The bit in [`APIC_BASE_MSR`] that enables the local APIC.
+const APIC_BASE_ENABLE: u64 = 1 << 11;
+
+/// The physical address of the I/O APIC.
+///
+/// # Notes
+///
+/// * This is where chipsets put it by default. The real address is in the ACPI MADT, which isn't parsed yet.
+const IO_APIC_BASE: u64 = 0xFEC0_0000;
+
+/// The size of the register window of either APIC, in bytes.
+const REGISTERS_SIZE: u64 = 0x1000;
+
+/// The local APIC ID register.
+const LOCAL_ID: usize = 0x20;
+/// The local APIC task priority register.
+const LOCAL_TASK_PRIORITY: usize = 0x80;
+/// The local APIC end of interrupt register.
+const LOCAL_EOI: usize = 0xB0;
+/// The local APIC spurious interrupt vector register.
+const LOCAL_SPURIOUS: usize = 0xF0;
+
+/// The bit in [`LOCAL_SPURIOUS`] that software enables the local APIC.
+const LOCAL_SPURIOUS_ENABLE: u32 = 1 << 8;
+
+/// The I/O APIC version register, holding the number of redirection entries.
+const IO_VERSION: u32 = 0x01;
+/// The first I/O APIC redirection entry register, each entry taking two.
+const IO_REDIRECTION: u32 = 0x10;
+
+/// The bit in a redirection entry that masks it.
+const REDIRECTION_MASKED: u64 = 1 << 16;
+
+/// The local APIC and the I/O APIC, delivering the legacy interrupt request lines to the bootstrap CPU.
+///
+/// Each line is delivered on the same vector as with the PIC, so the interrupt handlers work with either.
+///
+/// # Fields
+///
+/// * `local` - The registers of the local APIC.
+/// * `io` - The registers of the I/O APIC.
+/// * `local_id` - The ID of the local APIC, which every interrupt is sent to.
+/// * `entries` - The number of redirection entries of the I/O APIC.
+///
+/// # Notes
+///
+/// * The lines are routed edge triggered and active high, like ISA devices. Level triggered PCI lines would need the
+///   routing from ACPI.
+#[derive(Debug)]
+pub struct Apic {
+    local: VirtAddr,
+    io: VirtAddr,
+    local_id: u8,
+    entries: u8,
+}
+
+impl Apic {
+    /// Enables the local APIC and maps both APICs, with every line masked.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Self, Error>` - The APICs.
+    ///
+    /// # Errors
+    ///
+    /// * If the registers of either APIC can't be mapped.
+    ///
+    /// # Safety
+    ///
+    /// * The CPU must have a local APIC, and interrupts must be disabled until the PIC is masked.
+    pub unsafe fn new() -> Result<Self, Error> {
+        let mut base_msr = Msr::new(APIC_BASE_MSR);
+        let base = base_msr.read();
+        base_msr.write(base | APIC_BASE_ENABLE);
+
+        let local = mem::map_mmio(base & 0x000F_FFFF_FFFF_F000, REGISTERS_SIZE)?;
+        let io = mem::map_mmio(IO_APIC_BASE, REGISTERS_SIZE)?;
+
+        let mut apic = Self {
+            local,
+            io,
+            local_id: 0,
+            entries: 0,
+        };
+
+        apic.write_local(LOCAL_TASK_PRIORITY, 0);
+        apic.write_local(
+            LOCAL_SPURIOUS,
+            LOCAL_SPURIOUS_ENABLE | u32::from(SPURIOUS_VECTOR),
+        );
+
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            apic.local_id = (apic.read_local(LOCAL_ID) >> 24) as u8;
+            apic.entries = ((apic.read_io(IO_VERSION) >> 16) as u8).saturating_add(1);
+        }
+
+        for entry in 0..apic.entries {
+            apic.write_redirection(entry, REDIRECTION_MASKED);
+        }
+
+        Ok(apic)
+    }
+
+    /// Reads a local APIC register.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset of the register.
+    ///
+    /// # Returns
+    ///
+    /// * `u32` - The value.
+    fn read_local(&self, offset: usize) -> u32 {
+        // SAFETY: The registers were mapped on creation.
+        unsafe { ptr::read_volatile((self.local + offset).as_ptr()) }
+    }
+
+    /// Writes a local APIC register.
+    ///
+    /// # Arguments
+    ///
+    /// * `offset` - The offset of the register.
+    /// * `value` - The value.
+    fn write_local(&mut self, offset: usize, value: u32) {
+        // SAFETY: The registers were mapped on creation.
+        unsafe { ptr::write_volatile((self.local + offset).as_mut_ptr(), value) };
+    }
+
+    /// Reads an I/O APIC register, through its index and data window.
+    ///
+    /// # Arguments
+    ///
+    /// * `register` - The index of the register.
+    ///
+    /// # Returns
+    ///
+    /// * `u32` - The value.
+    fn read_io(&mut self, register: u32) -> u32 {
+        // SAFETY: The registers were mapped on creation.
+        unsafe {
+            ptr::write_volatile(self.io.as_mut_ptr(), register);
+
+            ptr::read_volatile((self.io + 0x10u64).as_ptr())
+        }
+    }
+
+    /// Writes an I/O APIC register, through its index and data window.
+    ///
+    /// # Arguments
+    ///
+    /// * `register` - The index of the register.
+    /// * `value` - The value.
+    fn write_io(&mut self, register: u32, value: u32) {
+        // SAFETY: The registers were mapped on creation.
+        unsafe {
+            ptr::write_volatile(self.io.as_mut_ptr(), register);
+            ptr::write_volatile((self.io + 0x10u64).as_mut_ptr(), value);
+        }
+    }
+
+    /// Writes an I/O APIC redirection entry.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - The index of the entry, which is the global system interrupt it routes.
+    /// * `value` - The entry.
+    #[allow(clippy::cast_possible_truncation)]
+    fn write_redirection(&mut self, entry: u8, value: u64) {
+        let register = IO_REDIRECTION + u32::from(entry) * 2;
+
+        // Write the low half last, since it holds the mask.
+        self.write_io(register + 1, (value >> 32) as u32);
+        self.write_io(register, value as u32);
+    }
+}
+
+impl InterruptController for Apic {
+    /// Gets the name of the controller.
+    fn name(&self) -> &'static str {
+        "APIC"
+    }
+
+    /// Routes a line to the local APIC, through its redirection entry.
+    fn enable(&mut self, irq: u8) {
+        let entry = global_system_interrupt(irq);
+        if entry < self.entries {
+            self.write_redirection(entry, redirection_entry(irq, self.local_id));
+        }
+    }
+
+    /// Masks the redirection entry of a line.
+    fn mask(&mut self, irq: u8) {
+        let entry = global_system_interrupt(irq);
+        if entry < self.entries {
+            self.write_redirection(entry, REDIRECTION_MASKED);
+        }
+    }
+
+    /// Signals the end of an interrupt to the local APIC, which doesn't need the line.
+    fn end_of_interrupt(&mut self, _irq: u8) {
+        self.write_local(LOCAL_EOI, 0);
+    }
+}
+
+/// Gets the I/O APIC input an interrupt request line is wired to.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line.
+///
+/// # Returns
+///
+/// * `u8` - The global system interrupt.
+///
+/// # Notes
+///
+/// * Without the ACPI MADT, the override every PC chipset has is assumed, where the PIT is wired to input 2 instead
+///   of the cascade.
+const fn global_system_interrupt(irq: u8) -> u8 {
+    match irq {
+        0 => 2,
+        irq => irq,
+    }
+}
+
+/// Builds an unmasked redirection entry, delivering an interrupt request line to a local APIC.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line, delivered on the same vector as with the PIC.
+/// * `local_id` - The ID of the local APIC to deliver to.
+///
+/// # Returns
+///
+/// * `u64` - The entry, with fixed delivery, physical destination, active high and edge triggered.
+const fn redirection_entry(irq: u8, local_id: u8) -> u64 {
+    (local_id as u64) << 56 | (PIC_1_OFFSET + irq) as u64
+}
+
+/// Tests that the lines are routed to the right inputs, on the same vectors as with the PIC.
+///
+/// # Panics
+///
+/// * If the PIT isn't routed to input 2.
+/// * If an entry has the wrong vector, destination or flags.
+#[test_case]
+fn test_redirection_entry() {
+    assert_eq!(global_system_interrupt(0), 2);
+    assert_eq!(global_system_interrupt(1), 1);
+
+    assert_eq!(redirection_entry(1, 0), 33);
+    assert_eq!(redirection_entry(8, 3), 3 << 56 | 40);
+    assert_eq!(redirection_entry(8, 3) & REDIRECTION_MASKED, 0);
+}
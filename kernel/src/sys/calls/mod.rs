@@ -1,5 +1,37 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::fs::fd;
+use crate::serial_println;
 use crate::sys::time::rtc::RTC;
 
+pub mod syscall;
+
+/// Whether system calls are logged to serial.
+static TRACING: AtomicBool = AtomicBool::new(false);
+
+/// Enables or disables logging every system call, with its arguments and result, to serial.
+///
+/// # Arguments
+///
+/// * `enabled` - Whether to log the system calls.
+///
+/// # Notes
+///
+/// * There are no processes yet, so this traces every system call, not those of one program.
+pub fn set_tracing(enabled: bool) {
+    TRACING.store(enabled, Ordering::Relaxed);
+}
+
+/// Checks if system calls are logged.
+///
+/// # Returns
+///
+/// * `bool` - Whether the system calls are logged.
+#[must_use]
+pub fn tracing() -> bool {
+    TRACING.load(Ordering::Relaxed)
+}
+
 /// System calls are used to interact with the kernel.
 ///
 /// # Variants
@@ -7,13 +39,58 @@ use crate::sys::time::rtc::RTC;
 /// * `Sleep` - Sleep for a specified amount of time.
 /// * `Uptime` - Get the uptime of the system.
 /// * `RTC` - Get the current time from the RTC.
+/// * `Shutdown` - Shut down the machine.
+/// * `Reboot` - Reboot the machine.
+/// * `Read` - Read from a file descriptor.
+/// * `Write` - Write to a file descriptor.
+/// * `Open` - Open a file.
+/// * `Close` - Close a file descriptor.
+/// * `Duplicate` - Duplicate a file descriptor.
+/// * `Version` - Get the version string of the kernel.
+/// * `Seek` - Move the offset of a file descriptor.
 /// * `Unknown` - An unknown system call.
+///
+/// # Notes
+///
+/// * `Unknown` is fixed at `usize::MAX`, so new calls are numbered after the last one without changing it.
 #[derive(Debug)]
+#[repr(usize)]
+#[allow(clippy::enum_clike_unportable_variant)] // The kernel only targets x86_64.
 pub enum Call {
     Sleep = 0x1,
     Uptime = 0x2,
     RTC = 0x3,
-    Unknown = 0x4,
+    Shutdown = 0x4,
+    Reboot = 0x5,
+    Read = 0x6,
+    Write = 0x7,
+    Open = 0x8,
+    Close = 0x9,
+    Duplicate = 0xA,
+    Version = 0xB,
+    Seek = 0xC,
+    Unknown = usize::MAX,
+}
+
+impl From<usize> for Call {
+    /// Converts a system call number, as passed in `rax` by the `syscall` instruction.
+    fn from(number: usize) -> Self {
+        match number {
+            0x1 => Self::Sleep,
+            0x2 => Self::Uptime,
+            0x3 => Self::RTC,
+            0x4 => Self::Shutdown,
+            0x5 => Self::Reboot,
+            0x6 => Self::Read,
+            0x7 => Self::Write,
+            0x8 => Self::Open,
+            0x9 => Self::Close,
+            0xA => Self::Duplicate,
+            0xB => Self::Version,
+            0xC => Self::Seek,
+            _ => Self::Unknown,
+        }
+    }
 }
 
 /// Dispatches a system call.
@@ -25,12 +102,49 @@ pub enum Call {
 ///
 /// # Returns
 ///
-/// * `Option<usize>` - The return value of the system call.
+/// * `Option<usize>` - The return value of the system call, or `None` if it failed or is missing arguments.
+///
+/// # Safety
+///
+/// * For `Read`, the second and third arguments must describe memory that is valid for writes.
+/// * For `Write` and `Open`, the pointer and length arguments must describe memory that is valid for reads.
+/// * For `Version`, the first argument must point to a `usize` that is valid for writes, since the length of the
+///   static [`VERSION_STRING`](crate::VERSION_STRING) is stored through it.
 #[must_use]
-pub fn dispatch(call: &Call, args: &[usize]) -> Option<usize> {
+pub unsafe fn dispatch(call: &Call, args: &[usize]) -> Option<usize> {
+    if !tracing() {
+        return unsafe { execute(call, args) };
+    }
+
+    serial_println!("[TRACE]: {call:?}({args:#X?})", call = call, args = args);
+    let result = unsafe { execute(call, args) };
+    serial_println!(
+        "[TRACE]: {call:?} = {result:#X?}",
+        call = call,
+        result = result
+    );
+
+    result
+}
+
+/// Executes a system call, see [`dispatch`].
+///
+/// # Arguments
+///
+/// * `call` - The system call.
+/// * `args` - The arguments for the system call.
+///
+/// # Returns
+///
+/// * `Option<usize>` - The return value of the system call.
+///
+/// # Safety
+///
+/// * See [`dispatch`].
+unsafe fn execute(call: &Call, args: &[usize]) -> Option<usize> {
     match call {
         Call::Sleep => {
-            let duration = args[0];
+            let duration = args.first().copied()?;
 
             crate::sys::time::sleep(duration as f64);
 
@@ -47,6 +161,105 @@ pub fn dispatch(call: &Call, args: &[usize]) -> Option<usize> {
 
             usize::try_from(millis).ok()
         }
+        Call::Shutdown => crate::sys::power::shutdown(),
+        Call::Reboot => crate::sys::power::reboot(),
+        Call::Read => {
+            let (fd, ptr, len) = (
+                args.first().copied()?,
+                args.get(1).copied()?,
+                args.get(2).copied()?,
+            );
+            let buffer = unsafe { core::slice::from_raw_parts_mut(ptr as *mut u8, len) };
+
+            fd::read(fd, buffer).ok()
+        }
+        Call::Write => {
+            let (fd, ptr, len) = (
+                args.first().copied()?,
+                args.get(1).copied()?,
+                args.get(2).copied()?,
+            );
+            let buffer = unsafe { core::slice::from_raw_parts(ptr as *const u8, len) };
+
+            fd::write(fd, buffer).ok()
+        }
+        Call::Open => {
+            let (ptr, len) = (args.first().copied()?, args.get(1).copied()?);
+            let path = unsafe { core::slice::from_raw_parts(ptr as *const u8, len) };
+
+            fd::open(core::str::from_utf8(path).ok()?).ok()
+        }
+        Call::Close => fd::close(args.first().copied()?).ok().map(|()| 0),
+        Call::Duplicate => fd::duplicate(args.first().copied()?).ok(),
+        Call::Version => {
+            let version = crate::VERSION_STRING;
+            let len = args.first().copied()? as *mut usize;
+            if len.is_null() {
+                return None;
+            }
+
+            // The caller's `usize` may not be aligned.
+            unsafe { len.write_unaligned(version.len()) };
+
+            Some(version.as_ptr() as usize)
+        }
+        Call::Seek => {
+            // The offset is signed, passed in a register as its two's complement.
+            #[allow(clippy::cast_possible_wrap)]
+            let offset = args.get(1).copied()? as isize;
+            let whence = fd::Whence::try_from(args.get(2).copied()?).ok()?;
+
+            fd::seek(args.first().copied()?, offset, whence).ok()
+        }
         Call::Unknown => None,
     }
 }
+
+/// Tests that tracing doesn't change the result of a system call.
+///
+/// # Panics
+///
+/// * If the traced call returns something else than the untraced one.
+#[test_case]
+fn test_tracing() {
+    let mut len = 0;
+    let untraced = unsafe { dispatch(&Call::Version, &[core::ptr::addr_of_mut!(len) as usize]) };
+
+    set_tracing(true);
+    let traced = unsafe { dispatch(&Call::Version, &[core::ptr::addr_of_mut!(len) as usize]) };
+    set_tracing(false);
+
+    assert_eq!(traced, untraced);
+    assert_eq!(len, crate::VERSION_STRING.len());
+}
+
+/// Tests that unknown system call numbers map to `Unknown`, which keeps its number.
+///
+/// # Panics
+///
+/// * If a number past the last call isn't unknown.
+#[test_case]
+fn test_unknown_call() {
+    assert!(matches!(Call::from(0xD), Call::Unknown));
+    assert!(matches!(Call::from(usize::MAX), Call::Unknown));
+    assert_eq!(Call::Unknown as usize, usize::MAX);
+}
+
+/// Tests that a system call missing arguments fails instead of panicking.
+///
+/// # Panics
+///
+/// * If a call with too few arguments succeeds.
+#[test_case]
+fn test_missing_arguments() {
+    for call in [
+        Call::Read,
+        Call::Write,
+        Call::Open,
+        Call::Close,
+        Call::Version,
+        Call::Seek,
+    ] {
+        assert_eq!(unsafe { dispatch(&call, &[]) }, None);
+    }
+}
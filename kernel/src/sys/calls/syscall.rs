@@ -0,0 +1,171 @@
+use core::arch::global_asm;
+use core::mem::size_of;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use x86_64::instructions::interrupts;
+use x86_64::registers::model_specific::{Efer, EferFlags, LStar, SFMask, Star};
+use x86_64::registers::rflags::RFlags;
+use x86_64::VirtAddr;
+
+use crate::errors::Error;
+use crate::mem;
+use crate::sys::gdt;
+
+use super::{dispatch, Call};
+
+/// The value returned in `rax` when a system call fails or doesn't exist.
+pub const SYSCALL_ERROR: usize = usize::MAX;
+
+/// The stack pointer of the caller, while the kernel handles its system call.
+static SYSCALL_USER_RSP: AtomicU64 = AtomicU64::new(0);
+/// The stack the kernel handles system calls on.
+static SYSCALL_KERNEL_RSP: AtomicU64 = AtomicU64::new(0);
+
+// The entry point of the `syscall` instruction.
+//
+// The system call number is passed in `rax` and the arguments in `rdi`, `rsi` and `rdx`, and the result is returned in
+// `rax`. Like on Linux, only `rax`, `rcx` and `r11` are clobbered. Interrupts are masked on entry, so the switch to the
+// kernel stack can't be interrupted.
+global_asm!(
+    ".global syscall_entry",
+    "syscall_entry:",
+    "mov [rip + {user_rsp}], rsp",
+    "mov rsp, [rip + {kernel_rsp}]",
+    "and rsp, -16",
+    // The return address and flags, for `sysret`.
+    "push rcx",
+    "push r11",
+    // The caller saved registers the handler may clobber.
+    "push rdi",
+    "push rsi",
+    "push rdx",
+    "push r8",
+    "push r9",
+    "push r10",
+    "mov rcx, rdx",
+    "mov rdx, rsi",
+    "mov rsi, rdi",
+    "mov rdi, rax",
+    "call {handler}",
+    // Back on the caller's stack below, so nothing may interrupt from here on.
+    "cli",
+    "pop r10",
+    "pop r9",
+    "pop r8",
+    "pop rdx",
+    "pop rsi",
+    "pop rdi",
+    "pop r11",
+    "pop rcx",
+    "mov rsp, [rip + {user_rsp}]",
+    "sysretq",
+    user_rsp = sym SYSCALL_USER_RSP,
+    kernel_rsp = sym SYSCALL_KERNEL_RSP,
+    handler = sym handle_syscall,
+);
+
+extern "C" {
+    /// The entry point of the `syscall` instruction, defined above.
+    fn syscall_entry();
+}
+
+/// Handles a system call made with the `syscall` instruction.
+///
+/// # Arguments
+///
+/// * `number` - The number of the system call.
+/// * `arg0` - The first argument.
+/// * `arg1` - The second argument.
+/// * `arg2` - The third argument.
+///
+/// # Returns
+///
+/// * `usize` - The return value of the system call, or [`SYSCALL_ERROR`].
+///
+/// # Notes
+///
+/// * Pointer arguments come from user mode, so they're checked with [`user_args_valid`] before being dispatched.
+extern "C" fn handle_syscall(number: usize, arg0: usize, arg1: usize, arg2: usize) -> usize {
+    let call = Call::from(number);
+    let arguments = [arg0, arg1, arg2];
+    if !user_args_valid(&call, &arguments) {
+        return SYSCALL_ERROR;
+    }
+
+    // Calls like `Sleep` wait for the timer, so let interrupts in while on the kernel stack.
+    interrupts::enable();
+    // The pointer arguments were checked to be user memory above.
+    let result = unsafe { dispatch(&call, &arguments) }.unwrap_or(SYSCALL_ERROR);
+    interrupts::disable();
+
+    result
+}
+
+/// Checks that the pointer arguments of a system call from user mode point to user memory.
+///
+/// # Arguments
+///
+/// * `call` - The system call.
+/// * `args` - The arguments for the system call.
+///
+/// # Returns
+///
+/// * `bool` - Whether or not every pointer and length pair is mapped user memory, writable if the kernel writes to it.
+fn user_args_valid(call: &Call, args: &[usize; 3]) -> bool {
+    let [first, second, third] = args.map(|arg| arg as u64);
+
+    match call {
+        Call::Read => mem::is_user_accessible(second, third, true),
+        Call::Write => mem::is_user_accessible(second, third, false),
+        Call::Open => mem::is_user_accessible(first, second, false),
+        Call::Version => mem::is_user_accessible(first, size_of::<usize>() as u64, true),
+        _ => true,
+    }
+}
+
+/// Enables the `syscall` and `sysret` instructions, for system calls from ring 3.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the GDT isn't laid out the way `syscall` and `sysret` expect.
+///
+/// # Notes
+///
+/// * The GDT must be loaded first.
+pub fn init() -> Result<(), Error> {
+    let (user_code, user_data, kernel_code, kernel_data) = gdt::syscall_selectors();
+    Star::write(user_code, user_data, kernel_code, kernel_data)
+        .map_err(|why| Error::Internal(why.into()))?;
+
+    SYSCALL_KERNEL_RSP.store(gdt::kernel_stack().as_u64(), Ordering::Relaxed);
+    LStar::write(VirtAddr::from_ptr(syscall_entry as *const ()));
+    SFMask::write(RFlags::INTERRUPT_FLAG);
+
+    unsafe { Efer::update(|flags| flags.insert(EferFlags::SYSTEM_CALL_EXTENSIONS)) };
+
+    Ok(())
+}
+
+/// Tests that pointers outside of user memory are rejected.
+///
+/// # Panics
+///
+/// * If a kernel, null, overflowing or higher half pointer is accepted.
+#[test_case]
+fn test_user_args_valid() {
+    let kernel = core::ptr::addr_of!(SYSCALL_USER_RSP) as usize;
+
+    assert!(!user_args_valid(&Call::Write, &[1, kernel, 8]));
+    assert!(!user_args_valid(&Call::Read, &[0, 0, 8]));
+    assert!(!user_args_valid(&Call::Read, &[0, usize::MAX - 4, 8]));
+    assert!(!user_args_valid(
+        &Call::Open,
+        &[0xFFFF_8000_0000_0000, 8, 0]
+    ));
+    assert!(!user_args_valid(&Call::Version, &[kernel, 0, 0]));
+    assert!(user_args_valid(&Call::Uptime, &[0, 0, 0]));
+}
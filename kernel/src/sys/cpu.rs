@@ -0,0 +1,282 @@
+use core::arch::x86_64::{__cpuid, __cpuid_count, CpuidResult};
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// The features of the CPU, detected on first use.
+    pub static ref CPU_FEATURES: CpuFeatures = CpuFeatures::detect();
+}
+
+/// The features of the CPU, as reported by `cpuid`.
+///
+/// # Fields
+///
+/// * `vendor` - The vendor ID, like `GenuineIntel`.
+/// * `brand` - The brand string, padded with spaces or zeros.
+/// * `features_ecx` - The feature flags in `ecx` of leaf 1.
+/// * `features_edx` - The feature flags in `edx` of leaf 1.
+/// * `extended_features_ebx` - The feature flags in `ebx` of leaf 7.
+/// * `power_management_edx` - The flags in `edx` of leaf `0x8000_0007`.
+#[derive(Debug, Clone)]
+pub struct CpuFeatures {
+    vendor: [u8; 12],
+    brand: [u8; 48],
+    features_ecx: u32,
+    features_edx: u32,
+    extended_features_ebx: u32,
+    power_management_edx: u32,
+}
+
+impl CpuFeatures {
+    /// Detects the features of the CPU.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The features.
+    #[must_use]
+    #[allow(clippy::similar_names, unused_unsafe)]
+    pub fn detect() -> Self {
+        let leaf = cpuid(0);
+        let max_leaf = leaf.eax;
+
+        let mut vendor = [0; 12];
+        vendor[0..4].copy_from_slice(&leaf.ebx.to_le_bytes());
+        vendor[4..8].copy_from_slice(&leaf.edx.to_le_bytes());
+        vendor[8..12].copy_from_slice(&leaf.ecx.to_le_bytes());
+
+        let (features_ecx, features_edx) = if max_leaf >= 1 {
+            let leaf = cpuid(1);
+
+            (leaf.ecx, leaf.edx)
+        } else {
+            (0, 0)
+        };
+        let extended_features_ebx = if max_leaf >= 7 {
+            // SAFETY: The leaf was just checked to be supported.
+            unsafe { __cpuid_count(7, 0) }.ebx
+        } else {
+            0
+        };
+
+        let max_extended_leaf = cpuid(0x8000_0000).eax;
+        let mut brand = [0; 48];
+        if max_extended_leaf >= 0x8000_0004 {
+            for (leaf, chunk) in (0x8000_0002..=0x8000_0004).zip(brand.chunks_exact_mut(16)) {
+                let leaf = cpuid(leaf);
+
+                for (bytes, register) in chunk
+                    .chunks_exact_mut(4)
+                    .zip([leaf.eax, leaf.ebx, leaf.ecx, leaf.edx])
+                {
+                    bytes.copy_from_slice(&register.to_le_bytes());
+                }
+            }
+        }
+        let power_management_edx = if max_extended_leaf >= 0x8000_0007 {
+            cpuid(0x8000_0007).edx
+        } else {
+            0
+        };
+
+        Self {
+            vendor,
+            brand,
+            features_ecx,
+            features_edx,
+            extended_features_ebx,
+            power_management_edx,
+        }
+    }
+
+    /// Gets the vendor ID.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The vendor ID, like `GenuineIntel` or `AuthenticAMD`.
+    #[must_use]
+    pub fn vendor_string(&self) -> &str {
+        core::str::from_utf8(&self.vendor).unwrap_or("Unknown")
+    }
+
+    /// Gets the brand string.
+    ///
+    /// # Returns
+    ///
+    /// * `&str` - The brand string, or an empty string if the CPU doesn't report one.
+    #[must_use]
+    pub fn brand_string(&self) -> &str {
+        let end = self
+            .brand
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(self.brand.len());
+
+        core::str::from_utf8(&self.brand[..end])
+            .unwrap_or_default()
+            .trim()
+    }
+
+    /// Checks if the CPU has the time-stamp counter.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the `rdtsc` instruction is available.
+    #[must_use]
+    pub const fn has_tsc(&self) -> bool {
+        self.features_edx & 1 << 4 != 0
+    }
+
+    /// Checks if the time-stamp counter runs at a constant rate, regardless of power states.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the time-stamp counter is invariant.
+    #[must_use]
+    pub const fn has_invariant_tsc(&self) -> bool {
+        self.power_management_edx & 1 << 8 != 0
+    }
+
+    /// Checks if the CPU has a local APIC.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the APIC is available.
+    #[must_use]
+    pub const fn has_apic(&self) -> bool {
+        self.features_edx & 1 << 9 != 0
+    }
+
+    /// Checks if the local APIC supports x2APIC mode.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether x2APIC is available.
+    #[must_use]
+    pub const fn has_x2apic(&self) -> bool {
+        self.features_ecx & 1 << 21 != 0
+    }
+
+    /// Checks if the CPU supports SSE.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether SSE is available.
+    #[must_use]
+    pub const fn has_sse(&self) -> bool {
+        self.features_edx & 1 << 25 != 0
+    }
+
+    /// Checks if the CPU supports SSE2.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether SSE2 is available.
+    #[must_use]
+    pub const fn has_sse2(&self) -> bool {
+        self.features_edx & 1 << 26 != 0
+    }
+
+    /// Checks if the CPU supports SSE3.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether SSE3 is available.
+    #[must_use]
+    pub const fn has_sse3(&self) -> bool {
+        self.features_ecx & 1 != 0
+    }
+
+    /// Checks if the CPU supports AVX.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether AVX is available.
+    #[must_use]
+    pub const fn has_avx(&self) -> bool {
+        self.features_ecx & 1 << 28 != 0
+    }
+
+    /// Checks if the CPU has the `rdrand` instruction.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether `rdrand` is available.
+    #[must_use]
+    pub const fn has_rdrand(&self) -> bool {
+        self.features_ecx & 1 << 30 != 0
+    }
+
+    /// Checks if the CPU has the `rdseed` instruction.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether `rdseed` is available.
+    #[must_use]
+    pub const fn has_rdseed(&self) -> bool {
+        self.extended_features_ebx & 1 << 18 != 0
+    }
+
+    /// Checks if the hypervisor bit is set, meaning the kernel runs in a virtual machine.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the CPU is virtualized.
+    #[must_use]
+    pub const fn is_virtualized(&self) -> bool {
+        self.features_ecx & 1 << 31 != 0
+    }
+
+    /// Lists the names of the detected features.
+    ///
+    /// # Returns
+    ///
+    /// * `impl Iterator<Item = &str>` - The names of the features the CPU has.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        [
+            ("tsc", self.has_tsc()),
+            ("invariant_tsc", self.has_invariant_tsc()),
+            ("apic", self.has_apic()),
+            ("x2apic", self.has_x2apic()),
+            ("sse", self.has_sse()),
+            ("sse2", self.has_sse2()),
+            ("sse3", self.has_sse3()),
+            ("avx", self.has_avx()),
+            ("rdrand", self.has_rdrand()),
+            ("rdseed", self.has_rdseed()),
+            ("hypervisor", self.is_virtualized()),
+        ]
+        .into_iter()
+        .filter_map(|(name, present)| present.then_some(name))
+    }
+}
+
+/// Runs `cpuid` for a leaf.
+///
+/// # Arguments
+///
+/// * `leaf` - The leaf, which the caller checked to be supported.
+///
+/// # Returns
+///
+/// * `CpuidResult` - The registers returned.
+#[allow(unused_unsafe)]
+fn cpuid(leaf: u32) -> CpuidResult {
+    // SAFETY: Every x86_64 CPU has `cpuid`, and unsupported leaves only return meaningless values.
+    unsafe { __cpuid(leaf) }
+}
+
+/// Tests that the features every x86_64 CPU has are detected.
+///
+/// # Panics
+///
+/// * If the vendor ID isn't ASCII.
+/// * If the time-stamp counter, SSE or SSE2 aren't detected.
+#[test_case]
+fn test_cpu_features() {
+    let features = CpuFeatures::detect();
+
+    assert!(features.vendor.is_ascii());
+    assert!(features.has_tsc());
+    assert!(features.has_sse() && features.has_sse2());
+    assert!(features.names().any(|name| name == "sse2"));
+}
@@ -0,0 +1,155 @@
+use x86_64::registers::control::{Cr0, Cr0Flags, Cr4, Cr4Flags};
+
+/// Initializes the x87 FPU and enables the SSE instructions.
+///
+/// Clears `CR0.EM` so FPU instructions aren't emulated, sets `CR0.MP` so `wait` respects `CR0.TS`, and sets
+/// `CR4.OSFXSR` and `CR4.OSXMMEXCPT` so SSE instructions and their exceptions are available, then resets the FPU.
+///
+/// # Notes
+///
+/// * The kernel itself is built with soft floats, so this is for code using the FPU or SSE directly.
+pub fn init() {
+    unsafe {
+        Cr0::update(|flags| {
+            flags.remove(Cr0Flags::EMULATE_COPROCESSOR | Cr0Flags::TASK_SWITCHED);
+            flags.insert(Cr0Flags::MONITOR_COPROCESSOR);
+        });
+        Cr4::update(|flags| {
+            flags.insert(Cr4Flags::OSFXSR | Cr4Flags::OSXMMEXCPT_ENABLE);
+        });
+
+        core::arch::asm!("fninit", options(nomem, nostack));
+    }
+}
+
+/// The x87 FPU, MMX and SSE registers, as saved by `fxsave`.
+///
+/// # Fields
+///
+/// * `0` - The 512 byte save area, which `fxsave` and `fxrstor` require to be 16 byte aligned.
+#[derive(Clone)]
+#[repr(C, align(16))]
+pub struct FpuState([u8; 512]);
+
+impl FpuState {
+    /// Creates a new `FpuState`, holding the state of a freshly reset FPU.
+    ///
+    /// # Returns
+    ///
+    /// * `Self` - The state.
+    #[must_use]
+    pub const fn new() -> Self {
+        let mut area = [0; 512];
+
+        // The FPU control word and MXCSR after `fninit`, with every exception masked.
+        area[0] = 0x7F;
+        area[1] = 0x03;
+        area[24] = 0x80;
+        area[25] = 0x1F;
+
+        Self(area)
+    }
+
+    /// Saves the current FPU state.
+    ///
+    /// # Notes
+    ///
+    /// * A context switch saves the state of the task it switches away from with this.
+    pub fn save(&mut self) {
+        unsafe {
+            core::arch::asm!("fxsave [{}]", in(reg) self.0.as_mut_ptr(), options(nostack));
+        }
+    }
+
+    /// Restores this FPU state.
+    ///
+    /// # Notes
+    ///
+    /// * A context switch restores the state of the task it switches to with this.
+    pub fn restore(&self) {
+        unsafe {
+            core::arch::asm!("fxrstor [{}]", in(reg) self.0.as_ptr(), options(nostack, readonly));
+        }
+    }
+}
+
+impl Default for FpuState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Tests that the FPU and SSE are enabled, and that floating point arithmetic works.
+///
+/// # Panics
+///
+/// * If the control registers aren't set up for the FPU and SSE.
+/// * If the arithmetic gives the wrong result.
+#[test_case]
+#[allow(clippy::float_cmp)]
+fn test_fpu() {
+    assert!(!Cr0::read().contains(Cr0Flags::EMULATE_COPROCESSOR));
+    assert!(Cr0::read().contains(Cr0Flags::MONITOR_COPROCESSOR));
+    assert!(Cr4::read().contains(Cr4Flags::OSFXSR | Cr4Flags::OSXMMEXCPT_ENABLE));
+
+    let value = core::hint::black_box(1.5_f64);
+    assert_eq!(value * 2.0 + 0.25, 3.25);
+
+    // Add with SSE directly, since the kernel is built with soft floats. `xmm0` is saved, as nothing expects it used.
+    let sum: u64;
+    unsafe {
+        core::arch::asm!(
+            "sub rsp, 16",
+            "movdqu [rsp], xmm0",
+            "movq xmm0, {value}",
+            "addsd xmm0, xmm0",
+            "movq {sum}, xmm0",
+            "movdqu xmm0, [rsp]",
+            "add rsp, 16",
+            value = in(reg) value.to_bits(),
+            sum = lateout(reg) sum,
+        );
+    }
+    assert_eq!(f64::from_bits(sum), 3.0);
+}
+
+/// Tests that two states saved and restored in turn each keep their own registers, like two tasks switched between.
+///
+/// # Panics
+///
+/// * If the state is misaligned.
+/// * If a restored state holds the other state's value.
+#[test_case]
+fn test_fpu_state() {
+    /// Loads a value into `xmm0`.
+    fn load(value: u64) {
+        unsafe { core::arch::asm!("movq xmm0, {}", in(reg) value, options(nomem, nostack)) };
+    }
+
+    /// Reads the value in `xmm0`.
+    fn read() -> u64 {
+        let value: u64;
+        unsafe { core::arch::asm!("movq {}, xmm0", out(reg) value, options(nomem, nostack)) };
+
+        value
+    }
+
+    let mut first = FpuState::new();
+    let mut second = FpuState::new();
+    assert_eq!(core::ptr::addr_of!(first) as usize % 16, 0);
+
+    let mut original = FpuState::new();
+    original.save();
+
+    load(1.5_f64.to_bits());
+    first.save();
+    load(2.5_f64.to_bits());
+    second.save();
+
+    first.restore();
+    assert_eq!(f64::from_bits(read()), 1.5);
+    second.restore();
+    assert_eq!(f64::from_bits(read()), 2.5);
+
+    original.restore();
+}
@@ -1,14 +1,38 @@
+use core::mem::size_of;
+use core::sync::atomic::{AtomicU8, Ordering};
+
 use lazy_static::lazy_static;
 use x86_64::structures::gdt::{Descriptor, GlobalDescriptorTable, SegmentSelector};
 use x86_64::structures::tss::TaskStateSegment;
-use x86_64::VirtAddr;
+use x86_64::{PrivilegeLevel, VirtAddr};
 
 pub const DOUBLE_FAULT_IST_INDEX: u16 = 0;
 
+/// The size of the I/O permission bitmap, a bit for each of the 65536 ports.
+const IO_BITMAP_SIZE: usize = 65536 / 8;
+
+/// A task state segment, followed by its I/O permission bitmap.
+///
+/// # Fields
+///
+/// * `tss` - The task state segment.
+/// * `io_bitmap` - A bit per port, set if ring 3 may not access it, followed by the all-ones byte the CPU requires.
+#[repr(C)]
+struct TaskState {
+    tss: TaskStateSegment,
+    io_bitmap: [AtomicU8; IO_BITMAP_SIZE + 1],
+}
+
 lazy_static! {
-    static ref TSS: TaskStateSegment = {
+    static ref TSS: TaskState = {
         let mut tss = TaskStateSegment::new();
 
+        // The bitmap follows the TSS directly.
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            tss.iomap_base = size_of::<TaskStateSegment>() as u16;
+        }
+
         tss.interrupt_stack_table[DOUBLE_FAULT_IST_INDEX as usize] = {
             const STACK_SIZE: usize = 4096 * 5;
             static mut STACK: [u8; STACK_SIZE] = [0; STACK_SIZE];
@@ -17,7 +41,20 @@ lazy_static! {
             stack_start + STACK_SIZE // Return the stack end address.
         };
 
-        tss
+        // The stack the CPU switches to when an interrupt or system call arrives in ring 3.
+        tss.privilege_stack_table[0] = {
+            const STACK_SIZE: usize = 4096 * 5;
+            static mut STACK: [u8; STACK_SIZE] = [0; STACK_SIZE];
+
+            let stack_start = VirtAddr::from_ptr(unsafe { &STACK });
+            stack_start + STACK_SIZE // Return the stack end address.
+        };
+
+        // Deny ring 3 every port.
+        TaskState {
+            tss,
+            io_bitmap: [const { AtomicU8::new(0xFF) }; IO_BITMAP_SIZE + 1],
+        }
     };
 }
 
@@ -25,19 +62,61 @@ lazy_static! {
     static ref GDT: (GlobalDescriptorTable, Selectors) = {
         let mut gdt = GlobalDescriptorTable::new();
         let code_selector = gdt.add_entry(Descriptor::kernel_code_segment());
-        let tss_selector = gdt.add_entry(Descriptor::tss_segment(&TSS));
+        let data_selector = gdt.add_entry(Descriptor::kernel_data_segment());
+        // The user data segment comes right before the user code segment, as `sysret` expects.
+        let user_data_selector = gdt.add_entry(Descriptor::user_data_segment());
+        let user_code_selector = gdt.add_entry(Descriptor::user_code_segment());
+        let tss_selector = gdt.add_entry(tss_descriptor(&TSS));
         (
             gdt,
             Selectors {
                 code_selector,
+                data_selector,
+                user_code_selector: SegmentSelector::new(user_code_selector.index(), PrivilegeLevel::Ring3),
+                user_data_selector: SegmentSelector::new(user_data_selector.index(), PrivilegeLevel::Ring3),
                 tss_selector,
             },
         )
     };
 }
 
+/// Creates the descriptor of a task state segment, with a limit covering its I/O permission bitmap.
+///
+/// # Arguments
+///
+/// * `task_state` - The task state segment and its bitmap.
+///
+/// # Returns
+///
+/// * `Descriptor` - The descriptor.
+fn tss_descriptor(task_state: &'static TaskState) -> Descriptor {
+    let Descriptor::SystemSegment(mut low, high) = Descriptor::tss_segment(&task_state.tss) else {
+        unreachable!("A TSS descriptor is always a system segment!");
+    };
+
+    // Replace the limit, which is inclusive, split into bits 0 to 15 and 48 to 51.
+    let limit = (size_of::<TaskState>() - 1) as u64;
+    low &= !(0xFFFF | 0xF << 48);
+    low |= limit & 0xFFFF | (limit >> 16 & 0xF) << 48;
+
+    Descriptor::SystemSegment(low, high)
+}
+
+/// The segment selectors of the global descriptor table.
+///
+/// # Fields
+///
+/// * `code_selector` - The kernel code segment.
+/// * `data_selector` - The kernel data segment.
+/// * `user_code_selector` - The user code segment, requested with privilege level 3.
+/// * `user_data_selector` - The user data segment, requested with privilege level 3.
+/// * `tss_selector` - The task state segment.
+#[allow(clippy::struct_field_names)]
 struct Selectors {
     code_selector: SegmentSelector,
+    data_selector: SegmentSelector,
+    user_code_selector: SegmentSelector,
+    user_data_selector: SegmentSelector,
     tss_selector: SegmentSelector,
 }
 
@@ -47,13 +126,156 @@ struct Selectors {
 ///
 /// * This function is unsafe because the caller must guarantee that the global descriptor table is not used while it is being reloaded.
 pub fn init() {
-    use x86_64::instructions::segmentation::{Segment, CS};
+    use x86_64::instructions::segmentation::{Segment, CS, DS, ES, SS};
     use x86_64::instructions::tables::load_tss;
 
     GDT.0.load();
 
     unsafe {
         CS::set_reg(GDT.1.code_selector);
+        SS::set_reg(GDT.1.data_selector);
+        DS::set_reg(GDT.1.data_selector);
+        ES::set_reg(GDT.1.data_selector);
         load_tss(GDT.1.tss_selector);
     }
 }
+
+/// Gets the segment selectors loaded by `syscall` and `sysret`.
+///
+/// # Returns
+///
+/// * `(SegmentSelector, SegmentSelector, SegmentSelector, SegmentSelector)` - The user code, user data, kernel code
+///   and kernel data selectors.
+pub(crate) fn syscall_selectors() -> (
+    SegmentSelector,
+    SegmentSelector,
+    SegmentSelector,
+    SegmentSelector,
+) {
+    (
+        GDT.1.user_code_selector,
+        GDT.1.user_data_selector,
+        GDT.1.code_selector,
+        GDT.1.data_selector,
+    )
+}
+
+/// Gets the top of the kernel stack used when entering the kernel from ring 3.
+///
+/// # Returns
+///
+/// * `VirtAddr` - The top of the stack.
+pub(crate) fn kernel_stack() -> VirtAddr {
+    TSS.tss.privilege_stack_table[0]
+}
+
+/// Allows or denies ring 3 access to an I/O port.
+///
+/// Every port is denied by default, so `in` and `out` in ring 3 cause a general protection fault.
+///
+/// # Arguments
+///
+/// * `port` - The port.
+/// * `allowed` - Whether ring 3 may access the port.
+///
+/// # Notes
+///
+/// * There's a single TSS, so the permission applies to all of ring 3, not to one process.
+/// * Accessing a multi-byte port needs every byte of it allowed, so allow `port + 1` for a 16-bit port too.
+pub fn set_port_access(port: u16, allowed: bool) {
+    let byte = &TSS.io_bitmap[usize::from(port / 8)];
+    let bit = 1 << (port % 8);
+
+    if allowed {
+        byte.fetch_and(!bit, Ordering::Relaxed);
+    } else {
+        byte.fetch_or(bit, Ordering::Relaxed);
+    }
+}
+
+/// Checks if ring 3 may access an I/O port.
+///
+/// # Arguments
+///
+/// * `port` - The port.
+///
+/// # Returns
+///
+/// * `bool` - Whether ring 3 may access the port.
+#[must_use]
+pub fn port_access(port: u16) -> bool {
+    TSS.io_bitmap[usize::from(port / 8)].load(Ordering::Relaxed) & 1 << (port % 8) == 0
+}
+
+/// Jumps to code in ring 3, with interrupts enabled.
+///
+/// Interrupts arriving while in ring 3 switch to the kernel stack in the TSS.
+///
+/// # Arguments
+///
+/// * `entry` - The address of the code to run.
+/// * `stack` - The top of the stack to run it on.
+///
+/// # Returns
+///
+/// * `!` - Never, the kernel only gets control back through interrupts and system calls.
+///
+/// # Safety
+///
+/// * The caller must make sure the code and the stack are mapped with the `USER_ACCESSIBLE` flag, and that the stack
+///   is large enough for the code.
+/// * Anything left on the current kernel stack is abandoned.
+pub unsafe fn enter_user_mode(entry: VirtAddr, stack: VirtAddr) -> ! {
+    use core::arch::asm;
+    use x86_64::registers::rflags::RFlags;
+
+    let code = u64::from(GDT.1.user_code_selector.0);
+    let data = u64::from(GDT.1.user_data_selector.0);
+    let rflags = (RFlags::INTERRUPT_FLAG | RFlags::from_bits_truncate(0x2)).bits();
+
+    // Build the frame `iretq` pops: the instruction pointer, code segment, flags, stack pointer and stack segment.
+    asm!(
+        "mov ds, {data:x}",
+        "mov es, {data:x}",
+        "push {data}",
+        "push {stack}",
+        "push {rflags}",
+        "push {code}",
+        "push {entry}",
+        "iretq",
+        data = in(reg) data,
+        stack = in(reg) stack.as_u64(),
+        rflags = in(reg) rflags,
+        code = in(reg) code,
+        entry = in(reg) entry.as_u64(),
+        options(noreturn),
+    );
+}
+
+/// Tests that ring 3 is denied every port until one is allowed, and that the TSS limit covers the bitmap.
+///
+/// # Panics
+///
+/// * If a port is allowed by default, or the permission doesn't change.
+/// * If the bitmap lies outside the TSS limit.
+#[test_case]
+fn test_port_access() {
+    assert!(!port_access(0x60));
+
+    set_port_access(0x60, true);
+    assert!(port_access(0x60));
+    assert!(!port_access(0x61));
+
+    set_port_access(0x60, false);
+    assert!(!port_access(0x60));
+
+    let Descriptor::SystemSegment(low, _) = tss_descriptor(&TSS) else {
+        unreachable!();
+    };
+    let limit = (low & 0xFFFF | (low >> 48 & 0xF) << 16) as usize;
+    assert_eq!(
+        limit + 1,
+        size_of::<TaskStateSegment>() + IO_BITMAP_SIZE + 1
+    );
+    assert_eq!(TSS.io_bitmap[IO_BITMAP_SIZE].load(Ordering::Relaxed), 0xFF);
+}
@@ -1,12 +1,20 @@
-use crate::println;
-use crate::sys::pic::{PICS, PIC_1_OFFSET, PIC_2_OFFSET};
+use alloc::format;
+
+use crate::errors::Error;
+use crate::mem;
+use crate::sys::pic::{self, PIC_1_OFFSET, PIC_2_OFFSET};
+use crate::sys::task::{timer, watchdog};
 use crate::sys::time::rtc::RTC;
-use crate::sys::{gdt, time};
-use core::sync::atomic::Ordering;
+use crate::sys::{apic, gdt, interrupt_controller, time};
+use crate::{println, try_println};
+use core::sync::atomic::{AtomicUsize, Ordering};
 use lazy_static::lazy_static;
+use spin::Mutex;
 use x86_64::instructions::port::Port;
 use x86_64::registers::control::Cr2;
-use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode};
+use x86_64::structures::idt::{
+    HandlerFunc, InterruptDescriptorTable, InterruptStackFrame, PageFaultErrorCode,
+};
 
 /// The interrupt indices.
 ///
@@ -15,6 +23,11 @@ use x86_64::structures::idt::{InterruptDescriptorTable, InterruptStackFrame, Pag
 /// 1. `Timer` - The timer interrupt (exists at [`PIC_1_OFFSET`]).
 /// 2. `Keyboard` - The keyboard interrupt, used for keyboard input (exists at [`PIC_1_OFFSET`] + 1).
 /// 3. `RTC` - The RTC interrupt, used for the RTC (exists at [`PIC_2_OFFSET`]).
+///
+/// # Notes
+///
+/// * These lines are handled by the kernel itself. Drivers register handlers for the other lines with
+///   [`set_interrupt_request_handler`].
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
 pub enum InterruptIndex {
@@ -33,6 +46,15 @@ impl InterruptIndex {
         self as u8
     }
 
+    /// Gets the interrupt request line of the interrupt index.
+    ///
+    /// # Returns
+    ///
+    /// * `u8` - The line, which the interrupt controller is told about.
+    const fn irq(self) -> u8 {
+        self.as_u8() - PIC_1_OFFSET
+    }
+
     /// Convert the interrupt index to a `usize`.
     ///
     /// # Returns
@@ -43,6 +65,184 @@ impl InterruptIndex {
     }
 }
 
+/// The number of breakpoint exceptions handled.
+static BREAKPOINTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Gets the number of breakpoint exceptions handled.
+///
+/// # Returns
+///
+/// * `usize` - The number of breakpoints.
+#[must_use]
+pub fn breakpoint_count() -> usize {
+    BREAKPOINTS.load(Ordering::Relaxed)
+}
+
+/// The number of spurious interrupts ignored.
+static SPURIOUS_INTERRUPTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Gets the number of spurious interrupts ignored.
+///
+/// # Returns
+///
+/// * `usize` - The number of spurious interrupts.
+#[must_use]
+pub fn spurious_interrupt_count() -> usize {
+    SPURIOUS_INTERRUPTS.load(Ordering::Relaxed)
+}
+
+/// The number of interrupt handlers running, counting nested ones.
+static INTERRUPT_DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/// Checks if the caller is running inside an interrupt handler.
+///
+/// # Returns
+///
+/// * `bool` - Whether or not an interrupt handler is running.
+///
+/// # Notes
+///
+/// * Only the hardware interrupt and breakpoint handlers are tracked, since the other exceptions panic anyway.
+#[must_use]
+pub fn in_interrupt() -> bool {
+    INTERRUPT_DEPTH.load(Ordering::Relaxed) > 0
+}
+
+/// Marks an interrupt handler as running, until it's dropped at the end of the handler.
+struct InterruptContext;
+
+impl InterruptContext {
+    /// Enters an interrupt handler.
+    ///
+    /// # Returns
+    ///
+    /// * `InterruptContext` - The marker, to keep alive for the rest of the handler.
+    fn enter() -> Self {
+        INTERRUPT_DEPTH.fetch_add(1, Ordering::Relaxed);
+
+        Self
+    }
+}
+
+impl Drop for InterruptContext {
+    /// Leaves the interrupt handler.
+    fn drop(&mut self) {
+        INTERRUPT_DEPTH.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// The number of interrupt request lines.
+const INTERRUPT_REQUEST_LINES: usize = 16;
+
+/// A handler of an interrupt request line, registered by a driver.
+pub type InterruptRequestHandler = fn();
+
+/// The handlers drivers registered for the interrupt request lines, indexed by line.
+static INTERRUPT_REQUEST_HANDLERS: Mutex<
+    [Option<InterruptRequestHandler>; INTERRUPT_REQUEST_LINES],
+> = Mutex::new([None; INTERRUPT_REQUEST_LINES]);
+
+/// Registers the handler of an interrupt request line, and enables the line.
+///
+/// The handler is called from a trampoline, which signals the end of the interrupt after it returns, so drivers can
+/// handle their interrupts without editing the IDT.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line.
+/// * `handler` - The handler, called with interrupts disabled.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the line doesn't exist.
+/// * If the line is one of the [`InterruptIndex`] lines the kernel handles itself.
+pub fn set_interrupt_request_handler(
+    irq: u8,
+    handler: InterruptRequestHandler,
+) -> Result<(), Error> {
+    use x86_64::instructions::interrupts;
+
+    let reserved = [
+        InterruptIndex::Timer,
+        InterruptIndex::Keyboard,
+        InterruptIndex::RTC,
+    ];
+    if usize::from(irq) >= INTERRUPT_REQUEST_LINES || irq == pic::CASCADE_IRQ {
+        return Err(Error::Internal(format!("IRQ {irq} doesn't exist!")));
+    }
+    if reserved.iter().any(|index| index.irq() == irq) {
+        return Err(Error::Internal(format!(
+            "IRQ {irq} is handled by the kernel!"
+        )));
+    }
+
+    interrupts::without_interrupts(|| {
+        INTERRUPT_REQUEST_HANDLERS.lock()[usize::from(irq)] = Some(handler);
+        interrupt_controller::enable(irq);
+    });
+
+    Ok(())
+}
+
+/// Calls the handler registered for an interrupt request line, and signals the end of the interrupt.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line that raised the interrupt.
+fn handle_interrupt_request(irq: u8) {
+    let _context = InterruptContext::enter();
+    if interrupt_controller::is_spurious(irq) {
+        SPURIOUS_INTERRUPTS.fetch_add(1, Ordering::Relaxed);
+
+        return;
+    }
+
+    // Copy the handler out, so the table isn't locked while it runs.
+    let handler = INTERRUPT_REQUEST_HANDLERS.lock()[usize::from(irq)];
+    if let Some(handler) = handler {
+        handler();
+    }
+
+    interrupt_controller::end_of_interrupt(irq);
+}
+
+/// Defines a trampoline per interrupt request line, passing the line to [`handle_interrupt_request`].
+macro_rules! interrupt_request_trampolines {
+    ($($irq:literal => $name:ident),* $(,)?) => {
+        $(
+            extern "x86-interrupt" fn $name(_stack_frame: InterruptStackFrame) {
+                handle_interrupt_request($irq);
+            }
+        )*
+
+        /// The trampolines, indexed by interrupt request line.
+        const TRAMPOLINES: [HandlerFunc; INTERRUPT_REQUEST_LINES] = [$($name),*];
+    };
+}
+
+interrupt_request_trampolines! {
+    0 => irq_0_trampoline,
+    1 => irq_1_trampoline,
+    2 => irq_2_trampoline,
+    3 => irq_3_trampoline,
+    4 => irq_4_trampoline,
+    5 => irq_5_trampoline,
+    6 => irq_6_trampoline,
+    7 => irq_7_trampoline,
+    8 => irq_8_trampoline,
+    9 => irq_9_trampoline,
+    10 => irq_10_trampoline,
+    11 => irq_11_trampoline,
+    12 => irq_12_trampoline,
+    13 => irq_13_trampoline,
+    14 => irq_14_trampoline,
+    15 => irq_15_trampoline,
+}
+
 /// Initializes the interrupt descriptor table.
 pub fn init() {
     IDT.load();
@@ -110,10 +310,14 @@ lazy_static! {
         // Set the breakpoint handler.
         idt.breakpoint.set_handler_fn(breakpoint_handler);
 
-        // Add the interrupt handlers.
+        // Route every interrupt request line to its trampoline, then put the kernel's own handlers over theirs.
+        for (irq, trampoline) in TRAMPOLINES.into_iter().enumerate() {
+            idt[usize::from(PIC_1_OFFSET) + irq].set_handler_fn(trampoline);
+        }
         idt[InterruptIndex::Timer.as_usize()].set_handler_fn(timer_interrupt_handler);
         idt[InterruptIndex::Keyboard.as_usize()].set_handler_fn(keyboard_interrupt_handler);
         idt[InterruptIndex::RTC.as_usize()].set_handler_fn(rtc_interrupt_handler);
+        idt[usize::from(apic::SPURIOUS_VECTOR)].set_handler_fn(spurious_interrupt_handler);
 
         idt
     };
@@ -179,6 +383,14 @@ extern "x86-interrupt" fn double_fault_handler(
     stack_frame: InterruptStackFrame,
     error_code: u64,
 ) -> ! {
+    // An overflow of a guarded stack double faults, since the page fault can't be pushed onto the full stack.
+    if let Some(slot) = mem::stack_overflow_slot(Cr2::read()) {
+        panic!(
+            "Double Fault Exception: Stack overflow in stack {slot}!\
+            \nStack Frame: {stack_frame:#?}"
+        );
+    }
+
     panic!(
         "Double Fault Exception!\
         \nError Code: {code}\
@@ -241,6 +453,13 @@ extern "x86-interrupt" fn page_fault_handler(
     stack_frame: InterruptStackFrame,
     error_code: PageFaultErrorCode,
 ) {
+    if let Some(slot) = mem::stack_overflow_slot(Cr2::read()) {
+        panic!(
+            "Page Fault Exception: Stack overflow in stack {slot}!\
+            \nStack Frame: {stack_frame:#?}"
+        );
+    }
+
     println!(
         "Page Fault Exception!\
         \nAddress: {addr:?}\
@@ -345,7 +564,10 @@ extern "x86-interrupt" fn security_exception_handler(
 }
 
 extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
-    println!(
+    let _context = InterruptContext::enter();
+    BREAKPOINTS.fetch_add(1, Ordering::Relaxed);
+
+    try_println!(
         "Breakpoint Exception!\
         \nStack Frame: {frame:#?}",
         frame = stack_frame
@@ -353,41 +575,61 @@ extern "x86-interrupt" fn breakpoint_handler(stack_frame: InterruptStackFrame) {
 }
 
 extern "x86-interrupt" fn timer_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    let _context = InterruptContext::enter();
     // Increment the PIT tick.
     time::PIT_TICK.fetch_add(1, Ordering::Relaxed);
+    watchdog::on_timer_tick();
+    timer::on_timer_tick();
+    crate::on_test_timer_tick();
 
-    unsafe {
-        PICS.lock()
-            .notify_end_of_interrupt(InterruptIndex::Timer.as_u8());
-    }
+    interrupt_controller::end_of_interrupt(InterruptIndex::Timer.irq());
 }
 
 extern "x86-interrupt" fn keyboard_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    let _context = InterruptContext::enter();
     let mut port = Port::new(0x60);
     let scancode: u8 = unsafe { port.read() };
     crate::sys::task::keyboard::add_scancode(scancode);
 
-    unsafe {
-        PICS.lock()
-            .notify_end_of_interrupt(InterruptIndex::Keyboard.as_u8());
-    }
+    interrupt_controller::end_of_interrupt(InterruptIndex::Keyboard.irq());
 }
 
 extern "x86-interrupt" fn rtc_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    let _context = InterruptContext::enter();
     // Store the last RTC update tick.
     time::LAST_RTC_UPDATE.store(time::tick(), Ordering::Relaxed);
 
     // Notify the RTC that the interrupt has ended.
     RTC::default().notify_interrupt_end();
 
-    unsafe {
-        PICS.lock()
-            .notify_end_of_interrupt(InterruptIndex::RTC.as_u8());
-    }
+    interrupt_controller::end_of_interrupt(InterruptIndex::RTC.irq());
 
     // crate::sys::task::clock::print(&RTC::new_no_check());
 }
 
+extern "x86-interrupt" fn spurious_interrupt_handler(_stack_frame: InterruptStackFrame) {
+    // The local APIC raises these without an interrupt in service, so there's no end of interrupt to signal.
+    SPURIOUS_INTERRUPTS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Tests that handlers can't be registered for lines that don't exist, or that the kernel handles itself.
+///
+/// # Panics
+///
+/// * If a handler is registered for such a line.
+#[test_case]
+fn test_set_interrupt_request_handler() {
+    for irq in [0, 1, pic::CASCADE_IRQ, 8, 16] {
+        assert!(set_interrupt_request_handler(irq, || {}).is_err());
+    }
+
+    assert!(INTERRUPT_REQUEST_HANDLERS
+        .lock()
+        .iter()
+        .take(2)
+        .all(Option::is_none));
+}
+
 #[test_case]
 fn test_breakpoint_exception() {
     // Invoke a breakpoint exception.
@@ -0,0 +1,178 @@
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::boot_args;
+use crate::errors::Error;
+use crate::println;
+use crate::sys::apic::Apic;
+use crate::sys::cpu::CPU_FEATURES;
+use crate::sys::pic::{self, PICS};
+
+/// A controller delivering the interrupt request lines to the CPU.
+///
+/// Line `n` is always delivered on vector [`pic::PIC_1_OFFSET`] + `n`, whichever controller is used.
+pub trait InterruptController: Send {
+    /// Gets the name of the controller.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name, like `PIC`.
+    fn name(&self) -> &'static str;
+
+    /// Unmasks an interrupt request line, so its interrupts are delivered.
+    ///
+    /// # Arguments
+    ///
+    /// * `irq` - The interrupt request line, from 0 to 15.
+    fn enable(&mut self, irq: u8);
+
+    /// Masks an interrupt request line, so its interrupts are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `irq` - The interrupt request line, from 0 to 15.
+    fn mask(&mut self, irq: u8);
+
+    /// Signals the end of an interrupt, so the controller delivers the next one.
+    ///
+    /// # Arguments
+    ///
+    /// * `irq` - The interrupt request line the interrupt came from.
+    fn end_of_interrupt(&mut self, irq: u8);
+
+    /// Checks if an interrupt was spurious, signalling the end of it as far as the controller needs.
+    ///
+    /// # Arguments
+    ///
+    /// * `irq` - The interrupt request line the interrupt came from.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether the interrupt was spurious, in which case it must be ignored without an end of interrupt.
+    fn is_spurious(&mut self, irq: u8) -> bool {
+        let _ = irq;
+
+        false
+    }
+}
+
+/// The APIC, once it has taken over from the PIC.
+static APIC: Mutex<Option<Apic>> = Mutex::new(None);
+
+/// Switches from the PIC to the APIC, unless the `noapic` boot argument is set or the CPU has no APIC.
+///
+/// The lines enabled on the PIC are enabled on the APIC, and then every PIC line is masked.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the APIC registers can't be mapped.
+///
+/// # Notes
+///
+/// * The APIC registers are device memory, so this must be called after `mem::init`.
+pub fn init() -> Result<(), Error> {
+    if boot_args::get().no_apic || !CPU_FEATURES.has_apic() {
+        println!("[INFO]: Using the PIC for interrupts.");
+
+        return Ok(());
+    }
+
+    interrupts::without_interrupts(|| {
+        // SAFETY: The CPU has an APIC, and interrupts are disabled until the PIC is masked.
+        let mut apic = unsafe { Apic::new() }?;
+
+        let masks = pic::masks();
+        for irq in (0..16).filter(|&irq| irq != pic::CASCADE_IRQ && masks & 1 << irq == 0) {
+            apic.enable(irq);
+        }
+
+        pic::mask_all();
+        *APIC.lock() = Some(apic);
+
+        Ok::<(), Error>(())
+    })?;
+
+    println!("[INFO]: Using the APIC for interrupts.");
+
+    Ok(())
+}
+
+/// Calls a function with the interrupt controller in use.
+///
+/// # Arguments
+///
+/// * `f` - The function.
+///
+/// # Returns
+///
+/// * `T` - What the function returned.
+fn with_controller<T>(f: impl FnOnce(&mut dyn InterruptController) -> T) -> T {
+    if let Some(apic) = APIC.lock().as_mut() {
+        return f(apic);
+    }
+
+    f(&mut *PICS.lock())
+}
+
+/// Gets the name of the interrupt controller in use.
+///
+/// # Returns
+///
+/// * `&'static str` - The name, like `PIC`.
+#[must_use]
+pub fn controller() -> &'static str {
+    interrupts::without_interrupts(|| with_controller(|controller| controller.name()))
+}
+
+/// Unmasks an interrupt request line on the interrupt controller in use.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line, from 0 to 15.
+pub fn enable(irq: u8) {
+    interrupts::without_interrupts(|| with_controller(|controller| controller.enable(irq)));
+}
+
+/// Masks an interrupt request line on the interrupt controller in use.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line, from 0 to 15.
+pub fn mask(irq: u8) {
+    interrupts::without_interrupts(|| with_controller(|controller| controller.mask(irq)));
+}
+
+/// Checks if an interrupt was spurious, on the interrupt controller in use.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line the interrupt came from.
+///
+/// # Returns
+///
+/// * `bool` - Whether the interrupt was spurious, in which case it must be ignored without an end of interrupt.
+///
+/// # Notes
+///
+/// * Only call this from the interrupt handler, where interrupts are disabled.
+#[must_use]
+pub fn is_spurious(irq: u8) -> bool {
+    with_controller(|controller| controller.is_spurious(irq))
+}
+
+/// Signals the end of an interrupt to the interrupt controller in use.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line the interrupt came from.
+///
+/// # Notes
+///
+/// * Only call this from the interrupt handler, where interrupts are disabled.
+pub fn end_of_interrupt(irq: u8) {
+    with_controller(|controller| controller.end_of_interrupt(irq));
+}
@@ -1,7 +1,14 @@
+pub mod apic;
 pub mod calls;
+pub mod cpu;
+pub mod fpu;
 pub mod gdt;
 pub mod idt;
+pub mod interrupt_controller;
 pub mod pic;
 pub mod pit;
+pub mod power;
+pub mod rand;
+pub mod selftest;
 pub mod task;
 pub mod time;
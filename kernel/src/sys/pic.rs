@@ -1,5 +1,9 @@
 use pic8259::ChainedPics;
 use spin::Mutex;
+use x86_64::instructions::port::Port;
+
+use crate::sys::interrupt_controller::InterruptController;
+use crate::sys::time;
 
 /// The first PIC offset, used for remapping.
 pub const PIC_1_OFFSET: u8 = 32;
@@ -7,6 +11,30 @@ pub const PIC_1_OFFSET: u8 = 32;
 /// The second PIC offset, used for remapping.
 pub const PIC_2_OFFSET: u8 = PIC_1_OFFSET + 8;
 
+/// The interrupt request line the second PIC is chained to the first one on.
+pub const CASCADE_IRQ: u8 = 2;
+
+/// The command port of the first PIC.
+const PIC_1_COMMAND: u16 = 0x20;
+
+/// The command port of the second PIC.
+const PIC_2_COMMAND: u16 = 0xA0;
+
+/// The command that reads the in-service register on the next read of the command port.
+const READ_IN_SERVICE: u8 = 0x0B;
+
+/// The command that signals the end of the interrupt in service.
+const END_OF_INTERRUPT: u8 = 0x20;
+
+/// The lowest priority line of each PIC, which a spurious interrupt shows up as.
+const SPURIOUS_IRQS: [u8; 2] = [7, 15];
+
+/// The data port of the first PIC, which holds its interrupt mask.
+const PIC_1_DATA: u16 = 0x21;
+
+/// The data port of the second PIC, which holds its interrupt mask.
+const PIC_2_DATA: u16 = 0xA1;
+
 /// The Programmable Interrupt Controller.
 ///
 /// # Notes
@@ -14,3 +42,149 @@ pub const PIC_2_OFFSET: u8 = PIC_1_OFFSET + 8;
 /// * This is a spinlock because it is shared between multiple CPUs.
 pub static PICS: Mutex<ChainedPics> =
     Mutex::new(unsafe { ChainedPics::new(PIC_1_OFFSET, PIC_2_OFFSET) });
+
+impl InterruptController for ChainedPics {
+    /// Gets the name of the controller.
+    fn name(&self) -> &'static str {
+        "PIC"
+    }
+
+    /// Unmasks a line in the mask of its PIC.
+    fn enable(&mut self, irq: u8) {
+        unmask(irq);
+    }
+
+    /// Masks a line in the mask of its PIC.
+    fn mask(&mut self, irq: u8) {
+        mask(irq);
+    }
+
+    /// Signals the end of an interrupt to the PICs it went through.
+    fn end_of_interrupt(&mut self, irq: u8) {
+        unsafe { self.notify_end_of_interrupt(PIC_1_OFFSET + irq) };
+    }
+
+    /// Checks the in-service register, since a spurious interrupt isn't in service.
+    ///
+    /// A PIC raises its lowest priority line when an interrupt goes away before the CPU acknowledges it. A spurious
+    /// IRQ 7 needs no end of interrupt at all. A spurious IRQ 15 came through the cascade, so the first PIC still
+    /// needs one.
+    fn is_spurious(&mut self, irq: u8) -> bool {
+        if !SPURIOUS_IRQS.contains(&irq) || in_service() & 1 << irq != 0 {
+            return false;
+        }
+
+        if irq >= 8 {
+            unsafe { Port::<u8>::new(PIC_1_COMMAND).write(END_OF_INTERRUPT) };
+        }
+
+        true
+    }
+}
+
+/// Gets the mask port of the PIC handling an interrupt request line, and the line's bit in it.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line, from 0 to 15.
+///
+/// # Returns
+///
+/// * `(Port<u8>, u8)` - The data port of the PIC and the line on it.
+fn mask_port(irq: u8) -> (Port<u8>, u8) {
+    if irq < 8 {
+        (Port::new(PIC_1_DATA), irq)
+    } else {
+        (Port::new(PIC_2_DATA), irq - 8)
+    }
+}
+
+/// Unmasks an interrupt request line, so the PICs deliver its interrupts.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line, from 0 to 15.
+pub fn unmask(irq: u8) {
+    let (mut port, line) = mask_port(irq);
+
+    unsafe {
+        let mask = port.read();
+        port.write(mask & !(1 << line));
+    }
+}
+
+/// Masks an interrupt request line, so the PICs ignore its interrupts.
+///
+/// # Arguments
+///
+/// * `irq` - The interrupt request line, from 0 to 15.
+pub fn mask(irq: u8) {
+    let (mut port, line) = mask_port(irq);
+
+    unsafe {
+        let mask = port.read();
+        port.write(mask | 1 << line);
+    }
+}
+
+/// Gets the interrupt masks of both PICs.
+///
+/// # Returns
+///
+/// * `u16` - A bit per interrupt request line, set if it's masked.
+#[must_use]
+pub fn masks() -> u16 {
+    let (mut first, mut second) = (Port::<u8>::new(PIC_1_DATA), Port::<u8>::new(PIC_2_DATA));
+
+    unsafe { u16::from(second.read()) << 8 | u16::from(first.read()) }
+}
+
+/// Gets the in-service registers of both PICs.
+///
+/// # Returns
+///
+/// * `u16` - A bit per interrupt request line, set if its interrupt is being handled.
+#[must_use]
+pub fn in_service() -> u16 {
+    let (mut first, mut second) = (
+        Port::<u8>::new(PIC_1_COMMAND),
+        Port::<u8>::new(PIC_2_COMMAND),
+    );
+
+    unsafe {
+        first.write(READ_IN_SERVICE);
+        second.write(READ_IN_SERVICE);
+        time::io_wait();
+
+        u16::from(second.read()) << 8 | u16::from(first.read())
+    }
+}
+
+/// Masks every interrupt request line, once another controller has taken over.
+pub fn mask_all() {
+    let (mut first, mut second) = (Port::<u8>::new(PIC_1_DATA), Port::<u8>::new(PIC_2_DATA));
+
+    unsafe {
+        first.write(0xFF);
+        second.write(0xFF);
+    }
+}
+
+/// Tests that only IRQ 7 and 15 can be spurious, and only when they aren't in service.
+///
+/// # Panics
+///
+/// * If another line is taken as spurious.
+/// * If IRQ 7 is in service outside of an interrupt handler.
+#[test_case]
+fn test_spurious() {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        let mut pics = PICS.lock();
+
+        assert!(!pics.is_spurious(1));
+        assert!(!pics.is_spurious(14));
+        assert!(pics.is_spurious(7));
+    });
+}
@@ -0,0 +1,52 @@
+use x86_64::instructions::port::Port;
+
+/// The ACPI power management ports of QEMU, Bochs and `VirtualBox`, and the values that power off the machine.
+const ACPI_SHUTDOWN_PORTS: [(u16, u16); 3] = [(0x604, 0x2000), (0xB004, 0x2000), (0x4004, 0x3400)];
+
+/// The command port of the 8042 keyboard controller.
+const KEYBOARD_CONTROLLER_PORT: u16 = 0x64;
+
+/// The 8042 command that pulses the CPU reset line.
+const KEYBOARD_CONTROLLER_RESET: u8 = 0xFE;
+
+/// Shuts down the machine.
+///
+/// # Returns
+///
+/// * `!` - Never.
+///
+/// # Notes
+///
+/// * If none of the known emulator shutdown ports work, the CPU is halted instead.
+pub fn shutdown() -> ! {
+    for (port, value) in ACPI_SHUTDOWN_PORTS {
+        let mut port: Port<u16> = Port::new(port);
+
+        unsafe {
+            port.write(value);
+        }
+    }
+
+    crate::println!("[WARN]: Failed to shut down, halting...");
+    crate::hlt_loop();
+}
+
+/// Reboots the machine by pulsing the CPU reset line through the 8042 keyboard controller.
+///
+/// # Returns
+///
+/// * `!` - Never.
+///
+/// # Notes
+///
+/// * If the reset doesn't happen, the CPU is halted instead.
+pub fn reboot() -> ! {
+    let mut port: Port<u8> = Port::new(KEYBOARD_CONTROLLER_PORT);
+
+    unsafe {
+        port.write(KEYBOARD_CONTROLLER_RESET);
+    }
+
+    crate::println!("[WARN]: Failed to reboot, halting...");
+    crate::hlt_loop();
+}
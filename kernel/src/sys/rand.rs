@@ -0,0 +1,158 @@
+use spin::Mutex;
+
+use crate::sys::cpu::CPU_FEATURES;
+use crate::sys::time::{self, rtc::RTC};
+
+/// The number of times `rdrand` is retried before falling back, as recommended by Intel.
+const RDRAND_RETRIES: usize = 10;
+
+/// The fallback generator, seeded on first use.
+static FALLBACK: Mutex<Option<Xorshift64Star>> = Mutex::new(None);
+
+/// A xorshift64* pseudo random number generator.
+///
+/// Fast and statistically decent, but predictable, so it's only a fallback for CPUs without `rdrand`.
+///
+/// # Fields
+///
+/// * `state` - The state, which is never zero.
+#[derive(Debug, Clone)]
+pub struct Xorshift64Star {
+    state: u64,
+}
+
+impl Xorshift64Star {
+    /// Creates a new `Xorshift64Star`.
+    ///
+    /// # Arguments
+    ///
+    /// * `seed` - The seed. Zero is replaced, since the generator would only ever produce zeros.
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self {
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+
+    /// Generates the next number.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
+
+/// Generates a random number with `rdrand`.
+///
+/// # Returns
+///
+/// * `Option<u64>` - The number, or `None` if the CPU ran out of entropy every try.
+///
+/// # Safety
+///
+/// * The caller must make sure the CPU supports `rdrand`.
+unsafe fn rdrand() -> Option<u64> {
+    for _ in 0..RDRAND_RETRIES {
+        let value: u64;
+        let success: u8;
+        core::arch::asm!(
+            "rdrand {value}",
+            "setc {success}",
+            value = out(reg) value,
+            success = out(reg_byte) success,
+            options(nomem, nostack),
+        );
+
+        if success != 0 {
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+/// Generates a random number.
+///
+/// # Returns
+///
+/// * `u64` - The number.
+///
+/// # Notes
+///
+/// * Uses `rdrand` when the CPU has it, otherwise a xorshift64* generator seeded from the time-stamp counter and the
+///   RTC, which isn't suitable for anything security related.
+pub fn random_u64() -> u64 {
+    if CPU_FEATURES.has_rdrand() {
+        // SAFETY: The CPU was just checked to support `rdrand`.
+        if let Some(value) = unsafe { rdrand() } {
+            return value;
+        }
+    }
+
+    FALLBACK
+        .lock()
+        .get_or_insert_with(|| {
+            let seed = time::read_tsc() ^ RTC::new_no_check().as_millis().rotate_left(32);
+
+            Xorshift64Star::new(seed)
+        })
+        .next_u64()
+}
+
+/// Fills a buffer with random bytes.
+///
+/// # Arguments
+///
+/// * `buffer` - The buffer to fill.
+pub fn fill_bytes(buffer: &mut [u8]) {
+    for chunk in buffer.chunks_mut(8) {
+        let bytes = random_u64().to_le_bytes();
+
+        chunk.copy_from_slice(&bytes[..chunk.len()]);
+    }
+}
+
+/// Tests that the fallback generator is deterministic for a seed, and never gets stuck at zero.
+///
+/// # Panics
+///
+/// * If two generators with the same seed differ.
+/// * If a zero seed only produces zeros.
+#[test_case]
+fn test_xorshift() {
+    let mut first = Xorshift64Star::new(42);
+    let mut second = Xorshift64Star::new(42);
+    for _ in 0..100 {
+        assert_eq!(first.next_u64(), second.next_u64());
+    }
+
+    let mut zero = Xorshift64Star::new(0);
+    assert!((0..100).any(|_| zero.next_u64() != 0));
+}
+
+/// Tests that random bytes fill the whole buffer.
+///
+/// # Panics
+///
+/// * If the tail of an odd sized buffer is never written.
+#[test_case]
+fn test_fill_bytes() {
+    let mut buffer = [0; 13];
+
+    // The chance of 5 zero bytes at the end, 8 times in a row, is negligible.
+    assert!((0..8).any(|_| {
+        fill_bytes(&mut buffer);
+
+        buffer[8..].iter().any(|&byte| byte != 0)
+    }));
+}
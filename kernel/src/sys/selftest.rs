@@ -0,0 +1,249 @@
+use alloc::alloc::{alloc, dealloc};
+use alloc::format;
+use core::alloc::Layout;
+
+use x86_64::{PhysAddr, VirtAddr};
+
+use crate::allocator::{self, fixed_size_block::BLOCK_SIZES};
+use crate::dev::ramdisk::{self, RamDisk};
+use crate::dev::BlockDevice;
+use crate::errors::Error;
+use crate::mem::{self, PHYSICAL_MEMORY_OFFSET};
+use crate::println;
+use crate::sys::idt;
+use crate::sys::time::{self, rtc::RTC};
+
+/// The physical address of the VGA text buffer, a mapping that always exists.
+const VGA_BUFFER_ADDRESS: u64 = 0xB8000;
+
+/// A runtime diagnostic of a kernel subsystem.
+///
+/// # Fields
+///
+/// * `name` - The name of the check.
+/// * `run` - Runs the check.
+struct Check {
+    name: &'static str,
+    run: fn() -> Result<(), Error>,
+}
+
+/// The checks run by [`run`], in order.
+const CHECKS: &[Check] = &[
+    Check {
+        name: "allocator",
+        run: check_allocator,
+    },
+    Check {
+        name: "ramdisk",
+        run: check_ramdisk,
+    },
+    Check {
+        name: "rtc",
+        run: check_rtc,
+    },
+    Check {
+        name: "paging",
+        run: check_paging,
+    },
+    Check {
+        name: "breakpoint",
+        run: check_breakpoint,
+    },
+];
+
+/// The outcome of a self-test.
+///
+/// # Fields
+///
+/// * `passed` - The number of checks that passed.
+/// * `failed` - The number of checks that failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+/// Runs every diagnostic, printing `[OK]` or `[FAIL]` for each.
+///
+/// Like the test runner, but in the booted kernel, so it can be used to diagnose a running system.
+///
+/// # Returns
+///
+/// * `Report` - How many checks passed and failed.
+#[must_use]
+pub fn run() -> Report {
+    let mut report = Report {
+        passed: 0,
+        failed: 0,
+    };
+
+    for check in CHECKS {
+        match (check.run)() {
+            Ok(()) => {
+                println!("{name}...\t[OK]", name = check.name);
+                report.passed += 1;
+            }
+            Err(why) => {
+                println!("{name}...\t[FAIL]: {why}", name = check.name);
+                report.failed += 1;
+            }
+        }
+    }
+
+    report
+}
+
+/// Allocates, writes, verifies and frees a block of every size class, and checks that nothing leaked.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the check.
+///
+/// # Errors
+///
+/// * If an allocation fails, is corrupted, or isn't returned to the heap.
+fn check_allocator() -> Result<(), Error> {
+    let used = allocator::heap_stats().used;
+
+    for &size in BLOCK_SIZES {
+        let layout = Layout::from_size_align(size, size)?;
+
+        // SAFETY: The layout has a non-zero size, and the block is freed with the same layout.
+        unsafe {
+            let block = alloc(layout);
+            if block.is_null() {
+                return Err(Error::Internal(format!("Failed to allocate {size} bytes!")));
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            let pattern = size as u8 ^ 0xA5;
+            block.write_bytes(pattern, size);
+            let intact = (0..size).all(|offset| *block.add(offset) == pattern);
+
+            dealloc(block, layout);
+            if !intact {
+                return Err(Error::Internal(format!(
+                    "A {size} byte block was corrupted!"
+                )));
+            }
+        }
+    }
+
+    let leaked = allocator::heap_stats().used.saturating_sub(used);
+    if leaked != 0 {
+        return Err(Error::Internal(format!("{leaked} bytes weren't freed!")));
+    }
+
+    Ok(())
+}
+
+/// Writes a scratch sector of a RAM disk and reads it back.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the check.
+///
+/// # Errors
+///
+/// * If the sector can't be written or read, or reads back different.
+///
+/// # Notes
+///
+/// * Uses a RAM disk of its own, so the mounted file system is never touched.
+fn check_ramdisk() -> Result<(), Error> {
+    let mut disk = RamDisk::new(2);
+
+    #[allow(clippy::cast_possible_truncation)]
+    let written: [u8; ramdisk::BLOCK_SIZE] = core::array::from_fn(|index| index as u8);
+    disk.write_block(1, &written)?;
+
+    let mut read = [0; ramdisk::BLOCK_SIZE];
+    disk.read_block(1, &mut read)?;
+
+    if read != written {
+        return Err(Error::Internal(
+            "The scratch sector read back different!".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reads the RTC twice, a little over a second apart, and checks that the time moved forward.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the check.
+///
+/// # Errors
+///
+/// * If the time didn't advance.
+fn check_rtc() -> Result<(), Error> {
+    let before = RTC::new().as_millis();
+    time::sleep(1.1);
+    let after = RTC::new().as_millis();
+
+    if after <= before {
+        return Err(Error::Internal(format!(
+            "The RTC went from {before}ms to {after}ms!"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Translates the VGA text buffer through the physical memory mapping, and checks that it maps back.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the check.
+///
+/// # Errors
+///
+/// * If the address isn't mapped, or maps to the wrong frame.
+fn check_paging() -> Result<(), Error> {
+    // SAFETY: The bootloader maps all of physical memory at the offset.
+    let physical = unsafe {
+        let offset = VirtAddr::new(PHYSICAL_MEMORY_OFFSET);
+
+        mem::translate_addr(offset + VGA_BUFFER_ADDRESS, offset)
+    };
+
+    match physical {
+        Some(address) if address == PhysAddr::new(VGA_BUFFER_ADDRESS) => Ok(()),
+        Some(address) => Err(Error::Internal(format!(
+            "The VGA buffer translated to {address:?}!"
+        ))),
+        None => Err(Error::Internal("The VGA buffer isn't mapped!".into())),
+    }
+}
+
+/// Triggers a breakpoint exception, and checks that the handler caught it.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the check.
+///
+/// # Errors
+///
+/// * If the handler didn't run.
+fn check_breakpoint() -> Result<(), Error> {
+    let before = idt::breakpoint_count();
+    x86_64::instructions::interrupts::int3();
+
+    if idt::breakpoint_count() == before {
+        return Err(Error::Internal("The breakpoint wasn't handled!".into()));
+    }
+
+    Ok(())
+}
+
+/// Tests that every check passes in the test kernel.
+///
+/// # Panics
+///
+/// * If a check fails.
+#[test_case]
+fn test_selftest() {
+    assert_eq!(run().failed, 0);
+}
@@ -0,0 +1,243 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use crossbeam_queue::ArrayQueue;
+use futures_util::task::AtomicWaker;
+
+/// A bounded channel, passing values from one task to another.
+///
+/// Share it between the tasks with an `Arc`.
+///
+/// # Fields
+///
+/// * `queue` - The values sent but not received yet.
+/// * `sender` - The waker of the task waiting to send, while the queue is full.
+/// * `receiver` - The waker of the task waiting to receive, while the queue is empty.
+///
+/// # Notes
+///
+/// * Only one waiting sender and one waiting receiver are woken, so use a channel per producer and consumer pair.
+pub struct Channel<T> {
+    queue: ArrayQueue<T>,
+    sender: AtomicWaker,
+    receiver: AtomicWaker,
+}
+
+impl<T> Channel<T> {
+    /// Creates a new `Channel`.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - The number of values the channel can hold before senders have to wait.
+    ///
+    /// # Panics
+    ///
+    /// * If the capacity is zero.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queue: ArrayQueue::new(capacity),
+            sender: AtomicWaker::new(),
+            receiver: AtomicWaker::new(),
+        }
+    }
+
+    /// Sends a value, waiting while the channel is full.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to send.
+    ///
+    /// # Returns
+    ///
+    /// * `SendFuture<T>` - A future that resolves once the value is sent.
+    pub const fn send(&self, value: T) -> SendFuture<'_, T> {
+        SendFuture {
+            channel: self,
+            value: Some(value),
+        }
+    }
+
+    /// Receives a value, waiting while the channel is empty.
+    ///
+    /// # Returns
+    ///
+    /// * `RecvFuture<T>` - A future that resolves to the value.
+    pub const fn recv(&self) -> RecvFuture<'_, T> {
+        RecvFuture { channel: self }
+    }
+
+    /// Sends a value, if the channel isn't full.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to send.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<(), T>` - The value back, if the channel is full.
+    ///
+    /// # Errors
+    ///
+    /// * If the channel is full.
+    pub fn try_send(&self, value: T) -> Result<(), T> {
+        self.queue.push(value)?;
+        self.receiver.wake();
+
+        Ok(())
+    }
+
+    /// Receives a value, if one is waiting.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<T>` - The value, if one is waiting.
+    pub fn try_recv(&self) -> Option<T> {
+        let value = self.queue.pop()?;
+        self.sender.wake();
+
+        Some(value)
+    }
+}
+
+/// A future that sends a value through a [`Channel`].
+///
+/// # Fields
+///
+/// * `channel` - The channel.
+/// * `value` - The value, until it's sent.
+pub struct SendFuture<'a, T> {
+    channel: &'a Channel<T>,
+    value: Option<T>,
+}
+
+// The value is never pinned, so the future can be moved freely.
+impl<T> Unpin for SendFuture<'_, T> {}
+
+impl<T> Future for SendFuture<'_, T> {
+    type Output = ();
+
+    /// Tries to send the value.
+    ///
+    /// # Arguments
+    ///
+    /// * `cx` - The context to use for polling.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<()>` - Ready once the value is sent.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+        let this = self.get_mut();
+        let Some(value) = this.value.take() else {
+            return Poll::Ready(());
+        };
+
+        // Fast path if there's room.
+        let value = match this.channel.try_send(value) {
+            Ok(()) => return Poll::Ready(()),
+            Err(value) => value,
+        };
+
+        // Register first, so a receive between the check and the registration isn't missed.
+        this.channel.sender.register(cx.waker());
+        match this.channel.try_send(value) {
+            Ok(()) => {
+                this.channel.sender.take();
+
+                Poll::Ready(())
+            }
+            Err(value) => {
+                this.value = Some(value);
+
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A future that receives a value from a [`Channel`].
+///
+/// # Fields
+///
+/// * `channel` - The channel.
+pub struct RecvFuture<'a, T> {
+    channel: &'a Channel<T>,
+}
+
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = T;
+
+    /// Tries to receive a value.
+    ///
+    /// # Arguments
+    ///
+    /// * `cx` - The context to use for polling.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<T>` - The value, once one is received.
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<T> {
+        // Fast path if a value is waiting.
+        if let Some(value) = self.channel.try_recv() {
+            return Poll::Ready(value);
+        }
+
+        // Register first, so a send between the check and the registration isn't missed.
+        self.channel.receiver.register(cx.waker());
+        match self.channel.try_recv() {
+            Some(value) => {
+                self.channel.receiver.take();
+
+                Poll::Ready(value)
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// Tests that a producer and a consumer task pass every value through a channel smaller than the values sent.
+///
+/// # Panics
+///
+/// * If spawning the tasks fails.
+/// * If the values aren't received in order.
+/// * If the tasks don't complete.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_channel() {
+    use alloc::rc::Rc;
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use super::executor::Executor;
+    use super::Task;
+
+    let channel = Arc::new(Channel::new(2));
+    let received = Rc::new(RefCell::new(Vec::new()));
+
+    let mut executor = Executor::new();
+    let producer = channel.clone();
+    executor
+        .spawn(Task::new(async move {
+            for value in 0..10 {
+                producer.send(value).await;
+            }
+        }))
+        .expect("Failed to spawn the producer!");
+
+    let consumer = received.clone();
+    executor
+        .spawn(Task::new(async move {
+            for _ in 0..10 {
+                let value = channel.recv().await;
+                consumer.borrow_mut().push(value);
+            }
+        }))
+        .expect("Failed to spawn the consumer!");
+
+    executor.run_until_idle();
+    assert_eq!(executor.task_count(), 0);
+    assert_eq!(*received.borrow(), (0..10).collect::<Vec<_>>());
+}
@@ -0,0 +1,91 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// A future that runs long synchronous work cooperatively, a bounded chunk per poll.
+///
+/// Each poll hands the step function a budget of work units, and if the work isn't done yet the task wakes itself
+/// and yields, so the executor can run other tasks, like the keyboard, in between chunks.
+///
+/// # Fields
+///
+/// * `step` - Does up to the given amount of work, returning the output once the work is done.
+/// * `chunk_size` - The amount of work done per poll.
+///
+/// # Notes
+///
+/// * The chunk size trades throughput for responsiveness. Small chunks keep other tasks responsive, but spend more
+///   time going through the executor. Large chunks finish sooner, but other tasks wait for a whole chunk before they
+///   run. A chunk should take well under a timer tick, and never near the watchdog timeout.
+pub struct ChunkedTask<F> {
+    step: F,
+    chunk_size: usize,
+}
+
+impl<F> ChunkedTask<F> {
+    /// Creates a new `ChunkedTask`.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size` - The amount of work done per poll, at least 1.
+    /// * `step` - Does up to the given amount of work, returning the output once the work is done.
+    #[must_use]
+    pub fn new(chunk_size: usize, step: F) -> Self {
+        Self {
+            step,
+            chunk_size: chunk_size.max(1),
+        }
+    }
+}
+
+impl<T, F: FnMut(usize) -> Poll<T> + Unpin> Future for ChunkedTask<F> {
+    type Output = T;
+
+    /// Runs the next chunk of work.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The context to use for polling.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<T>` - The output, once all the work is done.
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<T> {
+        let this = self.get_mut();
+        let poll = (this.step)(this.chunk_size);
+        if poll.is_pending() {
+            // Nothing else will wake us, so ask to be polled again after the other ready tasks.
+            context.waker().wake_by_ref();
+        }
+
+        poll
+    }
+}
+
+/// Tests that the work is split into chunks, yielding in between.
+///
+/// # Panics
+///
+/// * If a chunk does more work than the chunk size.
+/// * If the task doesn't yield between chunks.
+#[test_case]
+fn test_chunked_task() {
+    use futures_util::task::noop_waker_ref;
+
+    let mut remaining = 10;
+    let mut task = ChunkedTask::new(4, |budget: usize| {
+        assert!(budget <= 4);
+
+        remaining -= budget.min(remaining);
+        if remaining == 0 {
+            Poll::Ready("done")
+        } else {
+            Poll::Pending
+        }
+    });
+
+    let mut context = Context::from_waker(noop_waker_ref());
+    assert_eq!(Pin::new(&mut task).poll(&mut context), Poll::Pending);
+    assert_eq!(Pin::new(&mut task).poll(&mut context), Poll::Pending);
+    assert_eq!(Pin::new(&mut task).poll(&mut context), Poll::Ready("done"));
+}
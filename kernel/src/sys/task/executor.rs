@@ -1,11 +1,18 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
 use alloc::task::Wake;
+use alloc::vec::Vec;
 use alloc::{collections::BTreeMap, sync::Arc};
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::Pin;
 use core::task::{Context, Poll, Waker};
 
 use crate::errors::Error;
 use crossbeam_queue::ArrayQueue;
 
-use super::{Identifier, Task};
+use super::chunked::ChunkedTask;
+use super::{watchdog, Identifier, Task};
 
 /// The task executor.
 ///
@@ -16,10 +23,14 @@ use super::{Identifier, Task};
 /// * `tasks`: The tasks to be executed.
 /// * `task_queue`: The queue of task IDs.
 /// * `waker_cache`: The cache of task wakers.
+/// * `spawned`: The tasks spawned through a [`Spawner`], which haven't been moved into `tasks` yet.
+/// * `idle_hooks`: The callbacks run each time the executor goes idle.
 pub struct Executor {
     tasks: BTreeMap<Identifier, Task>,
     task_queue: Arc<ArrayQueue<Identifier>>,
     waker_cache: BTreeMap<Identifier, Waker>,
+    spawned: Rc<RefCell<Vec<Task>>>,
+    idle_hooks: Vec<Box<dyn FnMut()>>,
 }
 
 impl Executor {
@@ -30,6 +41,44 @@ impl Executor {
             tasks: BTreeMap::new(),
             task_queue: Arc::new(ArrayQueue::new(100)),
             waker_cache: BTreeMap::new(),
+            spawned: Rc::new(RefCell::new(Vec::new())),
+            idle_hooks: Vec::new(),
+        }
+    }
+
+    /// Gets the number of tasks that haven't completed yet.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of alive tasks, including those spawned but not polled yet.
+    #[must_use]
+    pub fn task_count(&self) -> usize {
+        self.tasks.len() + self.spawned.borrow().len()
+    }
+
+    /// Registers a callback to run each time the executor goes idle, right before it sleeps.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook`: The callback.
+    ///
+    /// # Notes
+    ///
+    /// * The callback runs with interrupts enabled, but shouldn't block, since no task runs until it returns.
+    pub fn on_idle(&mut self, hook: impl FnMut() + 'static) {
+        self.idle_hooks.push(Box::new(hook));
+    }
+
+    /// Creates a spawner, which lets running tasks spawn other tasks.
+    ///
+    /// # Returns
+    ///
+    /// * `Spawner` - The spawner.
+    #[must_use]
+    pub fn spawner(&self) -> Spawner {
+        Spawner {
+            task_queue: self.task_queue.clone(),
+            spawned: self.spawned.clone(),
         }
     }
 
@@ -71,9 +120,16 @@ impl Executor {
             tasks,
             task_queue,
             waker_cache,
+            spawned,
+            ..
         } = self;
 
         while let Some(task_id) = task_queue.pop() {
+            // Tasks spawned by other tasks are queued before they're moved in.
+            for task in spawned.borrow_mut().drain(..) {
+                tasks.insert(task.id, task);
+            }
+
             let Some(task) = tasks.get_mut(&task_id) else {
                 continue;
             };
@@ -84,7 +140,12 @@ impl Executor {
 
             let mut context = Context::from_waker(waker);
 
-            match task.poll(&mut context) {
+            watchdog::set_running(Some(task_id));
+            let poll = task.poll(&mut context);
+            watchdog::set_running(None);
+            watchdog::pet();
+
+            match poll {
                 Poll::Ready(()) => {
                     // Task done -> remove it and its cached waker.
                     tasks.remove(&task_id);
@@ -101,16 +162,35 @@ impl Executor {
     pub fn run(&mut self) -> ! {
         loop {
             self.run_ready_tasks();
+
+            // An idle executor is still making progress.
+            watchdog::pet();
             self.sleep_if_idle();
         }
     }
 
+    /// Runs tasks until none are ready.
+    ///
+    /// Unlike [`Executor::run`], this returns once the executor would go to sleep, which lets tests drive tasks to
+    /// completion.
+    pub fn run_until_idle(&mut self) {
+        while !self.task_queue.is_empty() {
+            self.run_ready_tasks();
+        }
+    }
+
     /// Sleeps if the executor is idle.
     ///
-    /// This function sleeps if the executor is idle.
-    fn sleep_if_idle(&self) {
+    /// This function runs the idle hooks, then sleeps if the executor is still idle.
+    fn sleep_if_idle(&mut self) {
         use x86_64::instructions::interrupts::{self, enable_and_hlt};
 
+        if self.task_queue.is_empty() {
+            for hook in &mut self.idle_hooks {
+                hook();
+            }
+        }
+
         interrupts::disable();
         if self.task_queue.is_empty() {
             enable_and_hlt();
@@ -126,6 +206,129 @@ impl Default for Executor {
     }
 }
 
+/// A handle used by running tasks to spawn other tasks on the executor.
+///
+/// # Fields
+///
+/// * `task_queue`: The queue of task IDs of the executor.
+/// * `spawned`: The tasks spawned, which the executor hasn't moved in yet.
+#[derive(Clone)]
+pub struct Spawner {
+    task_queue: Arc<ArrayQueue<Identifier>>,
+    spawned: Rc<RefCell<Vec<Task>>>,
+}
+
+impl Spawner {
+    /// Spawns a future as a new task.
+    ///
+    /// # Arguments
+    ///
+    /// * `future`: The future to run.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<JoinHandle<T>, Error>` - A handle that resolves to the output of the future.
+    ///
+    /// # Errors
+    ///
+    /// * If the task queue is full.
+    pub fn spawn<T: 'static>(
+        &self,
+        future: impl Future<Output = T> + 'static,
+    ) -> Result<JoinHandle<T>, Error> {
+        let state = Rc::new(RefCell::new(JoinState {
+            output: None,
+            waker: None,
+        }));
+
+        let task_state = state.clone();
+        let task = Task::new(async move {
+            let output = future.await;
+
+            let mut state = task_state.borrow_mut();
+            state.output = Some(output);
+            if let Some(waker) = state.waker.take() {
+                waker.wake();
+            }
+        });
+
+        let task_id = task.id;
+        self.spawned.borrow_mut().push(task);
+        self.task_queue.push(task_id)?;
+
+        Ok(JoinHandle { state })
+    }
+
+    /// Spawns long synchronous work as a new task, run a chunk at a time.
+    ///
+    /// The task yields between chunks, so other tasks keep running while the work is done. See [`ChunkedTask`] for
+    /// how to pick the chunk size.
+    ///
+    /// # Arguments
+    ///
+    /// * `chunk_size`: The amount of work done per poll.
+    /// * `step`: Does up to the given amount of work, returning the output once the work is done.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<JoinHandle<T>, Error>` - A handle that resolves to the output of the work.
+    ///
+    /// # Errors
+    ///
+    /// * If the task queue is full.
+    pub fn spawn_chunked<T: 'static>(
+        &self,
+        chunk_size: usize,
+        step: impl FnMut(usize) -> Poll<T> + Unpin + 'static,
+    ) -> Result<JoinHandle<T>, Error> {
+        self.spawn(ChunkedTask::new(chunk_size, step))
+    }
+}
+
+/// The state shared between a spawned task and its [`JoinHandle`].
+///
+/// # Fields
+///
+/// * `output`: The output of the task, once it's done.
+/// * `waker`: The waker of the task awaiting the handle.
+struct JoinState<T> {
+    output: Option<T>,
+    waker: Option<Waker>,
+}
+
+/// A future that resolves to the output of a spawned task.
+///
+/// # Fields
+///
+/// * `state`: The state shared with the task.
+pub struct JoinHandle<T> {
+    state: Rc<RefCell<JoinState<T>>>,
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = T;
+
+    /// Polls the spawned task for its output.
+    ///
+    /// # Arguments
+    ///
+    /// * `context`: The context to use for polling.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<T>` - The output of the task, once it's done.
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<T> {
+        let mut state = self.state.borrow_mut();
+        if let Some(output) = state.output.take() {
+            return Poll::Ready(output);
+        }
+
+        state.waker = Some(context.waker().clone());
+
+        Poll::Pending
+    }
+}
+
 /// The task waker.
 ///
 /// This is a simple task waker that wakes tasks on a single thread.
@@ -180,3 +383,28 @@ impl Wake for TaskWaker {
         self.wake_task();
     }
 }
+
+/// Tests that spawned tasks are counted until they complete.
+///
+/// # Panics
+///
+/// * If spawning a task fails.
+/// * If the task count is wrong before or after running the tasks.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_task_count() {
+    let mut executor = Executor::new();
+    let spawner = executor.spawner();
+    executor
+        .spawn(Task::new(async move {
+            spawner
+                .spawn(async {})
+                .expect("Failed to spawn the inner task!")
+                .await;
+        }))
+        .expect("Failed to spawn the outer task!");
+    assert_eq!(executor.task_count(), 1);
+
+    executor.run_until_idle();
+    assert_eq!(executor.task_count(), 0);
+}
@@ -1,24 +1,100 @@
+use alloc::collections::VecDeque;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec;
+use core::mem;
+use core::ops::ControlFlow;
 use core::pin::Pin;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use core::task::{Context, Poll};
 
 use conquer_once::spin::OnceCell;
 use crossbeam_queue::ArrayQueue;
 use futures_util::task::AtomicWaker;
 use futures_util::{Stream, StreamExt};
-use pc_keyboard::{layouts, DecodedKey, HandleControl, Keyboard, ScancodeSet1};
+use pc_keyboard::{layouts, HandleControl, KeyCode, KeyEvent, KeyState, Keyboard, ScancodeSet1};
+use spin::Mutex;
 
 use crate::print;
 use crate::println;
+use crate::sys::task::channel::Channel;
+
+// Re-exported, since the decoded keys are handed out to other crates.
+pub use pc_keyboard::DecodedKey;
 
 /// The scancode queue.
 static SCANCODE_QUEUE: OnceCell<ArrayQueue<u8>> = OnceCell::uninit();
+/// The active keyboard layout.
+///
+/// This is consulted every time a scancode is decoded, so changes take effect immediately.
+static LAYOUT: Mutex<Layout> = Mutex::new(Layout::Us104Key);
+/// The state of the modifier keys.
+///
+/// This is updated as key events are decoded, and can be read from outside the keyboard task.
+static MODIFIERS: Mutex<Modifiers> = Mutex::new(Modifiers::new());
+/// The decoder used by [`try_read_key`].
+///
+/// This is kept between calls, so scancodes spanning multiple calls are still decoded.
+static DECODER: Mutex<Decoder> = Mutex::new(Decoder::new(Layout::Us104Key));
 /// The waker.
 ///
 /// This is used to wake up the `read_line` function when a scancode is received.
 static WAKER: AtomicWaker = AtomicWaker::new();
+/// The number of scancodes dropped because the [`SCANCODE_QUEUE`] was full, since the last report.
+static DROPPED_SCANCODES: AtomicUsize = AtomicUsize::new(0);
+/// The keys typed ahead past the end of a line, taken while checking for a paste, which the next read starts with.
+static TYPE_AHEAD: Mutex<VecDeque<DecodedKey>> = Mutex::new(VecDeque::new());
+
+/// The number of keys that have to be waiting at once to be taken as a paste.
+///
+/// # Notes
+///
+/// * Keys pile up while a command runs, so this leaves room for typing a command or two ahead.
+const PASTE_THRESHOLD: usize = 32;
 
-/// The size of the scancode queue.
-const SCANCODE_QUEUE_SIZE: usize = 100;
+/// The default size of the scancode queue.
+///
+/// # Notes
+///
+/// * A character can take up to four scancodes with `Shift`, so this holds a pasted line of a few hundred characters
+///   while the reader catches up.
+pub const SCANCODE_QUEUE_SIZE: usize = 1024;
+
+/// Initializes the [`SCANCODE_QUEUE`] with the given capacity.
+///
+/// # Arguments
+///
+/// * `capacity` - The number of scancodes the queue can hold.
+///
+/// # Notes
+///
+/// * If a consumer already created the queue with the default capacity, that queue is kept.
+pub fn init(capacity: usize) {
+    init_queue(&SCANCODE_QUEUE, capacity);
+}
+
+/// Initializes a scancode queue with the given capacity, unless it already is.
+///
+/// # Arguments
+///
+/// * `queue` - The queue.
+/// * `capacity` - The number of scancodes the queue can hold.
+fn init_queue(queue: &OnceCell<ArrayQueue<u8>>, capacity: usize) {
+    // Already initialized is fine, the queue is shared either way.
+    let _ = queue.try_init_once(|| ArrayQueue::new(capacity));
+}
+
+/// Gets the [`SCANCODE_QUEUE`], initializing it with the default capacity on first use.
+///
+/// Both the interrupt handler and the consumers go through this, so they always share the same queue no matter which
+/// side touches it first.
+///
+/// # Returns
+///
+/// * `&'static ArrayQueue<u8>` - The scancode queue.
+fn scancode_queue() -> &'static ArrayQueue<u8> {
+    SCANCODE_QUEUE.get_or_init(|| ArrayQueue::new(SCANCODE_QUEUE_SIZE))
+}
 
 /// Called by the keyboard interrupt handler
 ///
@@ -28,22 +104,53 @@ const SCANCODE_QUEUE_SIZE: usize = 100;
 ///
 /// * `scancode` - The scancode received from the keyboard.
 ///
-/// # Panics
+/// # Notes
 ///
-/// * If the scancode queue is not initialized.
-/// * If the scancode queue is full.
+/// * If the scancode queue is full, or isn't created yet because the key was pressed during boot, the scancode is
+///   dropped and counted, to be reported by the next consumer. Printing here could deadlock on the `WRITER` lock.
 pub(crate) fn add_scancode(scancode: u8) {
-    if SCANCODE_QUEUE
-        .get_or_init(|| ArrayQueue::new(SCANCODE_QUEUE_SIZE))
-        .push(scancode)
-        .is_err()
-    {
-        println!("[WARN]: Scancode queue full, dropping keyboard input...");
-    }
+    push_scancode(&SCANCODE_QUEUE, scancode);
 
     WAKER.wake();
 }
 
+/// Pushes a scancode into a scancode queue, counting it as dropped if the queue is full or not created yet.
+///
+/// # Arguments
+///
+/// * `queue` - The queue.
+/// * `scancode` - The scancode.
+fn push_scancode(queue: &OnceCell<ArrayQueue<u8>>, scancode: u8) {
+    // Never creates the queue, since that would allocate, possibly before the heap is even set up.
+    let pushed = queue
+        .get()
+        .is_some_and(|queue| queue.push(scancode).is_ok());
+    if !pushed {
+        DROPPED_SCANCODES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Takes the number of scancodes dropped since the last call.
+///
+/// # Returns
+///
+/// * `usize` - The number of dropped scancodes.
+pub fn take_dropped_scancodes() -> usize {
+    DROPPED_SCANCODES.swap(0, Ordering::Relaxed)
+}
+
+/// Prints a warning if any scancodes were dropped since the last report.
+///
+/// Called by the consumers of the [`SCANCODE_QUEUE`], outside of the interrupt handler.
+fn report_dropped_scancodes() {
+    let dropped = take_dropped_scancodes();
+    if dropped > 0 {
+        println!(
+            "[WARN]: Scancode queue was full, dropped {dropped} scancode(s) of keyboard input!"
+        );
+    }
+}
+
 /// An API for interacting with the [`SCANCODE_QUEUE`].
 #[derive(Clone, Copy)]
 pub struct ScancodeStream;
@@ -69,12 +176,9 @@ impl Stream for ScancodeStream {
     /// # Returns
     ///
     /// * `Poll<Option<u8>>` - The next scancode, if available.
-    ///
-    /// # Notes
-    ///
-    /// * If the [`SCANCODE_QUEUE`] is uninitialized, it will be initialized with a capacity of [`SCANCODE_QUEUE_SIZE`].
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<u8>> {
-        let queue = SCANCODE_QUEUE.get_or_init(|| ArrayQueue::new(SCANCODE_QUEUE_SIZE));
+        let queue = scancode_queue();
+        report_dropped_scancodes();
 
         // Fast path if we have already received a scancode.
         if let Some(scancode) = queue.pop() {
@@ -90,26 +194,777 @@ impl Stream for ScancodeStream {
     }
 }
 
+/// The supported keyboard layouts.
+///
+/// # Variants
+///
+/// * `Us104Key` - The US 104-key layout.
+/// * `De105Key` - The German 105-key layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    Us104Key,
+    De105Key,
+}
+
+impl Layout {
+    /// All supported layouts.
+    pub const ALL: [Self; 2] = [Self::Us104Key, Self::De105Key];
+
+    /// Gets the layout with the given name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the layout, e.g. `us` or `de`.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Layout>` - The layout, if one with the given name exists.
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|layout| layout.name() == name)
+    }
+
+    /// Gets the name of the layout.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name of the layout.
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Us104Key => "us",
+            Self::De105Key => "de",
+        }
+    }
+}
+
+/// Gets the active keyboard layout.
+///
+/// # Returns
+///
+/// * `Layout` - The active keyboard layout.
+#[must_use]
+pub fn layout() -> Layout {
+    *LAYOUT.lock()
+}
+
+/// Sets the active keyboard layout.
+///
+/// # Arguments
+///
+/// * `layout` - The layout to switch to.
+pub fn set_layout(layout: Layout) {
+    *LAYOUT.lock() = layout;
+}
+
+/// The state of the modifier keys.
+///
+/// # Fields
+///
+/// * `shift` - Whether or not a shift key is held.
+/// * `ctrl` - Whether or not a control key is held.
+/// * `alt` - Whether or not an alt key is held.
+/// * `caps` - Whether or not caps lock is on.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub caps: bool,
+}
+
+impl Modifiers {
+    /// Creates a new `Modifiers` with no modifiers active.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            shift: false,
+            ctrl: false,
+            alt: false,
+            caps: false,
+        }
+    }
+
+    /// Updates the modifier state from the given key event.
+    ///
+    /// # Arguments
+    ///
+    /// * `key_event` - The key event.
+    fn update(&mut self, key_event: &KeyEvent) {
+        let down = key_event.state != KeyState::Up;
+
+        match key_event.code {
+            KeyCode::LShift | KeyCode::RShift => self.shift = down,
+            KeyCode::LControl | KeyCode::RControl => self.ctrl = down,
+            KeyCode::LAlt | KeyCode::RAltGr => self.alt = down,
+            KeyCode::CapsLock if key_event.state == KeyState::Down => self.caps = !self.caps,
+            _ => {}
+        }
+    }
+}
+
+/// Gets the state of the modifier keys.
+///
+/// # Returns
+///
+/// * `Modifiers` - The state of the modifier keys.
+#[must_use]
+pub fn modifiers() -> Modifiers {
+    *MODIFIERS.lock()
+}
+
+/// A scancode decoder for any of the supported layouts.
+///
+/// `pc_keyboard::Keyboard` is generic over its layout, so this wraps one instance per layout.
+///
+/// # Variants
+///
+/// * `Us104Key` - A decoder for the US 104-key layout.
+/// * `De105Key` - A decoder for the German 105-key layout.
+enum Decoder {
+    Us104Key(Keyboard<layouts::Us104Key, ScancodeSet1>),
+    De105Key(Keyboard<layouts::De105Key, ScancodeSet1>),
+}
+
+impl Decoder {
+    /// Creates a new decoder for the given layout.
+    ///
+    /// # Arguments
+    ///
+    /// * `layout` - The layout to decode with.
+    const fn new(layout: Layout) -> Self {
+        match layout {
+            Layout::Us104Key => Self::Us104Key(Keyboard::new(
+                ScancodeSet1::new(),
+                layouts::Us104Key,
+                HandleControl::MapLettersToUnicode,
+            )),
+            Layout::De105Key => Self::De105Key(Keyboard::new(
+                ScancodeSet1::new(),
+                layouts::De105Key,
+                HandleControl::MapLettersToUnicode,
+            )),
+        }
+    }
+
+    /// Gets the layout of the decoder.
+    ///
+    /// # Returns
+    ///
+    /// * `Layout` - The layout of the decoder.
+    const fn layout(&self) -> Layout {
+        match self {
+            Self::Us104Key(_) => Layout::Us104Key,
+            Self::De105Key(_) => Layout::De105Key,
+        }
+    }
+
+    /// Decodes the given scancode.
+    ///
+    /// If the active layout has changed since the last call, the decoder is rebuilt for the new layout first.
+    ///
+    /// # Arguments
+    ///
+    /// * `scancode` - The scancode to decode.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<DecodedKey>` - The decoded key, if the scancode completed one.
+    fn decode(&mut self, scancode: u8) -> Option<DecodedKey> {
+        let layout = layout();
+        if self.layout() != layout {
+            *self = Self::new(layout);
+        }
+
+        match self {
+            Self::Us104Key(keyboard) => {
+                let key_event = keyboard.add_byte(scancode).ok()??;
+                MODIFIERS.lock().update(&key_event);
+
+                keyboard.process_keyevent(key_event)
+            }
+            Self::De105Key(keyboard) => {
+                let key_event = keyboard.add_byte(scancode).ok()??;
+                MODIFIERS.lock().update(&key_event);
+
+                keyboard.process_keyevent(key_event)
+            }
+        }
+    }
+}
+
+/// A stream of the keys decoded from the [`ScancodeStream`].
+///
+/// The decoder is kept across polls, so keys spanning multiple scancodes are decoded correctly.
+///
+/// # Fields
+///
+/// * `scancodes` - The scancode stream.
+/// * `decoder` - The decoder.
+pub struct KeyEvents {
+    scancodes: ScancodeStream,
+    decoder: Decoder,
+}
+
+impl KeyEvents {
+    /// Waits for the next key without an executor, halting the CPU until one arrives.
+    ///
+    /// # Returns
+    ///
+    /// * `DecodedKey` - The key.
+    ///
+    /// # Notes
+    ///
+    /// * Interrupts are enabled while waiting for input.
+    pub fn next_blocking(&mut self) -> DecodedKey {
+        loop {
+            if let Some(key) = self.decoder.decode(pop_scancode_blocking()) {
+                return key;
+            }
+        }
+    }
+
+    /// Takes the next key, if its scancodes have already arrived.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<DecodedKey>` - The key, or `None` if the queue ran dry before a key was complete.
+    ///
+    /// # Notes
+    ///
+    /// * Never blocks or registers a waker, so it's for draining keys that are already waiting.
+    pub fn try_next(&mut self) -> Option<DecodedKey> {
+        let queue = scancode_queue();
+
+        loop {
+            if let Some(key) = self.decoder.decode(queue.pop()?) {
+                return Some(key);
+            }
+        }
+    }
+}
+
+impl Stream for KeyEvents {
+    /// The type of item produced by the stream.
+    type Item = DecodedKey;
+
+    /// Polls the stream for the next key.
+    ///
+    /// # Arguments
+    ///
+    /// * `cx` - The context to use for polling.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<Option<DecodedKey>>` - The next key, if available.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<DecodedKey>> {
+        let this = self.get_mut();
+
+        // Scancodes that don't complete a key are consumed until one does, or the queue runs dry.
+        loop {
+            match Pin::new(&mut this.scancodes).poll_next(cx) {
+                Poll::Ready(Some(scancode)) => {
+                    if let Some(key) = this.decoder.decode(scancode) {
+                        return Poll::Ready(Some(key));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Creates a stream of the keys pressed on the keyboard.
+///
+/// # Returns
+///
+/// * `KeyEvents` - The stream, decoding with the active layout.
+///
+/// # Notes
+///
+/// * Keep the stream around between keys, since a new one starts with a fresh decoder.
+#[must_use]
+pub fn key_events() -> KeyEvents {
+    KeyEvents {
+        scancodes: ScancodeStream::new(),
+        decoder: Decoder::new(layout()),
+    }
+}
+
+/// Sends the keys pressed on the keyboard into a channel, forever.
+///
+/// # Arguments
+///
+/// * `keys` - The channel, which a consumer like the shell receives the keys from.
+pub async fn forward_keys(keys: Arc<Channel<DecodedKey>>) {
+    let mut events = key_events();
+
+    while let Some(key) = events.next().await {
+        keys.send(key).await;
+    }
+}
+
 /// Print keys pressed on the keyboard.
 pub async fn print_keypress() {
-    let mut scancode_stream = ScancodeStream::new();
-    let mut keyboard = Keyboard::new(
-        ScancodeSet1::new(),
-        layouts::Us104Key,
-        HandleControl::MapLettersToUnicode,
-    );
-
-    while let Some(scancode) = scancode_stream.next().await {
-        let Ok(Some(key_event)) = keyboard.add_byte(scancode) else {
-            continue;
-        };
-        let Some(key) = keyboard.process_keyevent(key_event) else {
-            continue;
-        };
+    let mut keys = key_events();
 
+    while let Some(key) = keys.next().await {
         match key {
             DecodedKey::Unicode(character) => print!("{character}"),
             DecodedKey::RawKey(key) => print!("{key:?}"),
         }
     }
 }
+
+/// Applies the given key to the line being read, echoing it.
+///
+/// # Arguments
+///
+/// * `line` - The line read so far.
+/// * `key` - The decoded key.
+///
+/// # Returns
+///
+/// * `ControlFlow<Option<String>>` - `Break` with the result of the read once the line is finished, `Continue`
+///   otherwise.
+///
+/// # Notes
+///
+/// * `Ctrl+L` clears the screen and echoes the pending input again.
+fn edit_line(line: &mut String, key: DecodedKey) -> ControlFlow<Option<String>> {
+    match key {
+        DecodedKey::Unicode('\n') => {
+            println!();
+
+            return ControlFlow::Break(Some(mem::take(line)));
+        }
+        // `Ctrl+C`, mapped to `ETX` by the decoder.
+        DecodedKey::Unicode('\u{3}') if modifiers().ctrl => {
+            println!("^C");
+
+            return ControlFlow::Break(None);
+        }
+        // `Ctrl+L`, mapped to `FF` by the decoder.
+        DecodedKey::Unicode('\u{c}') if modifiers().ctrl => {
+            crate::clear!();
+            print!("{line}");
+        }
+        DecodedKey::Unicode('\u{8}') => {
+            // Only erase what was typed, not the prompt.
+            if line.pop().is_some() {
+                print!("\u{8}");
+            }
+        }
+        DecodedKey::Unicode(character) => {
+            print!("{character}");
+
+            line.push(character);
+        }
+        DecodedKey::RawKey(_) => {}
+    }
+
+    ControlFlow::Continue(())
+}
+
+/// Applies a key, and any keys already waiting behind it, to the line being read.
+///
+/// A burst of at least [`PASTE_THRESHOLD`] keys waiting at once, more than anyone types ahead, is treated as a paste.
+/// A paste is echoed in one go, and line breaks in it are kept in the line as spaces instead of finishing it. So pasted
+/// text never runs as a command until `Enter` is pressed on its own. Fewer keys are applied one by one, as typed.
+///
+/// # Arguments
+///
+/// * `line` - The line read so far.
+/// * `key` - The key that was waited for.
+/// * `next` - Takes the next key if one is already waiting, without blocking.
+///
+/// # Returns
+///
+/// * `ControlFlow<Option<String>>` - `Break` with the result of the read once the line is finished, `Continue`
+///   otherwise.
+///
+/// # Notes
+///
+/// * Keys typed ahead past the end of the line are kept for the next read, see [`next_type_ahead`].
+/// * `Ctrl+C` still interrupts the read during a paste, and `Backspace` erases, while other control keys in it are
+///   dropped.
+fn edit_keys(
+    line: &mut String,
+    key: DecodedKey,
+    mut next: impl FnMut() -> Option<DecodedKey>,
+) -> ControlFlow<Option<String>> {
+    let mut burst = vec![key];
+    while burst.len() < PASTE_THRESHOLD {
+        let Some(key) = next() else {
+            break;
+        };
+
+        burst.push(key);
+    }
+
+    if burst.len() < PASTE_THRESHOLD {
+        let mut keys = burst.into_iter();
+        for key in keys.by_ref() {
+            if let ControlFlow::Break(result) = edit_line(line, key) {
+                TYPE_AHEAD.lock().extend(keys);
+
+                return ControlFlow::Break(result);
+            }
+        }
+
+        return ControlFlow::Continue(());
+    }
+
+    let mut pasted = String::new();
+    for key in burst.into_iter().chain(core::iter::from_fn(next)) {
+        match key {
+            DecodedKey::Unicode('\u{3}') if modifiers().ctrl => {
+                println!("{pasted}^C");
+
+                return ControlFlow::Break(None);
+            }
+            DecodedKey::Unicode('\u{8}') => {
+                // Erase what was pasted first, then what was typed before it.
+                if pasted.pop().is_none() && line.pop().is_some() {
+                    print!("\u{8}");
+                }
+            }
+            DecodedKey::Unicode('\n' | '\t') => pasted.push(' '),
+            DecodedKey::Unicode(character) if !character.is_control() => pasted.push(character),
+            DecodedKey::Unicode(_) | DecodedKey::RawKey(_) => {}
+        }
+    }
+
+    print!("{pasted}");
+    line.push_str(&pasted);
+
+    ControlFlow::Continue(())
+}
+
+/// Takes the next key typed ahead past the end of the previous line.
+///
+/// # Returns
+///
+/// * `Option<DecodedKey>` - The key, or `None` if there's none left.
+fn next_type_ahead() -> Option<DecodedKey> {
+    TYPE_AHEAD.lock().pop_front()
+}
+
+/// Reads a line from keys received through a channel, echoing the typed characters.
+///
+/// # Arguments
+///
+/// * `keys` - The channel, fed by [`forward_keys`].
+///
+/// # Returns
+///
+/// * `Option<String>` - The line, without the trailing newline, or `None` if the read was interrupted with `Ctrl+C`.
+///
+/// # Notes
+///
+/// * `Ctrl+L` clears the screen and echoes the pending input again.
+/// * Pasted text is echoed in one go, and its line breaks don't finish the line, see [`edit_keys`].
+pub async fn read_line_from(keys: &Channel<DecodedKey>) -> Option<String> {
+    let mut line = String::new();
+
+    loop {
+        let key = match next_type_ahead() {
+            Some(key) => key,
+            None => keys.recv().await,
+        };
+        let next = || next_type_ahead().or_else(|| keys.try_recv());
+        if let ControlFlow::Break(result) = edit_keys(&mut line, key, next) {
+            return result;
+        }
+    }
+}
+
+/// Reads a line from the keyboard, echoing the typed characters.
+///
+/// # Returns
+///
+/// * `Option<String>` - The line, without the trailing newline, or `None` if the read was interrupted with `Ctrl+C`.
+///
+/// # Notes
+///
+/// * `Ctrl+L` clears the screen and echoes the pending input again.
+/// * Pasted text is echoed in one go, and its line breaks don't finish the line, see [`edit_keys`].
+pub async fn read_line() -> Option<String> {
+    let mut keys = key_events();
+    let mut line = String::new();
+
+    loop {
+        let key = match next_type_ahead() {
+            Some(key) => key,
+            None => match keys.next().await {
+                Some(key) => key,
+                None => return Some(line),
+            },
+        };
+        let next = || next_type_ahead().or_else(|| keys.try_next());
+        if let ControlFlow::Break(result) = edit_keys(&mut line, key, next) {
+            return result;
+        }
+    }
+}
+
+/// Pops a scancode from the [`SCANCODE_QUEUE`], halting the CPU until one arrives.
+///
+/// # Returns
+///
+/// * `u8` - The scancode.
+///
+/// # Notes
+///
+/// * This never touches the [`WAKER`], so any task waiting on a [`ScancodeStream`] is still woken afterwards.
+fn pop_scancode_blocking() -> u8 {
+    use x86_64::instructions::interrupts::{self, enable_and_hlt};
+
+    let queue = scancode_queue();
+    report_dropped_scancodes();
+
+    loop {
+        // Disable interrupts first, so a scancode can't arrive between the check and the halt.
+        interrupts::disable();
+        if let Some(scancode) = queue.pop() {
+            interrupts::enable();
+
+            return scancode;
+        }
+
+        enable_and_hlt();
+    }
+}
+
+/// Reads a line from the keyboard without an executor, echoing the typed characters.
+///
+/// # Returns
+///
+/// * `Option<String>` - The line, without the trailing newline, or `None` if the read was interrupted with `Ctrl+C`.
+///
+/// # Notes
+///
+/// * `Ctrl+L` clears the screen and echoes the pending input again.
+/// * Pasted text is echoed in one go, and its line breaks don't finish the line, see [`edit_keys`].
+/// * Interrupts are enabled while waiting for input.
+#[must_use]
+pub fn read_line_blocking() -> Option<String> {
+    let mut keys = key_events();
+    let mut line = String::new();
+
+    loop {
+        let key = next_type_ahead().unwrap_or_else(|| keys.next_blocking());
+        let next = || next_type_ahead().or_else(|| keys.try_next());
+        if let ControlFlow::Break(result) = edit_keys(&mut line, key, next) {
+            return result;
+        }
+    }
+}
+
+/// Reads a key from the keyboard, if one is available.
+///
+/// At most one scancode is taken from the [`SCANCODE_QUEUE`], so this never blocks.
+///
+/// # Returns
+///
+/// * `Option<DecodedKey>` - The decoded key, if the scancode completed one.
+///
+/// # Notes
+///
+/// * Extended keys consist of multiple scancodes, so they may take multiple calls to be returned.
+#[must_use]
+pub fn try_read_key() -> Option<DecodedKey> {
+    let scancode = scancode_queue().pop()?;
+
+    DECODER.lock().decode(scancode)
+}
+
+/// Tests that a blocking read leaves the waker of an async reader registered.
+///
+/// # Panics
+///
+/// * If the line isn't read correctly.
+/// * If the async reader isn't woken by the next scancode.
+#[test_case]
+fn test_read_line_blocking_keeps_waker() {
+    use alloc::sync::Arc;
+    use alloc::task::Wake;
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    /// A waker that records whether or not it was woken.
+    struct FlagWaker(AtomicBool);
+
+    impl Wake for FlagWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    // Register an async reader, as `print_keypress` would.
+    let flag = Arc::new(FlagWaker(AtomicBool::new(false)));
+    let waker = flag.clone().into();
+    let mut context = Context::from_waker(&waker);
+    assert!(Pin::new(&mut ScancodeStream::new())
+        .poll_next(&mut context)
+        .is_pending());
+
+    // Press and release `A`, then `Enter`, without waking the reader.
+    for scancode in [0x1E, 0x9E, 0x1C, 0x9C] {
+        assert!(scancode_queue().push(scancode).is_ok());
+    }
+
+    assert_eq!(read_line_blocking().as_deref(), Some("a"));
+
+    add_scancode(0x9E);
+    assert!(flag.0.load(Ordering::SeqCst));
+
+    // Drain the queue, so later reads start fresh.
+    while scancode_queue().pop().is_some() {}
+}
+
+/// Tests that [`try_read_key`] keeps decoding extended keys across calls.
+///
+/// # Panics
+///
+/// * If the key is decoded before its last scancode.
+/// * If the key isn't decoded as the up arrow.
+#[test_case]
+fn test_try_read_key_extended() {
+    assert_eq!(try_read_key(), None);
+
+    // The up arrow is prefixed with `0xE0`.
+    assert!(scancode_queue().push(0xE0).is_ok());
+    assert_eq!(try_read_key(), None);
+
+    assert!(scancode_queue().push(0x48).is_ok());
+    assert_eq!(try_read_key(), Some(DecodedKey::RawKey(KeyCode::ArrowUp)));
+}
+
+/// Tests that [`key_events`] decodes a key from its scancodes.
+///
+/// # Panics
+///
+/// * If the key isn't decoded once its scancode arrives.
+/// * If the release scancode produces a key.
+#[test_case]
+fn test_key_events() {
+    use futures_util::task::noop_waker_ref;
+
+    let mut keys = key_events();
+    let mut context = Context::from_waker(noop_waker_ref());
+
+    // Press and release `A`.
+    for scancode in [0x1E, 0x9E] {
+        assert!(scancode_queue().push(scancode).is_ok());
+    }
+
+    assert_eq!(
+        Pin::new(&mut keys).poll_next(&mut context),
+        Poll::Ready(Some(DecodedKey::Unicode('a')))
+    );
+    assert_eq!(Pin::new(&mut keys).poll_next(&mut context), Poll::Pending);
+}
+
+/// Tests that scancodes arriving while the queue is full are counted instead of queued.
+///
+/// # Panics
+///
+/// * If the dropped scancodes aren't counted.
+/// * If the count isn't reset after being taken.
+#[test_case]
+fn test_dropped_scancodes() {
+    let queue = scancode_queue();
+    take_dropped_scancodes();
+
+    // Release scancodes don't decode to keys, so they're harmless to leave behind.
+    while queue.push(0x9E).is_ok() {}
+    add_scancode(0x9E);
+    add_scancode(0x9E);
+
+    assert_eq!(take_dropped_scancodes(), 2);
+    assert_eq!(take_dropped_scancodes(), 0);
+
+    // Drain the queue, so later reads start fresh.
+    while queue.pop().is_some() {}
+}
+
+/// Tests that a scancode arriving before the queue is created is dropped, and doesn't make initializing it fail.
+///
+/// # Panics
+///
+/// * If the early scancode isn't counted as dropped.
+/// * If the queue isn't created with the given capacity.
+#[test_case]
+fn test_scancode_before_init() {
+    static QUEUE: OnceCell<ArrayQueue<u8>> = OnceCell::uninit();
+    take_dropped_scancodes();
+
+    push_scancode(&QUEUE, 0x9E);
+    assert_eq!(take_dropped_scancodes(), 1);
+    assert!(QUEUE.get().is_none());
+
+    init_queue(&QUEUE, 4);
+    init_queue(&QUEUE, 8);
+    assert_eq!(QUEUE.get().map(ArrayQueue::capacity), Some(4));
+
+    push_scancode(&QUEUE, 0x9E);
+    assert_eq!(take_dropped_scancodes(), 0);
+}
+
+/// Tests that a burst of keys is taken as a paste, which a line break in doesn't finish, and a backspace in erases.
+///
+/// # Panics
+///
+/// * If a line break in the paste finishes the line.
+/// * If the pasted text isn't added to the line.
+/// * If a line break on its own doesn't finish the line.
+#[test_case]
+fn test_paste() {
+    let text = "a\nb".repeat(PASTE_THRESHOLD) + "c\u{8}";
+    let mut line = String::from("echo ");
+    let mut burst = text.chars().map(DecodedKey::Unicode);
+    let first = burst.next().expect("The burst is empty!");
+
+    assert_eq!(
+        edit_keys(&mut line, first, || burst.next()),
+        ControlFlow::Continue(())
+    );
+
+    let expected = String::from("echo ") + &"a b".repeat(PASTE_THRESHOLD);
+    assert_eq!(line, expected);
+
+    assert_eq!(
+        edit_keys(&mut line, DecodedKey::Unicode('\n'), || None),
+        ControlFlow::Break(Some(expected))
+    );
+}
+
+/// Tests that a few keys typed ahead are applied as typed, keeping the keys past the end of the line for the next read.
+///
+/// # Panics
+///
+/// * If a line break typed ahead doesn't finish the line.
+/// * If a backspace typed ahead doesn't erase.
+/// * If the keys past the end of the line aren't kept.
+#[test_case]
+fn test_type_ahead() {
+    let mut line = String::new();
+    let mut keys = "lx\u{8}s\npwd".chars().map(DecodedKey::Unicode);
+    let first = keys.next().expect("The keys are empty!");
+
+    assert_eq!(
+        edit_keys(&mut line, first, || keys.next()),
+        ControlFlow::Break(Some("ls".into()))
+    );
+
+    let type_ahead = core::iter::from_fn(next_type_ahead).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(
+        type_ahead,
+        "pwd"
+            .chars()
+            .map(DecodedKey::Unicode)
+            .collect::<alloc::vec::Vec<_>>()
+    );
+}
@@ -1,13 +1,20 @@
 use alloc::boxed::Box;
-use core::sync::atomic::{AtomicU64, Ordering};
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use core::task::{Context, Poll};
 use core::{future::Future, pin::Pin};
 
+pub mod channel;
+pub mod chunked;
 pub mod clock;
 pub mod executor;
 pub mod keyboard;
 pub mod primes;
 pub mod simple_executor;
+pub mod timer;
+pub mod watchdog;
+
+/// The number of tasks created and not dropped yet, across all executors.
+static LIVE_TASKS: AtomicUsize = AtomicUsize::new(0);
 
 /// A task.
 ///
@@ -27,6 +34,8 @@ impl Task {
     ///
     /// * `future`: The future to be executed.
     pub fn new(future: impl Future<Output = ()> + 'static) -> Self {
+        LIVE_TASKS.fetch_add(1, Ordering::Relaxed);
+
         Self {
             id: Identifier::new(),
             future: Box::pin(future),
@@ -47,6 +56,27 @@ impl Task {
     }
 }
 
+impl Drop for Task {
+    /// Stops counting the task as live, once it's completed and the executor drops it.
+    fn drop(&mut self) {
+        LIVE_TASKS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Gets the number of tasks that haven't completed yet.
+///
+/// # Returns
+///
+/// * `usize` - The number of live tasks, across all executors.
+///
+/// # Notes
+///
+/// * Unlike [`executor::Executor::task_count`], this needs no access to the executor, so running tasks can use it.
+#[must_use]
+pub fn task_count() -> usize {
+    LIVE_TASKS.load(Ordering::Relaxed)
+}
+
 /// A task identifier.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Identifier(u64);
@@ -59,3 +89,101 @@ impl Identifier {
         Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
     }
 }
+
+/// Gives the other ready tasks a turn, resuming once the executor gets back to this one.
+///
+/// # Returns
+///
+/// * `YieldNow` - A future that completes on its second poll.
+///
+/// # Notes
+///
+/// * Await it between chunks of long work that has no `.await` of its own, so the work doesn't stall the keyboard
+///   and the shell.
+#[must_use]
+pub const fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// A future that yields to the executor once, returned by [`yield_now`].
+///
+/// # Fields
+///
+/// * `yielded` - Whether the task has already yielded.
+pub struct YieldNow {
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    /// Yields the first time, and completes the second.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The context to use for polling.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<()>` - Ready once the task has yielded.
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<()> {
+        if self.yielded {
+            return Poll::Ready(());
+        }
+
+        // Nothing else will wake us, so ask to be polled again after the other ready tasks.
+        self.yielded = true;
+        context.waker().wake_by_ref();
+
+        Poll::Pending
+    }
+}
+
+/// Tests that two tasks yielding in a loop take turns.
+///
+/// # Panics
+///
+/// * If spawning the tasks fails.
+/// * If the tasks don't interleave.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_yield_now() {
+    use alloc::rc::Rc;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use executor::Executor;
+
+    let order = Rc::new(RefCell::new(Vec::new()));
+    let mut executor = Executor::new();
+    for name in ['a', 'b'] {
+        let order = order.clone();
+        executor
+            .spawn(Task::new(async move {
+                for _ in 0..3 {
+                    order.borrow_mut().push(name);
+                    yield_now().await;
+                }
+            }))
+            .expect("Failed to spawn a task!");
+    }
+
+    executor.run_until_idle();
+    assert_eq!(*order.borrow(), ['a', 'b', 'a', 'b', 'a', 'b']);
+}
+
+/// Tests that tasks are counted as live until they're dropped.
+///
+/// # Panics
+///
+/// * If creating or dropping a task doesn't change the count.
+#[test_case]
+fn test_task_count() {
+    let count = task_count();
+
+    let task = Task::new(async {});
+    assert_eq!(task_count(), count + 1);
+
+    drop(task);
+    assert_eq!(task_count(), count);
+}
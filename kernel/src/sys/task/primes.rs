@@ -1,5 +1,15 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::task::Poll;
+
 use crate::println;
 
+/// The amount of sieve work done per chunk when run as a [`ChunkedTask`](super::chunked::ChunkedTask).
+///
+/// A unit of work is checking a candidate or crossing off a multiple, so a chunk takes well under a millisecond,
+/// which keeps typing responsive while costing only a few percent in executor overhead.
+pub const SIEVE_CHUNK_SIZE: usize = 100_000;
+
 /// Prints all prime numbers up to the given limit.
 ///
 /// # Arguments
@@ -16,6 +26,102 @@ pub fn print_primes(limit: u32) {
     }
 }
 
+/// Counts the prime numbers below the given limit, using the sieve of Eratosthenes.
+///
+/// # Arguments
+///
+/// * `limit` - The exclusive upper limit.
+///
+/// # Returns
+///
+/// * `usize` - The number of primes below the limit.
+#[allow(clippy::module_name_repetitions)]
+#[must_use]
+pub fn count_primes(limit: usize) -> usize {
+    let mut sieve = PrimeSieve::new(limit);
+
+    loop {
+        if let Poll::Ready(count) = sieve.step(usize::MAX) {
+            return count;
+        }
+    }
+}
+
+/// A sieve of Eratosthenes, which can be run a bounded amount of work at a time.
+///
+/// # Fields
+///
+/// * `is_composite` - Whether each number below the limit has been crossed off.
+/// * `candidate` - The next number to check.
+/// * `multiple` - The next multiple of `candidate` to cross off, if crossing off.
+/// * `count` - The number of primes found so far.
+#[allow(clippy::module_name_repetitions)]
+pub struct PrimeSieve {
+    is_composite: Vec<bool>,
+    candidate: usize,
+    multiple: Option<usize>,
+    count: usize,
+}
+
+impl PrimeSieve {
+    /// Creates a new `PrimeSieve`.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The exclusive upper limit.
+    #[must_use]
+    pub fn new(limit: usize) -> Self {
+        Self {
+            is_composite: vec![false; limit],
+            candidate: 2,
+            multiple: None,
+            count: 0,
+        }
+    }
+
+    /// Runs the sieve for up to the given amount of work.
+    ///
+    /// # Arguments
+    ///
+    /// * `budget` - The number of candidates to check and multiples to cross off.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<usize>` - The number of primes below the limit, once the sieve is done.
+    pub fn step(&mut self, budget: usize) -> Poll<usize> {
+        let limit = self.is_composite.len();
+
+        for _ in 0..budget {
+            if let Some(multiple) = self.multiple {
+                if multiple < limit {
+                    self.is_composite[multiple] = true;
+                    self.multiple = Some(multiple + self.candidate);
+                } else {
+                    self.multiple = None;
+                    self.candidate += 1;
+                }
+
+                continue;
+            }
+
+            if self.candidate >= limit {
+                return Poll::Ready(self.count);
+            }
+
+            if self.is_composite[self.candidate] {
+                self.candidate += 1;
+            } else {
+                self.count += 1;
+
+                // Smaller multiples were already crossed off by smaller primes.
+                self.multiple = Some(self.candidate.saturating_mul(self.candidate));
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
 /// Checks if the given number is prime.
 ///
 /// # Arguments
@@ -30,3 +136,38 @@ pub fn print_primes(limit: u32) {
 pub fn is_prime(num: u32) -> bool {
     (2..num).all(|i| num % i != 0)
 }
+
+/// Tests that the sieve agrees with the known prime counts.
+///
+/// # Panics
+///
+/// * If a count doesn't match.
+#[test_case]
+fn test_count_primes() {
+    assert_eq!(count_primes(0), 0);
+    assert_eq!(count_primes(3), 1);
+    assert_eq!(count_primes(10), 4);
+    assert_eq!(count_primes(1_000), 168);
+
+    let naive = (0..1_000).filter(|&num| num >= 2 && is_prime(num)).count();
+    assert_eq!(naive, 168);
+}
+
+/// Tests that running the sieve in chunks gives the same count as running it at once.
+///
+/// # Panics
+///
+/// * If the sieve finishes within a single small chunk.
+/// * If the chunked count doesn't match.
+#[test_case]
+fn test_prime_sieve_chunks() {
+    let mut sieve = PrimeSieve::new(1_000);
+    assert_eq!(sieve.step(10), Poll::Pending);
+
+    let count = loop {
+        if let Poll::Ready(count) = sieve.step(10) {
+            break count;
+        }
+    };
+    assert_eq!(count, 168);
+}
@@ -0,0 +1,192 @@
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::task::{Context, Poll, Waker};
+
+use spin::Mutex;
+use x86_64::instructions::interrupts;
+
+use crate::sys::time;
+
+/// The ID of the next timer.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+/// The timers of the pending [`Sleep`] futures, woken by the timer interrupt once their deadline passes.
+///
+/// # Notes
+///
+/// * Tasks only lock it with interrupts disabled, so the timer interrupt can't find it locked.
+static TIMERS: Mutex<Vec<Timer>> = Mutex::new(Vec::new());
+
+/// A pending timer.
+///
+/// # Fields
+///
+/// * `id` - The ID of the [`Sleep`] future it belongs to.
+/// * `deadline` - The PIT tick to wake the task at.
+/// * `waker` - The waker of the task.
+/// * `woken` - Whether the task has been woken already.
+struct Timer {
+    id: u64,
+    deadline: usize,
+    waker: Waker,
+    woken: bool,
+}
+
+/// Waits for the given time without blocking the executor.
+///
+/// # Arguments
+///
+/// * `seconds` - The time to wait, in seconds.
+///
+/// # Returns
+///
+/// * `Sleep` - A future that completes once the time has passed, rounded up to whole PIT ticks.
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub fn sleep(seconds: f64) -> Sleep {
+    let ticks = seconds.max(0.0) / time::pit_interval();
+    let whole = ticks as usize;
+    let ticks = whole + usize::from(ticks > whole as f64);
+
+    Sleep::until(time::tick().saturating_add(ticks))
+}
+
+/// A future that completes at a PIT tick, returned by [`sleep`].
+///
+/// # Fields
+///
+/// * `id` - The ID of its timer.
+/// * `deadline` - The PIT tick to complete at.
+pub struct Sleep {
+    id: u64,
+    deadline: usize,
+}
+
+impl Sleep {
+    /// Creates a new `Sleep`, completing at the given PIT tick.
+    ///
+    /// # Arguments
+    ///
+    /// * `deadline` - The PIT tick to complete at.
+    #[must_use]
+    pub fn until(deadline: usize) -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            deadline,
+        }
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    /// Checks if the deadline has passed, registering the task to be woken at it if not.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The context to use for polling.
+    ///
+    /// # Returns
+    ///
+    /// * `Poll<()>` - Ready once the deadline has passed.
+    fn poll(self: Pin<&mut Self>, context: &mut Context) -> Poll<()> {
+        interrupts::without_interrupts(|| {
+            let mut timers = TIMERS.lock();
+            let position = timers.iter().position(|timer| timer.id == self.id);
+
+            if time::tick() >= self.deadline {
+                if let Some(position) = position {
+                    timers.swap_remove(position);
+                }
+
+                return Poll::Ready(());
+            }
+
+            match position {
+                Some(position) => {
+                    let timer = &mut timers[position];
+                    timer.waker.clone_from(context.waker());
+                    timer.woken = false;
+                }
+                None => timers.push(Timer {
+                    id: self.id,
+                    deadline: self.deadline,
+                    waker: context.waker().clone(),
+                    woken: false,
+                }),
+            }
+
+            Poll::Pending
+        })
+    }
+}
+
+impl Drop for Sleep {
+    /// Removes the timer, if the future is dropped before it completes.
+    fn drop(&mut self) {
+        interrupts::without_interrupts(|| {
+            TIMERS.lock().retain(|timer| timer.id != self.id);
+        });
+    }
+}
+
+/// Wakes the tasks whose deadline has passed, called by the timer interrupt handler on every tick.
+///
+/// # Notes
+///
+/// * The wakers are only woken by reference and left in place, so nothing is freed in the interrupt handler.
+pub(crate) fn on_timer_tick() {
+    let Some(mut timers) = TIMERS.try_lock() else {
+        return;
+    };
+
+    let now = time::tick();
+    for timer in timers
+        .iter_mut()
+        .filter(|timer| !timer.woken && now >= timer.deadline)
+    {
+        timer.woken = true;
+        timer.waker.wake_by_ref();
+    }
+}
+
+/// Tests that a sleeping task is woken by the timer once its deadline passes.
+///
+/// # Panics
+///
+/// * If spawning the task fails.
+/// * If the task completes before its deadline, or its timer isn't removed.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_sleep() {
+    use alloc::rc::Rc;
+    use core::cell::Cell;
+
+    use super::executor::Executor;
+    use super::Task;
+
+    let start = time::tick();
+    let done = Rc::new(Cell::new(None));
+
+    let mut executor = Executor::new();
+    let finished = done.clone();
+    executor
+        .spawn(Task::new(async move {
+            Sleep::until(start + 3).await;
+            finished.set(Some(time::tick()));
+        }))
+        .expect("Failed to spawn the task!");
+
+    while done.get().is_none() {
+        executor.run_until_idle();
+        x86_64::instructions::hlt();
+    }
+
+    assert!(done.get().is_some_and(|tick| tick >= start + 3));
+    assert!(TIMERS.lock().is_empty());
+}
@@ -0,0 +1,116 @@
+use core::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+
+use crate::sys::time;
+use crate::try_println;
+
+use super::Identifier;
+
+/// The default time the executor may go without progress before the watchdog warns, in seconds.
+pub const DEFAULT_TIMEOUT: f64 = 5.0;
+
+/// Marks that no task is running in [`RUNNING_TASK`].
+const NO_TASK: u64 = u64::MAX;
+
+/// The progress counter, bumped by the executor each time it completes a poll.
+static PROGRESS: AtomicUsize = AtomicUsize::new(0);
+/// The progress counter, as seen by the last timer tick.
+static LAST_PROGRESS: AtomicUsize = AtomicUsize::new(0);
+/// The PIT tick at which the progress counter last moved.
+static LAST_PROGRESS_TICK: AtomicUsize = AtomicUsize::new(0);
+/// The time the executor may go without progress before the watchdog warns, in PIT ticks.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+static TIMEOUT_TICKS: AtomicUsize =
+    AtomicUsize::new((DEFAULT_TIMEOUT / time::pit_interval()) as usize);
+/// The ID of the task being polled, or [`NO_TASK`].
+static RUNNING_TASK: AtomicU64 = AtomicU64::new(NO_TASK);
+/// Whether the watchdog has already warned about the current stall.
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Feeds the watchdog, marking that the executor made progress.
+///
+/// # Notes
+///
+/// * The executor calls this after every poll, so tasks only need to call it themselves while doing long work between
+///   two `.await`s.
+pub fn pet() {
+    PROGRESS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Sets the time the executor may go without progress before the watchdog warns.
+///
+/// # Arguments
+///
+/// * `seconds` - The timeout, in seconds.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn set_timeout(seconds: f64) {
+    let ticks = (seconds / time::pit_interval()) as usize;
+
+    TIMEOUT_TICKS.store(ticks.max(1), Ordering::Relaxed);
+}
+
+/// Gets the time the executor may go without progress before the watchdog warns.
+///
+/// # Returns
+///
+/// * `f64` - The timeout, in seconds.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn timeout() -> f64 {
+    TIMEOUT_TICKS.load(Ordering::Relaxed) as f64 * time::pit_interval()
+}
+
+/// Records which task the executor is about to poll.
+///
+/// # Arguments
+///
+/// * `task_id` - The ID of the task, or `None` once the poll is done.
+pub(crate) fn set_running(task_id: Option<Identifier>) {
+    let id = task_id.map_or(NO_TASK, |task_id| task_id.0);
+
+    RUNNING_TASK.store(id, Ordering::Relaxed);
+}
+
+/// Checks the progress counter, called by the timer interrupt handler on every tick.
+///
+/// Warns once per stall if the counter hasn't moved within the timeout.
+///
+/// # Notes
+///
+/// * Prints with `try_println!`, since the interrupted task may be holding the writer.
+pub(crate) fn on_timer_tick() {
+    let now = time::tick();
+    let progress = PROGRESS.load(Ordering::Relaxed);
+    if LAST_PROGRESS.swap(progress, Ordering::Relaxed) != progress {
+        LAST_PROGRESS_TICK.store(now, Ordering::Relaxed);
+        WARNED.store(false, Ordering::Relaxed);
+
+        return;
+    }
+
+    let stalled = now.wrapping_sub(LAST_PROGRESS_TICK.load(Ordering::Relaxed));
+    if stalled < TIMEOUT_TICKS.load(Ordering::Relaxed) || WARNED.swap(true, Ordering::Relaxed) {
+        return;
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let seconds = stalled as f64 * time::pit_interval();
+    match RUNNING_TASK.load(Ordering::Relaxed) {
+        NO_TASK => try_println!("[WARN]: Executor made no progress for {seconds:.1}s!"),
+        id => try_println!(
+            "[WARN]: Executor made no progress for {seconds:.1}s, task {id} hasn't yielded!"
+        ),
+    }
+}
+
+/// Tests that the timeout is converted to and from PIT ticks.
+///
+/// # Panics
+///
+/// * If the timeout doesn't round trip within one PIT tick.
+#[test_case]
+fn test_timeout() {
+    set_timeout(2.0);
+    assert!((timeout() - 2.0).abs() <= time::pit_interval());
+
+    set_timeout(DEFAULT_TIMEOUT);
+}
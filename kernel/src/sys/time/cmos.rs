@@ -1,5 +1,7 @@
 use x86_64::instructions::port::Port;
 
+use crate::sys::time;
+
 /// Where the CMOS address is located.
 const CMOS_ADDRESS: u8 = 0x70;
 /// Where the CMOS data is located.
@@ -121,6 +123,7 @@ impl CMOS {
     pub fn read(&mut self, reg: &Register) -> u8 {
         unsafe {
             self.addr.write(*reg as u8);
+            time::io_wait();
             self.data.read()
         }
     }
@@ -134,6 +137,7 @@ impl CMOS {
     pub fn write(&mut self, reg: &Register, value: u8) {
         unsafe {
             self.addr.write(*reg as u8);
+            time::io_wait();
             self.data.write(value);
         }
     }
@@ -7,6 +7,8 @@ use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use x86_64::instructions::{interrupts, port::Port};
 
 use crate::errors::Error;
+use crate::serial_println;
+use crate::sys::cpu::CPU_FEATURES;
 use crate::sys::pit::{AccessMode, Channel, OperatingMode};
 use crate::sys::time::rtc::{RTCInterrupt, RTC};
 
@@ -25,6 +27,9 @@ pub(crate) static PIT_TICK: AtomicUsize = AtomicUsize::new(0);
 /// The last RTC update, in PIT ticks.
 pub(crate) static LAST_RTC_UPDATE: AtomicUsize = AtomicUsize::new(0);
 
+/// The port of the POST code display, which nothing listens to, so writing to it only takes time.
+const POST_PORT: u16 = 0x80;
+
 /// The number of clock cycles per nanosecond.
 static CLOCK_CYCLES_PER_NS: AtomicU64 = AtomicU64::new(0);
 
@@ -94,6 +99,11 @@ pub fn init() -> Result<(), Error> {
 
     CLOCK_CYCLES_PER_NS.store((end - start) / calibration, Ordering::Relaxed);
 
+    // The calibration assumes the time-stamp counter keeps a constant rate.
+    if !CPU_FEATURES.has_invariant_tsc() {
+        serial_println!("[WARN]: The TSC isn't invariant, so TSC based timing may drift!");
+    }
+
     Ok(())
 }
 
@@ -102,7 +112,8 @@ pub fn init() -> Result<(), Error> {
 /// # Returns
 ///
 /// * `u64` - The time-stamp counter.
-fn read_tsc() -> u64 {
+#[must_use]
+pub fn read_tsc() -> u64 {
     unsafe {
         core::arch::x86_64::_mm_lfence(); // Prevents instruction reordering.
         core::arch::x86_64::_rdtsc() // Reads the time-stamp counter.
@@ -122,20 +133,111 @@ pub fn sleep(seconds: f64) {
     }
 }
 
+/// Sleeps until the RTC reaches the given date and time.
+///
+/// Unlike [`sleep`], this follows the RTC rather than counting PIT ticks, so long sleeps don't drift from the wall
+/// clock.
+///
+/// # Arguments
+///
+/// * `target` - The date and time to wake up at.
+///
+/// # Notes
+///
+/// * Returns right away if the target has already passed.
+/// * The RTC is only read again after each update interrupt, once a second, when it's safe to read.
+pub fn sleep_until_rtc(target: &RTC) {
+    let target = target.date_time();
+
+    let mut last_update = last_rtc_update();
+    while RTC::new_no_check().date_time() < target {
+        while last_rtc_update() == last_update {
+            halt();
+        }
+
+        last_update = last_rtc_update();
+    }
+}
+
+/// Waits about a microsecond, by writing to the POST code port.
+///
+/// Unlike [`wait`], this doesn't depend on the clock being calibrated, so it's safe to use at any point during boot.
+///
+/// # Notes
+///
+/// * The delay comes from the I/O bus, so it's only roughly a microsecond, and never shorter.
+pub fn io_wait() {
+    let mut port: Port<u8> = Port::new(POST_PORT);
+
+    unsafe { port.write(0) };
+}
+
 /// Waits for the given amount of nanoseconds.
 ///
 /// # Arguments
 ///
 /// * `ns` - The amount of nanoseconds to wait.
+///
+/// # Notes
+///
+/// * Before [`init`] calibrates the clock, waits with [`io_wait`] instead, a microsecond at a time.
 pub fn wait(ns: u64) {
+    let cycles_per_ns = CLOCK_CYCLES_PER_NS.load(Ordering::Relaxed);
+    if cycles_per_ns == 0 {
+        for _ in 0..ns.div_ceil(1_000) {
+            io_wait();
+        }
+
+        return;
+    }
+
     let start = read_tsc();
-    let delta = ns * CLOCK_CYCLES_PER_NS.load(Ordering::Relaxed);
+    let delta = ns * cycles_per_ns;
 
     while read_tsc() - start < delta {
         spin_loop();
     }
 }
 
+/// Gets the time-stamp counter value the given amount of nanoseconds from now.
+///
+/// Unlike [`clock::uptime`], the time-stamp counter keeps counting while interrupts are disabled.
+///
+/// # Arguments
+///
+/// * `ns` - The amount of nanoseconds.
+///
+/// # Returns
+///
+/// * `u64` - The deadline, to compare against [`read_tsc`].
+#[must_use]
+pub fn deadline(ns: u64) -> u64 {
+    // The clock isn't calibrated before `init`, so count at least a cycle per nanosecond.
+    let cycles_per_ns = CLOCK_CYCLES_PER_NS.load(Ordering::Relaxed).max(1);
+
+    read_tsc().saturating_add(ns.saturating_mul(cycles_per_ns))
+}
+
+/// Measures how long a function takes to run, using the time-stamp counter.
+///
+/// # Arguments
+///
+/// * `f` - The function to measure.
+///
+/// # Returns
+///
+/// * `(T, u64)` - The output of the function, and the time it took in nanoseconds.
+pub fn measure<T>(f: impl FnOnce() -> T) -> (T, u64) {
+    let start = read_tsc();
+    let output = f();
+    let end = read_tsc();
+
+    // The clock isn't calibrated before `init`, so avoid dividing by zero.
+    let cycles_per_ns = CLOCK_CYCLES_PER_NS.load(Ordering::Relaxed).max(1);
+
+    (output, (end - start) / cycles_per_ns)
+}
+
 /// Sets the PIT frequency divider.
 ///
 /// # Arguments
@@ -181,3 +283,33 @@ pub fn set_pit_frequency_divider(divider: u16, channel: &Channel) -> Result<(),
         Ok(())
     })
 }
+
+/// Tests that sleeping until a time that has already passed returns right away.
+///
+/// # Panics
+///
+/// * If the sleep takes longer than a PIT tick.
+#[test_case]
+fn test_sleep_until_rtc_past() {
+    let mut target = RTC::new_no_check();
+    target.year = target.year.saturating_sub(1);
+
+    let start = tick();
+    sleep_until_rtc(&target);
+    assert!(tick() - start <= 1);
+}
+
+/// Tests that waiting takes at least as long as asked.
+///
+/// # Panics
+///
+/// * If the wait returns early.
+#[test_case]
+fn test_wait() {
+    let ((), ns) = measure(|| wait(10_000));
+    assert!(ns >= 10_000);
+
+    // An I/O port write takes real time, even if how much varies.
+    let ((), ns) = measure(io_wait);
+    assert!(ns > 0);
+}
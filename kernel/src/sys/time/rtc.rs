@@ -1,6 +1,15 @@
 use crate::sys::time::cmos::{Register, CMOS};
 use x86_64::instructions::interrupts::without_interrupts;
 
+/// The status A flag set while the RTC is updating.
+const STATUS_A_UPDATING: u8 = 1 << 7;
+/// The status B flag set when the RTC is in 24-hour mode.
+const STATUS_B_24_HOUR: u8 = 1 << 1;
+/// The status B flag set when the RTC is in binary mode, instead of BCD.
+const STATUS_B_BINARY: u8 = 1 << 2;
+/// The hours flag set for PM times in 12-hour mode.
+const HOURS_PM: u8 = 1 << 7;
+
 /// The real time clock.
 ///
 /// # Fields
@@ -57,88 +66,131 @@ impl RTC {
     ///
     /// * This function won't wait for the RTC to finish updating.
     pub fn update(&mut self) {
-        let mut seconds = self.cmos.read(&Register::Seconds);
-        let mut minutes = self.cmos.read(&Register::Minutes);
-        let mut hours = self.cmos.read(&Register::Hours);
-        let mut day = self.cmos.read(&Register::Day);
-        let mut month = self.cmos.read(&Register::Month);
-        let mut year = self.cmos.read(&Register::Year);
-        let mut century = self.cmos.read(&Register::Century);
-
-        // If the RTC is in BCD mode, then convert the values to binary.
-        if !self.binary_mode() {
-            seconds = Self::bcd_to_binary(seconds);
-            minutes = Self::bcd_to_binary(minutes);
-            hours = Self::bcd_to_binary(hours);
-            day = Self::bcd_to_binary(day);
-            month = Self::bcd_to_binary(month);
-            year = Self::bcd_to_binary(year);
-            century = Self::bcd_to_binary(century);
-        }
+        let registers = [
+            Register::Seconds,
+            Register::Minutes,
+            Register::Hours,
+            Register::Day,
+            Register::Month,
+            Register::Year,
+            Register::Century,
+        ]
+        .map(|register| self.cmos.read(&register));
+        let status_b = self.cmos.read(&Register::StatusB);
 
-        // If the RTC is in 12-hour mode, then convert the hours to 24-hour mode.
-        if !self.military_time_mode() {
-            // If the PM bit is set, then add 12 to the hours.
-            if hours & 0x80 != 0 {
-                hours = (hours & 0x7F) + 12;
-            }
-        }
+        let cmos = core::mem::take(&mut self.cmos);
+        *self = Self {
+            cmos,
+            ..Self::from_registers(registers, status_b)
+        };
+    }
 
-        self.seconds = seconds;
-        self.minutes = minutes;
-        self.hours = hours;
-        self.day = day;
-        self.month = month;
-        self.year = year;
-        self.century = century;
+    /// Creates a new `RTC` instance from raw CMOS register values, without touching the CMOS.
+    ///
+    /// # Arguments
+    ///
+    /// * `registers` - The seconds, minutes, hours, day, month, year and century registers, in that order.
+    /// * `status_b` - The status B register, which tells how the other registers are encoded.
+    #[must_use]
+    pub fn from_registers(registers: [u8; 7], status_b: u8) -> Self {
+        let [seconds, minutes, hours, day, month, year, century] = registers;
+
+        Self {
+            seconds: Self::decode(seconds, status_b),
+            minutes: Self::decode(minutes, status_b),
+            hours: Self::decode_hours(hours, status_b),
+            day: Self::decode(day, status_b),
+            month: Self::decode(month, status_b),
+            year: Self::decode(year, status_b),
+            century: Self::decode(century, status_b),
+            ..Self::default()
+        }
     }
 
-    /// Gets whether or not the RTC is updating.
+    /// Decodes a raw register value to binary.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The raw register value.
+    /// * `status_b` - The status B register.
     ///
     /// # Returns
     ///
-    /// * `bool` - Whether or not the RTC is updating.
-    pub fn rtc_updating(&mut self) -> bool {
-        let status = self.cmos.read(&Register::StatusA);
-        let update_bit = 1 << 7;
-
-        // If the RTC update in progress bit is 0, then the RTC is not updating, and vice versa.
-        status & update_bit == 0
+    /// * `u8` - The value in binary.
+    #[must_use]
+    pub const fn decode(value: u8, status_b: u8) -> u8 {
+        if status_b & STATUS_B_BINARY == 0 {
+            Self::bcd_to_binary(value)
+        } else {
+            value
+        }
     }
 
-    /// Waits for the RTC to finish updating.
+    /// Decodes a raw hours register value to binary, in 24-hour format.
+    ///
+    /// # Arguments
+    ///
+    /// * `hours` - The raw hours register value.
+    /// * `status_b` - The status B register.
+    ///
+    /// # Returns
+    ///
+    /// * `u8` - The hours, from 0 to 23.
     ///
     /// # Notes
     ///
-    /// * This function will spin until the RTC is done updating.
-    pub fn wait_for_rtc_update(&mut self) {
-        while self.rtc_updating() {
-            core::hint::spin_loop();
+    /// * In 12-hour mode the PM flag is the top bit, which has to be stripped before decoding BCD.
+    /// * Midnight is 12 AM and noon is 12 PM in 12-hour mode.
+    #[must_use]
+    pub const fn decode_hours(hours: u8, status_b: u8) -> u8 {
+        if status_b & STATUS_B_24_HOUR != 0 {
+            return Self::decode(hours, status_b);
+        }
+
+        let pm = hours & HOURS_PM != 0;
+        let hours = Self::decode(hours & !HOURS_PM, status_b) % 12;
+
+        if pm {
+            hours + 12
+        } else {
+            hours
         }
     }
 
-    /// Gets whether or not the RTC is in 24-hour mode, or in 12-hour mode.
+    /// Gets whether or not the RTC is updating.
     ///
     /// # Returns
     ///
-    /// * `bool` - Whether or not the RTC is in 24-hour mode or not.
-    fn military_time_mode(&mut self) -> bool {
-        let value = self.cmos.read(&Register::StatusB);
+    /// * `bool` - Whether or not the RTC is updating.
+    pub fn rtc_updating(&mut self) -> bool {
+        let status = self.cmos.read(&Register::StatusA);
 
-        // If the first bit is 0, then the RTC is in 12-hour mode, and vice versa.
-        value & 1 == 0
+        Self::is_updating(status)
     }
 
-    /// Gets whether or not the RTC is in binary mode.
+    /// Gets whether or not the given status A register says the RTC is updating.
+    ///
+    /// # Arguments
+    ///
+    /// * `status_a` - The status A register.
     ///
     /// # Returns
     ///
-    /// * `bool` - Whether or not the RTC is in binary mode or in BCD mode.
-    fn binary_mode(&mut self) -> bool {
-        let value = self.cmos.read(&Register::StatusB);
+    /// * `bool` - Whether or not the update in progress bit is set.
+    #[must_use]
+    pub const fn is_updating(status_a: u8) -> bool {
+        status_a & STATUS_A_UPDATING != 0
+    }
 
-        // If the second bit is 0, then the RTC is in BCD mode, and vice versa.
-        value & 2 == 0
+    /// Waits for the RTC to finish updating.
+    ///
+    /// # Notes
+    ///
+    /// * This function will spin until the RTC is done updating.
+    pub fn wait_for_rtc_update(&mut self) {
+        while self.rtc_updating() {
+            core::hint::spin_loop();
+        }
     }
 
     /// Disables the given interrupt.
@@ -222,25 +274,54 @@ impl RTC {
         ((value & 0xF0) >> 1) + ((value & 0xF0) >> 3) + (value & 0xF)
     }
 
-    /// Converts the RTC time to milliseconds.
+    /// Gets the date and time, most significant field first, so they compare chronologically.
     ///
     /// # Returns
     ///
-    /// * `u64` - The RTC time in milliseconds.
+    /// * `(u8, u8, u8, u8, u8, u8, u8)` - The century, year, month, day, hours, minutes and seconds.
+    #[must_use]
+    pub const fn date_time(&self) -> (u8, u8, u8, u8, u8, u8, u8) {
+        (
+            self.century,
+            self.year,
+            self.month,
+            self.day,
+            self.hours,
+            self.minutes,
+            self.seconds,
+        )
+    }
+
+    /// Converts the RTC time to milliseconds since the Unix epoch.
+    ///
+    /// # Returns
+    ///
+    /// * `u64` - The RTC time in milliseconds since `1970-01-01 00:00:00`, or 0 for earlier times.
+    ///
+    /// # Notes
+    ///
+    /// * A century of 0 means the RTC has no century register, so the 21st century is assumed.
     #[must_use]
     pub const fn as_millis(&self) -> u64 {
-        let mut millis = 0;
-
-        // Convert the RTC time to milliseconds.
-        millis += self.seconds as u64 * 1_000;
-        millis += self.minutes as u64 * 60 * 1_000;
-        millis += self.hours as u64 * 60 * 60 * 1_000;
-        millis += self.day as u64 * 24 * 60 * 60 * 1_000;
-        millis += self.month as u64 * 30 * 24 * 60 * 60 * 1_000;
-        millis += self.year as u64 * 365 * 24 * 60 * 60 * 1_000;
-        millis += self.century as u64 * 100 * 365 * 24 * 60 * 60 * 1_000;
-
-        millis
+        let century = if self.century == 0 { 20 } else { self.century };
+        let year = century as u64 * 100 + self.year as u64;
+        let month = self.month as u64;
+
+        // Converts the date to days since the epoch, see http://howardhinnant.github.io/date_algorithms.html.
+        let year = if month <= 2 { year - 1 } else { year };
+        let era = year / 400;
+        let year_of_era = year % 400;
+        let month_index = if month > 2 { month - 3 } else { month + 9 };
+        let day_of_year = (153 * month_index + 2) / 5 + self.day as u64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = (era * 146_097 + day_of_era).saturating_sub(719_468);
+
+        let seconds = days * 86_400
+            + self.hours as u64 * 3_600
+            + self.minutes as u64 * 60
+            + self.seconds as u64;
+
+        seconds * 1_000
     }
 }
 
@@ -260,3 +341,75 @@ pub enum RTCInterrupt {
     /// The update interrupt, which is triggered when the RTC updates.
     Update = 1 << 4,
 }
+
+/// Tests converting BCD values to binary.
+///
+/// # Panics
+///
+/// * If a value is converted wrong.
+#[test_case]
+fn test_bcd_to_binary() {
+    assert_eq!(RTC::bcd_to_binary(0x00), 0);
+    assert_eq!(RTC::bcd_to_binary(0x09), 9);
+    assert_eq!(RTC::bcd_to_binary(0x10), 10);
+    assert_eq!(RTC::bcd_to_binary(0x59), 59);
+    assert_eq!(RTC::bcd_to_binary(0x99), 99);
+}
+
+/// Tests decoding the hours in every mode.
+///
+/// # Panics
+///
+/// * If the hours are decoded wrong.
+#[test_case]
+fn test_decode_hours() {
+    // 12-hour BCD.
+    assert_eq!(RTC::decode_hours(0x12, 0), 0);
+    assert_eq!(RTC::decode_hours(0x11, 0), 11);
+    assert_eq!(RTC::decode_hours(0x92, 0), 12);
+    assert_eq!(RTC::decode_hours(0x81, 0), 13);
+    assert_eq!(RTC::decode_hours(0x91, 0), 23);
+
+    // 12-hour binary.
+    assert_eq!(RTC::decode_hours(12, STATUS_B_BINARY), 0);
+    assert_eq!(RTC::decode_hours(HOURS_PM | 12, STATUS_B_BINARY), 12);
+    assert_eq!(RTC::decode_hours(HOURS_PM | 11, STATUS_B_BINARY), 23);
+
+    // 24-hour.
+    assert_eq!(RTC::decode_hours(0x23, STATUS_B_24_HOUR), 23);
+    assert_eq!(
+        RTC::decode_hours(23, STATUS_B_24_HOUR | STATUS_B_BINARY),
+        23
+    );
+}
+
+/// Tests reading the update in progress bit.
+///
+/// # Panics
+///
+/// * If the bit is read wrong.
+#[test_case]
+fn test_is_updating() {
+    assert!(RTC::is_updating(0xA6));
+    assert!(!RTC::is_updating(0x26));
+}
+
+/// Tests converting raw registers to milliseconds since the epoch.
+///
+/// # Panics
+///
+/// * If a date converts to the wrong time.
+#[test_case]
+fn test_as_millis() {
+    let epoch = RTC::from_registers([0x00, 0x00, 0x00, 0x01, 0x01, 0x70, 0x19], STATUS_B_24_HOUR);
+    assert_eq!(epoch.as_millis(), 0);
+
+    // The day after a leap day.
+    let leap = RTC::from_registers([0x00, 0x00, 0x00, 0x01, 0x03, 0x00, 0x20], STATUS_B_24_HOUR);
+    assert_eq!(leap.as_millis(), 951_868_800_000);
+
+    // 2024-01-31 12:34:56, in 12-hour binary mode.
+    let pm = RTC::from_registers([56, 34, HOURS_PM | 12, 31, 1, 24, 20], STATUS_B_BINARY);
+    assert_eq!(pm.date_time(), (20, 24, 1, 31, 12, 34, 56));
+    assert_eq!(pm.as_millis(), 1_706_704_496_000);
+}
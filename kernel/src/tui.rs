@@ -0,0 +1,95 @@
+use crate::vga_buffer::{Color, WRITER};
+
+/// Draws a box with single line borders, drawn with the code page 437 line characters.
+///
+/// # Arguments
+///
+/// * `x` - The left column.
+/// * `y` - The top row.
+/// * `width` - The width, in columns, including the borders.
+/// * `height` - The height, in rows, including the borders.
+///
+/// # Notes
+///
+/// * Boxes smaller than 2x2 have no room for their corners, so nothing is drawn.
+/// * The inside of the box is left as it is, so fill it first with [`fill`] to clear it.
+pub fn draw_box(x: usize, y: usize, width: usize, height: usize) {
+    use x86_64::instructions::interrupts;
+
+    if width < 2 || height < 2 {
+        return;
+    }
+
+    let line = "─".repeat(width - 2);
+    let top = ["┌", &line, "┐"].concat();
+    let bottom = ["└", &line, "┘"].concat();
+
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+
+        // Drawn as one batch, so the box shows up at once.
+        writer.begin_batch();
+        writer.write_at(y, x, &top);
+        for row in y + 1..y + height - 1 {
+            writer.write_at(row, x, "│");
+            writer.write_at(row, x + width - 1, "│");
+        }
+        writer.write_at(y + height - 1, x, &bottom);
+        writer.end_batch();
+    });
+}
+
+/// Writes text at an absolute position, without moving where `print!` continues.
+///
+/// # Arguments
+///
+/// * `row` - The row.
+/// * `column` - The column of the first character.
+/// * `text` - The text, which is cut off at the end of the row.
+pub fn write_at(row: usize, column: usize, text: &str) {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| WRITER.lock().write_at(row, column, text));
+}
+
+/// Fills a rectangle with blanks on the given background color.
+///
+/// # Arguments
+///
+/// * `x` - The left column.
+/// * `y` - The top row.
+/// * `width` - The width, in columns.
+/// * `height` - The height, in rows.
+/// * `color` - The background color.
+pub fn fill(x: usize, y: usize, width: usize, height: usize, color: Color) {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| WRITER.lock().fill(y, x, width, height, color));
+}
+
+/// Tests that a box is drawn with its corners and borders where they belong.
+///
+/// # Panics
+///
+/// * If a corner or border is missing.
+/// * If the inside of the box is drawn on.
+#[test_case]
+fn test_draw_box() {
+    use x86_64::instructions::interrupts;
+
+    fill(0, 0, 6, 3, Color::Black);
+    draw_box(1, 0, 4, 3);
+
+    interrupts::without_interrupts(|| {
+        let writer = WRITER.lock();
+        let writer = &*writer;
+        let row = |row| (0..6).map(move |column| writer.char_at(row, column).unwrap_or(0));
+
+        // `┌──┐`, `│  │` and `└──┘` in code page 437.
+        assert!(row(0).eq([b' ', 0xDA, 0xC4, 0xC4, 0xBF, b' ']));
+        assert!(row(1).eq([b' ', 0xB3, b' ', b' ', 0xB3, b' ']));
+        assert!(row(2).eq([b' ', 0xC0, 0xC4, 0xC4, 0xD9, b' ']));
+    });
+
+    fill(0, 0, 6, 3, Color::Black);
+}
@@ -0,0 +1,255 @@
+use core::fmt;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use x86_64::instructions::interrupts;
+
+use crate::{exit_qemu, hlt_loop, serial_println, try_println, QemuExitCode};
+
+/// Whether the running test expects to panic, see [`should_panic`].
+static EXPECT_PANIC: AtomicBool = AtomicBool::new(false);
+
+/// Asserts that a condition holds, printing it with its location to serial and the screen and halting if it doesn't.
+///
+/// Takes an optional message, formatted like [`format!`].
+///
+/// # Notes
+///
+/// * Unlike `assert!`, a failure doesn't unwind through the panic handler, so it's safe where panicking isn't, like
+///   with the allocator locked.
+/// * Under `cargo test`, a failure fails the test run instead of halting.
+#[macro_export]
+macro_rules! kassert {
+    ($condition:expr $(,)?) => {
+        if !$condition {
+            $crate::util::assert::_assert_failed(
+                stringify!($condition),
+                None,
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    };
+    ($condition:expr, $($arg:tt)+) => {
+        if !$condition {
+            $crate::util::assert::_assert_failed(
+                stringify!($condition),
+                Some(format_args!($($arg)+)),
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    };
+}
+
+/// Like [`kassert!`], but only checked in debug builds.
+#[macro_export]
+macro_rules! kdebug_assert {
+    ($($arg:tt)*) => {
+        if cfg!(debug_assertions) {
+            $crate::kassert!($($arg)*);
+        }
+    };
+}
+
+/// Asserts that a condition holds in a test, printing it with its location to serial and failing the test run if it
+/// doesn't.
+///
+/// Takes an optional message, formatted like [`format!`].
+///
+/// # Notes
+///
+/// * Unlike `assert!`, the failure names the condition and where it is, not just the panic message.
+#[macro_export]
+macro_rules! test_assert {
+    ($condition:expr $(,)?) => {
+        if !$condition {
+            $crate::util::assert::_test_assert_failed(
+                stringify!($condition),
+                None,
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    };
+    ($condition:expr, $($arg:tt)+) => {
+        if !$condition {
+            $crate::util::assert::_test_assert_failed(
+                stringify!($condition),
+                Some(format_args!($($arg)+)),
+                file!(),
+                line!(),
+                column!(),
+            );
+        }
+    };
+}
+
+/// Reports a failed [`kassert!`] and stops the kernel.
+///
+/// # Arguments
+///
+/// * `condition` - The condition that didn't hold, as written.
+/// * `message` - The message, if one was given.
+/// * `file` - The file of the assertion.
+/// * `line` - The line of the assertion.
+/// * `column` - The column of the assertion.
+///
+/// # Returns
+///
+/// * `!` - Never.
+///
+/// # Notes
+///
+/// * The screen is printed to with `try_println!`, since the failing code may be holding the writer.
+#[doc(hidden)]
+pub fn _assert_failed(
+    condition: &str,
+    message: Option<fmt::Arguments>,
+    file: &str,
+    line: u32,
+    column: u32,
+) -> ! {
+    // Nothing else may run, since whatever the assertion guards is broken.
+    interrupts::disable();
+
+    let message: &dyn fmt::Display = match &message {
+        Some(message) => message,
+        None => &"No message.",
+    };
+    serial_println!(
+        "[ERROR]: Assertion `{condition}` failed at {file}:{line}:{column}: {message}",
+        condition = condition,
+        file = file,
+        line = line,
+        column = column,
+        message = message
+    );
+    try_println!(
+        "[ERROR]: Assertion `{condition}` failed at {file}:{line}:{column}: {message}",
+        condition = condition,
+        file = file,
+        line = line,
+        column = column,
+        message = message
+    );
+
+    #[cfg(test)]
+    crate::exit_qemu(crate::QemuExitCode::Failed);
+
+    hlt_loop();
+}
+
+/// Reports a failed [`test_assert!`] and fails the test run.
+///
+/// # Arguments
+///
+/// * `condition` - The condition that didn't hold, as written.
+/// * `message` - The message, if one was given.
+/// * `file` - The file of the assertion.
+/// * `line` - The line of the assertion.
+/// * `column` - The column of the assertion.
+///
+/// # Returns
+///
+/// * `!` - Never.
+#[doc(hidden)]
+pub fn _test_assert_failed(
+    condition: &str,
+    message: Option<fmt::Arguments>,
+    file: &str,
+    line: u32,
+    column: u32,
+) -> ! {
+    let message: &dyn fmt::Display = match &message {
+        Some(message) => message,
+        None => &"No message.",
+    };
+    serial_println!(
+        "[ERROR]\
+        \nAssertion `{condition}` failed at {file}:{line}:{column}: {message}",
+        condition = condition,
+        file = file,
+        line = line,
+        column = column,
+        message = message
+    );
+
+    exit_qemu(QemuExitCode::Failed);
+    hlt_loop();
+}
+
+/// Runs a function that must panic, like `#[should_panic]`, which the custom test framework doesn't support.
+///
+/// # Arguments
+///
+/// * `f` - The function to run.
+///
+/// # Returns
+///
+/// * `!` - Never, the run continues with the next test from the panic handler.
+///
+/// # Notes
+///
+/// * The panicking test's stack is never unwound, so the function must not panic while holding a lock or anything
+///   else later tests need.
+/// * If the function returns, the test run fails.
+pub fn should_panic(f: impl FnOnce()) -> ! {
+    EXPECT_PANIC.store(true, Ordering::Relaxed);
+    f();
+    EXPECT_PANIC.store(false, Ordering::Relaxed);
+
+    serial_println!("[ERROR]\nError: The test didn't panic!");
+
+    exit_qemu(QemuExitCode::Failed);
+    hlt_loop();
+}
+
+/// Takes whether the running test expects to panic, resetting it.
+///
+/// # Returns
+///
+/// * `bool` - Whether the running test expects to panic.
+pub(crate) fn take_expected_panic() -> bool {
+    EXPECT_PANIC.swap(false, Ordering::Relaxed)
+}
+
+/// Tests that assertions that hold return, with and without a message.
+///
+/// # Panics
+///
+/// * Never, a failed assertion halts instead.
+#[test_case]
+fn test_kassert() {
+    let value = 3;
+
+    kassert!(value == 3);
+    kassert!(value > 2, "{value} is too small!");
+    kdebug_assert!(value < 4, "{value} is too large!");
+}
+
+/// Tests that test assertions that hold return, with and without a message.
+///
+/// # Panics
+///
+/// * Never, a failed assertion fails the run instead.
+#[test_case]
+fn test_test_assert() {
+    let value = 3;
+
+    test_assert!(value == 3);
+    test_assert!(value > 2, "{value} is too small!");
+}
+
+/// Tests that a panic expected by [`should_panic`] lets the run continue.
+///
+/// # Panics
+///
+/// * Always, as expected.
+#[test_case]
+#[allow(clippy::panic)]
+fn test_should_panic() {
+    should_panic(|| panic!("This panic is expected!"));
+}
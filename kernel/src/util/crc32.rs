@@ -0,0 +1,66 @@
+/// The reversed CRC-32 (IEEE 802.3) polynomial.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/// The lookup table, with the CRC of every possible byte.
+static TABLE: [u32; 256] = table();
+
+/// Generates the lookup table.
+///
+/// # Returns
+///
+/// * `[u32; 256]` - The lookup table.
+const fn table() -> [u32; 256] {
+    let mut table = [0; 256];
+
+    let mut byte: u32 = 0;
+    while byte < 256 {
+        let mut crc = byte;
+
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 0 {
+                crc >> 1
+            } else {
+                (crc >> 1) ^ POLYNOMIAL
+            };
+
+            bit += 1;
+        }
+
+        table[byte as usize] = crc;
+        byte += 1;
+    }
+
+    table
+}
+
+/// Calculates the CRC-32 checksum of the given data.
+///
+/// # Arguments
+///
+/// * `data` - The data.
+///
+/// # Returns
+///
+/// * `u32` - The checksum.
+#[must_use]
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, &byte| {
+        TABLE[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+/// Tests the checksum against known test vectors.
+///
+/// # Panics
+///
+/// * If any of the checksums are wrong.
+#[test_case]
+fn test_crc32_vectors() {
+    assert_eq!(crc32(b""), 0);
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    assert_eq!(
+        crc32(b"The quick brown fox jumps over the lazy dog"),
+        0x414F_A339
+    );
+}
@@ -0,0 +1,3 @@
+pub mod assert;
+pub mod crc32;
+pub mod mutex;
@@ -0,0 +1,224 @@
+use core::ops::{Deref, DerefMut};
+#[cfg(debug_assertions)]
+use core::sync::atomic::{AtomicU8, Ordering};
+
+use spin::{Mutex, MutexGuard};
+
+#[cfg(debug_assertions)]
+use crate::sys::idt;
+
+/// The context a [`TrackedMutex`] was locked from.
+///
+/// # Variants
+///
+/// * `Unlocked` - Nothing holds the lock.
+/// * `Normal` - The lock is held by normal code, like a task.
+/// * `Interrupt` - The lock is held by an interrupt handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Owner {
+    Unlocked = 0,
+    Normal = 1,
+    Interrupt = 2,
+}
+
+impl Owner {
+    /// Gets the context the caller is running in.
+    ///
+    /// # Returns
+    ///
+    /// * `Owner` - `Interrupt` inside an interrupt handler, `Normal` otherwise.
+    #[cfg(debug_assertions)]
+    fn current() -> Self {
+        if idt::in_interrupt() {
+            Self::Interrupt
+        } else {
+            Self::Normal
+        }
+    }
+
+    /// Decodes an owner stored as a byte.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The stored byte.
+    ///
+    /// # Returns
+    ///
+    /// * `Owner` - The owner, where unknown values count as `Unlocked`.
+    #[cfg(debug_assertions)]
+    const fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Normal,
+            2 => Self::Interrupt,
+            _ => Self::Unlocked,
+        }
+    }
+}
+
+/// A spin lock that, in debug builds, turns a deadlock from an interrupt handler into a panic.
+///
+/// With a single CPU, an interrupt handler that finds the lock held can never get it, since the holder is the code
+/// it interrupted. A plain spin lock hangs silently there. This one records who holds the lock, and panics naming
+/// the lock and its holder instead.
+///
+/// # Fields
+///
+/// * `name` - The name of the lock, used in the panic message.
+/// * `inner` - The spin lock.
+/// * `owner` - The context holding the lock, as an [`Owner`]. Only tracked in debug builds.
+///
+/// # Notes
+///
+/// * In release builds the tracking compiles out, leaving a plain `spin::Mutex`.
+pub struct TrackedMutex<T> {
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    name: &'static str,
+    inner: Mutex<T>,
+    #[cfg(debug_assertions)]
+    owner: AtomicU8,
+}
+
+impl<T> TrackedMutex<T> {
+    /// Creates a new `TrackedMutex`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the lock, used in the panic message.
+    /// * `value` - The value to protect.
+    #[must_use]
+    pub const fn new(name: &'static str, value: T) -> Self {
+        Self {
+            name,
+            inner: Mutex::new(value),
+            #[cfg(debug_assertions)]
+            owner: AtomicU8::new(Owner::Unlocked as u8),
+        }
+    }
+
+    /// Locks the mutex, spinning until it's available.
+    ///
+    /// # Returns
+    ///
+    /// * `TrackedGuard<T>` - The guard, which unlocks the mutex when dropped.
+    ///
+    /// # Panics
+    ///
+    /// * In debug builds, if called from an interrupt handler while the lock is held, which would never return.
+    pub fn lock(&self) -> TrackedGuard<'_, T> {
+        #[cfg(debug_assertions)]
+        if let Some(owner) = self.deadlock_owner(idt::in_interrupt()) {
+            // Nothing will ever release the lock, so release it by force, letting the panic handler print.
+            unsafe { self.inner.force_unlock() };
+
+            panic!(
+                "Deadlock: `{name}` was locked from an interrupt handler while held by {owner:?} code!",
+                name = self.name
+            );
+        }
+
+        self.track(self.inner.lock())
+    }
+
+    /// Locks the mutex, if it's available.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<TrackedGuard<T>>` - The guard, or `None` if the lock is held.
+    pub fn try_lock(&self) -> Option<TrackedGuard<'_, T>> {
+        self.inner.try_lock().map(|guard| self.track(guard))
+    }
+
+    /// Records the caller as the holder of a freshly taken lock.
+    ///
+    /// # Arguments
+    ///
+    /// * `guard` - The guard of the inner spin lock.
+    ///
+    /// # Returns
+    ///
+    /// * `TrackedGuard<T>` - The guard, which clears the holder when dropped.
+    fn track<'a>(&'a self, guard: MutexGuard<'a, T>) -> TrackedGuard<'a, T> {
+        #[cfg(debug_assertions)]
+        self.owner.store(Owner::current() as u8, Ordering::Relaxed);
+
+        TrackedGuard { mutex: self, guard }
+    }
+
+    /// Checks if locking now would deadlock.
+    ///
+    /// # Arguments
+    ///
+    /// * `in_interrupt` - Whether the caller is an interrupt handler.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Owner>` - The holder of the lock, if locking would deadlock.
+    #[cfg(debug_assertions)]
+    fn deadlock_owner(&self, in_interrupt: bool) -> Option<Owner> {
+        if !in_interrupt || !self.inner.is_locked() {
+            return None;
+        }
+
+        Some(Owner::from_u8(self.owner.load(Ordering::Relaxed)))
+    }
+}
+
+/// A guard of a locked [`TrackedMutex`], giving access to the value.
+///
+/// # Fields
+///
+/// * `mutex` - The mutex, to clear its holder on drop.
+/// * `guard` - The guard of the inner spin lock.
+pub struct TrackedGuard<'a, T> {
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    mutex: &'a TrackedMutex<T>,
+    guard: MutexGuard<'a, T>,
+}
+
+impl<T> Deref for TrackedGuard<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.guard
+    }
+}
+
+impl<T> DerefMut for TrackedGuard<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.guard
+    }
+}
+
+impl<T> Drop for TrackedGuard<'_, T> {
+    /// Clears the holder, right before the inner guard unlocks the mutex.
+    fn drop(&mut self) {
+        #[cfg(debug_assertions)]
+        self.mutex
+            .owner
+            .store(Owner::Unlocked as u8, Ordering::Relaxed);
+    }
+}
+
+/// Tests that the holder is tracked, and that only locking from an interrupt handler while held counts as a deadlock.
+///
+/// # Panics
+///
+/// * If the holder isn't recorded or cleared.
+/// * If a deadlock is reported when there's none.
+#[test_case]
+#[cfg(debug_assertions)]
+fn test_tracked_mutex() {
+    let mutex = TrackedMutex::new("test", 0);
+    assert_eq!(mutex.deadlock_owner(true), None);
+
+    let mut guard = mutex.lock();
+    *guard += 1;
+    assert_eq!(mutex.deadlock_owner(true), Some(Owner::Normal));
+    assert_eq!(mutex.deadlock_owner(false), None);
+    assert!(mutex.try_lock().is_none());
+
+    drop(guard);
+    assert_eq!(mutex.deadlock_owner(true), None);
+    assert_eq!(*mutex.lock(), 1);
+}
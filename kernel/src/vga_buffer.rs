@@ -1,23 +1,117 @@
+use alloc::boxed::Box;
 use core::fmt;
+use core::ops::Range;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
+use conquer_once::spin::OnceCell;
+use crossbeam_queue::ArrayQueue;
 use lazy_static::lazy_static;
-use spin::Mutex;
 use volatile::Volatile;
 
-/// The height of the text buffer (normally 25 lines).
-const BUFFER_HEIGHT: usize = 25;
+use crate::dev::framebuffer::{self, FramebufferWriter};
+use crate::errors::Error;
+use crate::util::mutex::TrackedMutex;
+
+/// The maximum height of the text buffer, in the 80x50 text mode.
+const MAX_BUFFER_HEIGHT: usize = 50;
 /// The width of the text buffer (normally 80 columns).
 const BUFFER_WIDTH: usize = 80;
+/// The default distance between tab stops, in columns.
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+/// The CRT controller index register, used to select the register to access.
+const CRTC_INDEX_PORT: u16 = 0x3D4;
+/// The CRT controller data register, used to access the selected register.
+const CRTC_DATA_PORT: u16 = 0x3D5;
+/// The sequencer index register.
+const SEQUENCER_INDEX_PORT: u16 = 0x3C4;
+/// The sequencer data register.
+const SEQUENCER_DATA_PORT: u16 = 0x3C5;
+/// The graphics controller index register.
+const GRAPHICS_INDEX_PORT: u16 = 0x3CE;
+/// The graphics controller data register.
+const GRAPHICS_DATA_PORT: u16 = 0x3CF;
+/// The physical address of the font memory (plane 2), once mapped by the graphics controller.
+const FONT_ADDRESS: u64 = 0xA0000;
+/// The number of glyphs in a font.
+const FONT_GLYPHS: usize = 256;
+/// The bytes reserved per glyph in font memory, one per scan line.
+const FONT_GLYPH_SIZE: usize = 32;
+/// The offset of the second font block in font memory, which holds the 8x8 font.
+const FONT_BLOCK_1: usize = 0x4000;
+
+/// The Unicode characters of the code page 437 bytes from `0x80` to `0xFF`, the part that differs from ASCII.
+#[rustfmt::skip]
+const CP437_UPPER: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å',
+    'É', 'æ', 'Æ', 'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ',
+    'á', 'í', 'ó', 'ú', 'ñ', 'Ñ', 'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»',
+    '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕', '╣', '║', '╗', '╝', '╜', '╛', '┐',
+    '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦', '╠', '═', '╬', '╧',
+    '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐', '▀',
+    'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩',
+    '≡', '±', '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}',
+];
+/// The code page 437 byte written for characters it doesn't have, a `■`.
+const CP437_UNKNOWN: u8 = 0xFE;
+
+/// The default number of bytes of output buffered while the writer is busy.
+pub const PENDING_OUTPUT_SIZE: usize = 1024;
+
+/// The output of [`try_print!`] that couldn't be printed right away, because the writer was locked.
+static PENDING_OUTPUT: OnceCell<ArrayQueue<u8>> = OnceCell::uninit();
+/// The number of bytes of output dropped because the [`PENDING_OUTPUT`] was full or not initialized yet.
+static DROPPED_OUTPUT: AtomicUsize = AtomicUsize::new(0);
+
+/// A bitmap font of 8x16 glyphs, a byte per row with the leftmost pixel in the highest bit.
+pub type Font = [[u8; 16]; FONT_GLYPHS];
 
 lazy_static! {
     /// A global `Writer` instance that can be used for printing to the VGA text buffer.
     ///
     /// Used by the `print!` and `println!` macros.
-    pub static ref WRITER: Mutex<Writer> = Mutex::new(Writer {
-        column_position: 0,
-        color_code: ColorCode::new(Color::White, Color::Black),
-        buffer: unsafe { &mut *(0xb8000 as *mut Buffer) },
-    });
+    pub static ref WRITER: TrackedMutex<Writer> = TrackedMutex::new(
+        "WRITER",
+        Writer::new(ColorCode::new(Color::White, Color::Black)),
+    );
+}
+
+/// A VGA text mode.
+///
+/// # Variants
+///
+/// * `Text80x25` - 80 columns and 25 rows, with the BIOS 8x16 font.
+/// * `Text80x50` - 80 columns and 50 rows, with an 8x8 font.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextMode {
+    Text80x25,
+    Text80x50,
+}
+
+impl TextMode {
+    /// Gets the number of rows on screen.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of rows.
+    #[must_use]
+    pub const fn height(self) -> usize {
+        match self {
+            Self::Text80x25 => 25,
+            Self::Text80x50 => 50,
+        }
+    }
+
+    /// Gets the height of a character.
+    ///
+    /// # Returns
+    ///
+    /// * `u8` - The height, in scan lines.
+    const fn font_height(self) -> u8 {
+        match self {
+            Self::Text80x25 => 16,
+            Self::Text80x50 => 8,
+        }
+    }
 }
 
 /// The standard color palette in VGA text mode.
@@ -65,6 +159,32 @@ impl ColorCode {
     const fn new(foreground: Color, background: Color) -> Self {
         Self((background as u8) << 4 | (foreground as u8))
     }
+
+    /// Creates a copy of the `ColorCode` with the background color replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `background` - The new background color.
+    ///
+    /// # Returns
+    ///
+    /// * `ColorCode` - The same foreground on the new background.
+    const fn with_background(self, background: Color) -> Self {
+        Self((background as u8) << 4 | (self.0 & 0x0F))
+    }
+
+    /// Creates a copy of the `ColorCode` with the foreground color replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `foreground` - The new foreground color.
+    ///
+    /// # Returns
+    ///
+    /// * `ColorCode` - The new foreground on the same background.
+    const fn with_foreground(self, foreground: Color) -> Self {
+        Self((self.0 & 0xF0) | (foreground as u8))
+    }
 }
 
 /// A screen character in the VGA text buffer, consisting of an ASCII character and a `ColorCode`.
@@ -86,89 +206,490 @@ struct ScreenChar {
 ///
 /// # Fields
 ///
-/// * `chars`: A 2D array of `ScreenChar`s.
+/// * `chars`: A 2D array of `ScreenChar`s, of which only the rows of the current text mode are shown.
 #[repr(transparent)]
 struct Buffer {
-    chars: [[Volatile<ScreenChar>; BUFFER_WIDTH]; BUFFER_HEIGHT],
+    chars: [[Volatile<ScreenChar>; BUFFER_WIDTH]; MAX_BUFFER_HEIGHT],
 }
 
 /// A writer type that allows writing ASCII bytes and strings to an underlying `Buffer`.
 ///
 /// Wraps lines at `BUFFER_WIDTH`. Supports newline characters and implements the `core::fmt::Write` trait.
 ///
+/// Everything is written to a shadow buffer in memory first, and the changed rows are copied to the VGA text buffer by
+/// [`Writer::flush`], so scrolling is a plain memory move instead of a volatile read and write per character.
+///
 /// # Fields
 ///
+/// * `row_position`: The current row position.
 /// * `column_position`: The current column position.
+/// * `height`: The number of rows on screen, set by the text mode.
 /// * `color_code`: The color code.
+/// * `tab_width`: The distance between tab stops, in columns.
+/// * `shadow`: The characters on screen, as they will be after the next flush.
+/// * `dirty`: The rows changed in the shadow buffer since the last flush.
+/// * `batching`: Whether flushing is deferred until the batch ends.
 /// * `buffer`: The buffer.
 pub struct Writer {
+    row_position: usize,
     column_position: usize,
+    height: usize,
     color_code: ColorCode,
+    tab_width: usize,
+    shadow: [[ScreenChar; BUFFER_WIDTH]; MAX_BUFFER_HEIGHT],
+    dirty: Range<usize>,
+    batching: bool,
     buffer: &'static mut Buffer,
 }
 
 impl Writer {
+    /// Creates a new `Writer` on the VGA text buffer, starting at the bottom row.
+    ///
+    /// The shadow buffer starts as a copy of the screen, so the text already on it is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `color_code`: The color code.
+    ///
+    /// # Returns
+    ///
+    /// * `Writer` - The writer.
+    fn new(color_code: ColorCode) -> Self {
+        let buffer = unsafe { &mut *(0xb8000 as *mut Buffer) };
+        let height = TextMode::Text80x25.height();
+
+        let mut shadow = [[ScreenChar {
+            ascii_char: b' ',
+            color_code,
+        }; BUFFER_WIDTH]; MAX_BUFFER_HEIGHT];
+        for (shadow_row, row) in shadow.iter_mut().zip(&buffer.chars).take(height) {
+            for (character, cell) in shadow_row.iter_mut().zip(row) {
+                *character = cell.read();
+            }
+        }
+
+        Self {
+            row_position: height - 1,
+            column_position: 0,
+            height,
+            color_code,
+            tab_width: DEFAULT_TAB_WIDTH,
+            shadow,
+            dirty: 0..0,
+            batching: false,
+            buffer,
+        }
+    }
+
     /// Writes an ASCII byte to the buffer.
     ///
-    /// Wraps lines at `BUFFER_WIDTH`. Supports the `\n` newline character.
+    /// Wraps lines at `BUFFER_WIDTH`. Supports the `\n` newline, `\r` carriage return, `\t` tab and `\x08` backspace
+    /// characters.
     ///
     /// # Arguments
     ///
     /// * `byte`: The byte to write.
     pub fn write_byte(&mut self, byte: u8) {
+        self.put_byte(byte);
+        self.present();
+    }
+
+    /// Writes the given string to the buffer.
+    ///
+    /// Wraps lines at `BUFFER_WIDTH`. Supports the `\n` newline character.
+    /// Characters outside ASCII are translated to code page 437 with [`to_cp437`], or written as `■` if it doesn't
+    /// have them.
+    ///
+    /// # Arguments
+    ///
+    /// * `s`: The string to write.
+    fn write_string(&mut self, s: &str) {
+        if s.is_ascii() {
+            self.write_bytes(s.as_bytes());
+
+            return;
+        }
+
+        // Translated a chunk at a time, so the runs are still copied in bulk by `write_bytes`.
+        let mut chars = s.chars();
+        let mut chunk = [0; BUFFER_WIDTH];
+        loop {
+            let mut len = 0;
+            for (byte, character) in chunk.iter_mut().zip(chars.by_ref()) {
+                *byte = to_cp437(character);
+                len += 1;
+            }
+
+            if len == 0 {
+                break;
+            }
+
+            self.write_bytes(&chunk[..len]);
+        }
+    }
+
+    /// Writes the given bytes to the buffer, a row at a time.
+    ///
+    /// Wraps lines at `BUFFER_WIDTH`. Supports the `\n` newline, `\r` carriage return, `\t` tab and `\x08` backspace
+    /// characters.
+    /// The bytes are code page 437, and control bytes other than those are written as `■`.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes`: The bytes to write.
+    ///
+    /// # Notes
+    ///
+    /// * Each run of printable bytes is bounds checked once, then copied into its row, instead of going through
+    ///   `write_byte` per byte.
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        let mut bytes = bytes;
+        while let Some(&byte) = bytes.first() {
+            match byte {
+                b'\n' | b'\r' | b'\t' | 0x08 => {
+                    self.put_byte(byte);
+                    bytes = &bytes[1..];
+
+                    continue;
+                }
+                _ if self.column_position >= BUFFER_WIDTH => self.new_line(),
+                _ => {}
+            }
+
+            // The printable run ends at a control character, or at the end of the row.
+            let room = BUFFER_WIDTH - self.column_position;
+            let run = bytes
+                .iter()
+                .take(room)
+                .position(|&byte| matches!(byte, b'\n' | b'\r' | b'\t' | 0x08))
+                .unwrap_or_else(|| room.min(bytes.len()));
+
+            let color_code = self.color_code;
+            let start = self.column_position;
+            let row = &mut self.shadow[self.row_position][start..start + run];
+            for (cell, &byte) in row.iter_mut().zip(&bytes[..run]) {
+                let ascii_char = match byte {
+                    0x20..=0x7e | 0x80..=0xff => byte,
+                    _ => CP437_UNKNOWN,
+                };
+
+                *cell = ScreenChar {
+                    ascii_char,
+                    color_code,
+                };
+            }
+
+            self.mark_dirty(self.row_position..self.row_position + 1);
+            self.column_position += run;
+            bytes = &bytes[run..];
+        }
+
+        self.present();
+    }
+
+    /// Gets the number of rows on screen.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of rows, set by the text mode.
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.height
+    }
+
+    /// Gets the number of columns on screen.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` - The number of columns.
+    #[must_use]
+    pub const fn columns(&self) -> usize {
+        BUFFER_WIDTH
+    }
+
+    /// Writes a string at the given position, without moving the writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `row`: The row to write on.
+    /// * `column`: The column of the first character.
+    /// * `s`: The string, translated with [`to_cp437`].
+    ///
+    /// # Notes
+    ///
+    /// * Nothing wraps. Characters past the end of the row are dropped, as is everything if the row is off screen.
+    /// * Control characters are written as `■`, since they have no meaning at a fixed position.
+    pub fn write_at(&mut self, row: usize, column: usize, s: &str) {
+        if row >= self.height {
+            return;
+        }
+
+        let color_code = self.color_code;
+        let cells = self.shadow[row].iter_mut().skip(column);
+        for (cell, character) in cells.zip(s.chars()) {
+            let ascii_char = match to_cp437(character) {
+                byte @ (0x20..=0x7e | 0x80..=0xff) => byte,
+                _ => CP437_UNKNOWN,
+            };
+
+            *cell = ScreenChar {
+                ascii_char,
+                color_code,
+            };
+        }
+
+        self.mark_dirty(row..row + 1);
+        self.present();
+    }
+
+    /// Fills a rectangle with blanks on the given background color, without moving the writer.
+    ///
+    /// # Arguments
+    ///
+    /// * `row`: The top row.
+    /// * `column`: The left column.
+    /// * `width`: The width, in columns.
+    /// * `height`: The height, in rows.
+    /// * `background`: The background color, while the foreground color is kept.
+    ///
+    /// # Notes
+    ///
+    /// * The part of the rectangle that's off screen is skipped.
+    pub fn fill(
+        &mut self,
+        row: usize,
+        column: usize,
+        width: usize,
+        height: usize,
+        background: Color,
+    ) {
+        let rows = row.min(self.height)..row.saturating_add(height).min(self.height);
+        let columns = column.min(BUFFER_WIDTH)..column.saturating_add(width).min(BUFFER_WIDTH);
+        if rows.is_empty() || columns.is_empty() {
+            return;
+        }
+
+        let blank = ScreenChar {
+            ascii_char: b' ',
+            color_code: self.color_code.with_background(background),
+        };
+
+        for row in rows.clone() {
+            self.shadow[row][columns.clone()].fill(blank);
+        }
+
+        self.mark_dirty(rows);
+        self.present();
+    }
+
+    /// Gets the character at the given position, as it will be on screen after the next flush.
+    ///
+    /// # Arguments
+    ///
+    /// * `row`: The row.
+    /// * `column`: The column.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<u8>` - The code page 437 byte, or `None` if the position is off screen.
+    #[must_use]
+    pub fn char_at(&self, row: usize, column: usize) -> Option<u8> {
+        if row >= self.height {
+            return None;
+        }
+
+        self.shadow[row]
+            .get(column)
+            .map(|character| character.ascii_char)
+    }
+
+    /// Gets the position the next character is written at.
+    ///
+    /// # Returns
+    ///
+    /// * `(usize, usize)` - The row and the column.
+    #[must_use]
+    pub const fn position(&self) -> (usize, usize) {
+        (self.row_position, self.column_position)
+    }
+
+    /// Sets the distance between tab stops.
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: The distance, in columns, at least 1.
+    pub fn set_tab_width(&mut self, width: usize) {
+        self.tab_width = width.max(1);
+    }
+
+    /// Defers flushing until [`Writer::end_batch`], for writing a lot of output at once.
+    pub fn begin_batch(&mut self) {
+        self.batching = true;
+    }
+
+    /// Ends a batch started by [`Writer::begin_batch`], flushing everything written during it.
+    pub fn end_batch(&mut self) {
+        self.batching = false;
+        self.flush();
+    }
+
+    /// Copies the rows changed since the last flush from the shadow buffer to the VGA text buffer, and moves the
+    /// hardware cursor to the current position.
+    pub fn flush(&mut self) {
+        let dirty = self.dirty.start..self.dirty.end.min(self.height);
+        for row in dirty {
+            for (cell, &character) in self.buffer.chars[row].iter_mut().zip(&self.shadow[row]) {
+                cell.write(character);
+            }
+        }
+
+        self.dirty = 0..0;
+        self.update_cursor();
+    }
+
+    /// Flushes, unless a batch is in progress.
+    fn present(&mut self) {
+        if !self.batching {
+            self.flush();
+        }
+    }
+
+    /// Marks rows as changed since the last flush.
+    ///
+    /// # Arguments
+    ///
+    /// * `rows`: The changed rows.
+    fn mark_dirty(&mut self, rows: Range<usize>) {
+        self.dirty = if self.dirty.is_empty() {
+            rows
+        } else {
+            self.dirty.start.min(rows.start)..self.dirty.end.max(rows.end)
+        };
+    }
+
+    /// Writes an ASCII byte to the shadow buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `byte`: The byte to write.
+    fn put_byte(&mut self, byte: u8) {
         match byte {
             b'\n' => self.new_line(),
+            // Back to the start of the line without scrolling, so the line can be overwritten in place.
+            b'\r' => self.column_position = 0,
+            b'\t' => self.tab(),
+            0x08 => self.backspace(),
             byte => {
                 if self.column_position >= BUFFER_WIDTH {
                     self.new_line();
                 }
 
-                let row = BUFFER_HEIGHT - 1;
+                let row = self.row_position;
                 let col = self.column_position;
 
                 let color_code = self.color_code;
 
-                self.buffer.chars[row][col].write(ScreenChar {
+                self.shadow[row][col] = ScreenChar {
                     ascii_char: byte,
                     color_code,
-                });
+                };
+                self.mark_dirty(row..row + 1);
 
                 self.column_position += 1;
             }
         }
     }
 
-    /// Writes the given ASCII string to the buffer.
+    /// Moves to the next line.
     ///
-    /// Wraps lines at `BUFFER_WIDTH`. Supports the `\n` newline character.
-    /// Does **not** support strings with non-ASCII characters, since they can't be printed in the VGA text mode.
+    /// If already on the last row, all lines are shifted one line up and the last row is cleared.
+    fn new_line(&mut self) {
+        self.column_position = 0;
+
+        if self.row_position < self.height - 1 {
+            self.row_position += 1;
+
+            return;
+        }
+
+        self.shadow.copy_within(1..self.height, 0);
+        self.clear_row(self.height - 1);
+        self.mark_dirty(0..self.height);
+    }
+
+    /// Pads with spaces up to the next tab stop.
     ///
-    /// # Arguments
+    /// If the tab stop is past the end of the line, moves to the next line instead.
+    fn tab(&mut self) {
+        if self.column_position >= BUFFER_WIDTH {
+            self.new_line();
+        }
+
+        let stop = (self.column_position / self.tab_width + 1) * self.tab_width;
+        if stop > BUFFER_WIDTH {
+            self.new_line();
+
+            return;
+        }
+
+        let blank = ScreenChar {
+            ascii_char: b' ',
+            color_code: self.color_code,
+        };
+
+        self.shadow[self.row_position][self.column_position..stop].fill(blank);
+        self.mark_dirty(self.row_position..self.row_position + 1);
+        self.column_position = stop;
+    }
+
+    /// Moves one column back and erases the character there.
     ///
-    /// * `s`: The string to write.
-    fn write_string(&mut self, s: &str) {
-        for byte in s.bytes() {
-            match byte {
-                // Printable ASCII byte or newline.
-                0x20..=0x7e | b'\n' => self.write_byte(byte),
-                // Not part of printable ASCII range.
-                _ => self.write_byte(0xfe),
-            }
+    /// Does nothing at the start of a line.
+    fn backspace(&mut self) {
+        if self.column_position == 0 {
+            return;
         }
+
+        self.column_position -= 1;
+
+        let blank = ScreenChar {
+            ascii_char: b' ',
+            color_code: self.color_code,
+        };
+
+        self.shadow[self.row_position][self.column_position] = blank;
+        self.mark_dirty(self.row_position..self.row_position + 1);
     }
 
-    /// Shifts all lines one line up and clears the last row.
-    fn new_line(&mut self) {
-        for row in 1..BUFFER_HEIGHT {
-            for col in 0..BUFFER_WIDTH {
-                let character = self.buffer.chars[row][col].read();
+    /// Moves the hardware cursor to the current position.
+    fn update_cursor(&self) {
+        use x86_64::instructions::port::Port;
 
-                self.buffer.chars[row - 1][col].write(character);
-            }
+        let position =
+            self.row_position * BUFFER_WIDTH + self.column_position.min(BUFFER_WIDTH - 1);
+
+        let mut index: Port<u8> = Port::new(CRTC_INDEX_PORT);
+        let mut data: Port<u8> = Port::new(CRTC_DATA_PORT);
+
+        // The position is split over the cursor location low (0x0F) and high (0x0E) registers.
+        let [low, high, ..] = position.to_le_bytes();
+        unsafe {
+            index.write(0x0F);
+            data.write(low);
+            index.write(0x0E);
+            data.write(high);
+        }
+    }
+
+    /// Clears the screen, moving the writer and the hardware cursor to the top left corner.
+    fn clear(&mut self) {
+        for row in 0..self.height {
+            self.clear_row(row);
         }
 
-        self.clear_row(BUFFER_HEIGHT - 1);
+        self.row_position = 0;
         self.column_position = 0;
+        self.present();
     }
 
     /// Clears a row by overwriting it with blank characters.
@@ -182,9 +703,8 @@ impl Writer {
             color_code: self.color_code,
         };
 
-        for col in 0..BUFFER_WIDTH {
-            self.buffer.chars[row][col].write(blank);
-        }
+        self.shadow[row] = [blank; BUFFER_WIDTH];
+        self.mark_dirty(row..row + 1);
     }
 }
 
@@ -218,6 +738,23 @@ macro_rules! println {
     ($($arg:tt)*) => ($crate::print!("{}\n", format_args!($($arg)*)));
 }
 
+/// Like [`print!`], but never blocks, so it's safe to use in interrupt handlers.
+///
+/// If the writer is busy, the output is buffered and printed before the next output.
+#[macro_export]
+macro_rules! try_print {
+    ($($arg:tt)*) => ($crate::vga_buffer::_try_print(format_args!($($arg)*)));
+}
+
+/// Like [`println!`], but never blocks, so it's safe to use in interrupt handlers.
+///
+/// If the writer is busy, the output is buffered and printed before the next output.
+#[macro_export]
+macro_rules! try_println {
+    () => ($crate::try_print!("\n"));
+    ($($arg:tt)*) => ($crate::try_print!("{}\n", format_args!($($arg)*)));
+}
+
 /// Clears the VGA text buffer.
 #[macro_export]
 macro_rules! clear {
@@ -226,7 +763,8 @@ macro_rules! clear {
     };
 }
 
-/// Prints the given formatted string to the VGA text buffer through the global `WRITER` instance.
+/// Prints the given formatted string to the framebuffer if it's set up, otherwise to the VGA text buffer through the
+/// global `WRITER` instance.
 ///
 /// # Arguments
 ///
@@ -243,38 +781,480 @@ pub fn _print(args: fmt::Arguments) {
 
     // We need to disable interrupts to avoid a deadlock when the VGA text buffer is used.
     interrupts::without_interrupts(|| {
-        WRITER
-            .lock()
+        if let Some(writer) = framebuffer::WRITER.get() {
+            let mut writer = writer.lock();
+            flush_pending_output(&mut *writer, FramebufferWriter::write_byte);
+            writer
+                .write_fmt(args)
+                .expect("Printing to framebuffer failed!");
+
+            return;
+        }
+
+        let mut writer = WRITER.lock();
+        flush_pending_output(&mut *writer, Writer::write_byte);
+        writer
             .write_fmt(args)
             .expect("Printing to VGA text buffer failed!");
     });
 }
 
-/// Clears the VGA text buffer by overwriting it with blank characters.
+/// Prints the given formatted string like [`_print`], but buffers it instead of waiting if the writer is locked.
+///
+/// # Arguments
+///
+/// * `args`: The arguments to print.
+///
+/// # Notes
+///
+/// * Doesn't allocate, so the output is dropped if the [`PENDING_OUTPUT`] is full or not initialized yet.
 #[doc(hidden)]
-pub fn _clear() {
+pub fn _try_print(args: fmt::Arguments) {
+    use core::fmt::Write;
     use x86_64::instructions::interrupts;
 
     interrupts::without_interrupts(|| {
-        let mut writer = WRITER.lock();
-        for row in 0..BUFFER_HEIGHT {
-            writer.clear_row(row);
+        // Writing can't fail, so the results are ignored.
+        if let Some(writer) = framebuffer::WRITER.get() {
+            if let Some(mut writer) = writer.try_lock() {
+                flush_pending_output(&mut *writer, FramebufferWriter::write_byte);
+                let _ = writer.write_fmt(args);
+
+                return;
+            }
+        } else if let Some(mut writer) = WRITER.try_lock() {
+            flush_pending_output(&mut *writer, Writer::write_byte);
+            let _ = writer.write_fmt(args);
+
+            return;
         }
 
-        writer.column_position = 0;
+        let _ = PendingOutput.write_fmt(args);
     });
 }
 
-#[test_case]
-fn test_println_simple() {
-    println!("test_println_simple output");
+/// Initializes the [`PENDING_OUTPUT`] with the given capacity.
+///
+/// # Arguments
+///
+/// * `capacity` - The number of bytes of output that can be buffered.
+///
+/// # Returns
+///
+/// * `Result<(), Error>` - The result of the operation.
+///
+/// # Errors
+///
+/// * If the buffer is already initialized.
+pub fn init_pending_output(capacity: usize) -> Result<(), Error> {
+    PENDING_OUTPUT
+        .try_init_once(|| ArrayQueue::new(capacity))
+        .map_err(|_| Error::Internal("Pending output is already initialized!".into()))
 }
 
-/// Tests that the VGA text buffer is scrolled correctly.
-#[test_case]
-fn test_println_many() {
-    for _ in 0..200 {
-        println!("test_println_many output");
+/// Writes out the buffered output of [`try_print!`], with a warning if any of it was dropped.
+///
+/// # Arguments
+///
+/// * `writer` - The locked writer.
+/// * `write_byte` - Writes a raw byte with the writer.
+fn flush_pending_output<W: fmt::Write>(writer: &mut W, write_byte: fn(&mut W, u8)) {
+    let Some(queue) = PENDING_OUTPUT.get() else {
+        return;
+    };
+
+    while let Some(byte) = queue.pop() {
+        write_byte(writer, byte);
+    }
+
+    let dropped = DROPPED_OUTPUT.swap(0, Ordering::Relaxed);
+    if dropped > 0 {
+        let _ = writeln!(writer, "[WARN]: Dropped {dropped} byte(s) of output!");
+    }
+}
+
+/// Buffers output in the [`PENDING_OUTPUT`], without blocking or allocating.
+struct PendingOutput;
+
+impl fmt::Write for PendingOutput {
+    /// Buffers a string, dropping what doesn't fit.
+    ///
+    /// # Arguments
+    ///
+    /// * `s` - The string to buffer.
+    ///
+    /// # Returns
+    ///
+    /// * `fmt::Result` - Always `Ok`, since dropped output is counted instead.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let Some(queue) = PENDING_OUTPUT.get() else {
+            DROPPED_OUTPUT.fetch_add(s.chars().count(), Ordering::Relaxed);
+
+            return Ok(());
+        };
+
+        // Queued as code page 437, since the queue is flushed a raw byte at a time.
+        for byte in s.chars().map(to_cp437) {
+            if queue.push(byte).is_err() {
+                DROPPED_OUTPUT.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Translates a character to its code page 437 byte, which the VGA font and the framebuffer console draw.
+///
+/// # Arguments
+///
+/// * `character` - The character.
+///
+/// # Returns
+///
+/// * `u8` - The byte, which is the character itself for ASCII, or `0xFE` (`■`) if code page 437 doesn't have it.
+///
+/// # Notes
+///
+/// * A few look-alikes are mapped too, like `•` to `∙` and `β` to `ß`, since code page 437 draws them the same.
+#[must_use]
+pub fn to_cp437(character: char) -> u8 {
+    if let Ok(byte) = u8::try_from(character) {
+        if byte.is_ascii() {
+            return byte;
+        }
+    }
+
+    let character = match character {
+        '•' => '∙',
+        'β' => 'ß',
+        'μ' => 'µ',
+        '∅' => 'φ',
+        '∈' => 'ε',
+        character => character,
+    };
+
+    CP437_UPPER
+        .iter()
+        .position(|&upper| upper == character)
+        .and_then(|index| u8::try_from(0x80 + index).ok())
+        .unwrap_or(CP437_UNKNOWN)
+}
+
+/// Prints the given formatted string like [`print!`], but in the given foreground color.
+///
+/// # Arguments
+///
+/// * `color` - The foreground color, restored once the string is printed.
+/// * `args` - The arguments to print.
+///
+/// # Panics
+///
+/// * If writing to the VGA text buffer fails.
+#[allow(clippy::expect_used)]
+pub fn print_colored(color: Color, args: fmt::Arguments) {
+    use core::fmt::Write;
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        if let Some(writer) = framebuffer::WRITER.get() {
+            let mut writer = writer.lock();
+            let foreground = writer.foreground();
+            writer.set_foreground(color.into());
+            writer
+                .write_fmt(args)
+                .expect("Printing to framebuffer failed!");
+            writer.set_foreground(foreground);
+
+            return;
+        }
+
+        let mut writer = WRITER.lock();
+        let color_code = writer.color_code;
+        writer.color_code = color_code.with_foreground(color);
+        writer
+            .write_fmt(args)
+            .expect("Printing to VGA text buffer failed!");
+        writer.color_code = color_code;
+    });
+}
+
+/// Gets the position of the cursor on the console.
+///
+/// # Returns
+///
+/// * `(usize, usize)` - The row and the column the next character is printed at, on the framebuffer if it's set up,
+///   otherwise on the VGA text buffer.
+#[must_use]
+pub fn cursor() -> (usize, usize) {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        framebuffer::WRITER.get().map_or_else(
+            || WRITER.lock().position(),
+            |writer| writer.lock().position(),
+        )
+    })
+}
+
+/// Clears the framebuffer if it's set up, otherwise the VGA text buffer by overwriting it with blank characters.
+///
+/// The writer and the hardware cursor are moved to the top left corner.
+#[doc(hidden)]
+pub fn _clear() {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| match framebuffer::WRITER.get() {
+        Some(writer) => writer.lock().clear(),
+        None => WRITER.lock().clear(),
+    });
+}
+
+/// Sets the distance between the tab stops of the VGA text buffer.
+///
+/// # Arguments
+///
+/// * `width` - The distance, in columns, at least 1.
+pub fn set_tab_width(width: usize) {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| WRITER.lock().set_tab_width(width));
+}
+
+/// Defers updating the VGA text buffer until [`end_batch`], for printing a lot of output at once.
+///
+/// # Notes
+///
+/// * Nothing printed during the batch is shown until it ends, so keep batches short and always end them.
+pub fn begin_batch() {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| WRITER.lock().begin_batch());
+}
+
+/// Ends a batch started by [`begin_batch`], showing everything printed during it.
+pub fn end_batch() {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| WRITER.lock().end_batch());
+}
+
+/// Switches the VGA text mode, clearing the screen.
+///
+/// The rows are halved in height by changing the maximum scan line of the CRT controller, so the vertical timings
+/// stay the same. The 8x8 font is derived from the BIOS 8x16 font and loaded into the second font block, leaving the
+/// original font intact for switching back.
+///
+/// # Arguments
+///
+/// * `mode` - The text mode to switch to.
+///
+/// # Notes
+///
+/// * Reaching the font memory requires the physical memory to be mapped, so this must be called after `mem::init`.
+pub fn set_text_mode(mode: TextMode) {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        // Holding the writer keeps anyone from printing while the font memory replaces the text buffer.
+        let mut writer = WRITER.lock();
+
+        let font_block = match mode {
+            TextMode::Text80x25 => 0,
+            TextMode::Text80x50 => {
+                // SAFETY: The writer is locked with interrupts disabled, so nothing else touches VGA memory.
+                unsafe { load_8x8_font() };
+
+                1
+            }
+        };
+
+        unsafe {
+            // Character map select, using the same block for both character maps.
+            write_register(
+                SEQUENCER_INDEX_PORT,
+                SEQUENCER_DATA_PORT,
+                0x03,
+                font_block << 2 | font_block,
+            );
+
+            // Maximum scan line, keeping the line compare and double scan bits.
+            let font_height = mode.font_height();
+            let max_scan_line = read_register(CRTC_INDEX_PORT, CRTC_DATA_PORT, 0x09);
+            write_register(
+                CRTC_INDEX_PORT,
+                CRTC_DATA_PORT,
+                0x09,
+                max_scan_line & 0xE0 | (font_height - 1),
+            );
+
+            // Cursor start and end, keeping the cursor an underline on the last two scan lines.
+            let cursor_start = read_register(CRTC_INDEX_PORT, CRTC_DATA_PORT, 0x0A);
+            write_register(
+                CRTC_INDEX_PORT,
+                CRTC_DATA_PORT,
+                0x0A,
+                cursor_start & 0xE0 | (font_height - 2),
+            );
+            let cursor_end = read_register(CRTC_INDEX_PORT, CRTC_DATA_PORT, 0x0B);
+            write_register(
+                CRTC_INDEX_PORT,
+                CRTC_DATA_PORT,
+                0x0B,
+                cursor_end & 0xE0 | (font_height - 1),
+            );
+        }
+
+        writer.height = mode.height();
+        writer.clear();
+    });
+}
+
+/// Builds an 8x8 font from the 8x16 font in the first font block, and stores it in the second font block.
+///
+/// Each row of the new glyph is the OR of two rows of the old one, so thin strokes aren't lost.
+///
+/// # Safety
+///
+/// * The caller must make sure nothing else accesses VGA memory, since the text buffer is unmapped meanwhile.
+/// * The physical memory must be mapped at `mem::PHYSICAL_MEMORY_OFFSET`.
+unsafe fn load_8x8_font() {
+    let font = map_font_memory();
+    for glyph in 0..FONT_GLYPHS {
+        let source = font.add(glyph * FONT_GLYPH_SIZE);
+        let destination = font.add(FONT_BLOCK_1 + glyph * FONT_GLYPH_SIZE);
+
+        for row in 0..8 {
+            let line =
+                source.add(row * 2).read_volatile() | source.add(row * 2 + 1).read_volatile();
+
+            destination.add(row).write_volatile(line);
+        }
+    }
+
+    unmap_font_memory();
+}
+
+/// Copies the BIOS 8x16 font out of the first font block.
+///
+/// # Returns
+///
+/// * `Box<Font>` - The font.
+///
+/// # Notes
+///
+/// * Reaching the font memory requires the physical memory to be mapped, so this must be called after `mem::init`.
+pub(crate) fn read_font() -> Box<Font> {
+    use x86_64::instructions::interrupts;
+
+    let mut glyphs = Box::new([[0; 16]; FONT_GLYPHS]);
+    interrupts::without_interrupts(|| {
+        // Holding the writer keeps anyone from printing while the font memory replaces the text buffer.
+        let _writer = WRITER.lock();
+
+        // SAFETY: The writer is locked with interrupts disabled, so nothing else touches VGA memory.
+        unsafe {
+            let font = map_font_memory();
+            for (index, glyph) in glyphs.iter_mut().enumerate() {
+                let source = font.add(index * FONT_GLYPH_SIZE);
+
+                for (row, line) in glyph.iter_mut().enumerate() {
+                    *line = source.add(row).read_volatile();
+                }
+            }
+
+            unmap_font_memory();
+        }
+    });
+
+    glyphs
+}
+
+/// Maps the font memory (plane 2) at `0xA0000`, with sequential addressing.
+///
+/// # Returns
+///
+/// * `*mut u8` - The start of the font memory.
+///
+/// # Safety
+///
+/// * The caller must make sure nothing else accesses VGA memory, since the text buffer is unmapped until
+///   [`unmap_font_memory`] is called.
+/// * The physical memory must be mapped at `mem::PHYSICAL_MEMORY_OFFSET`.
+unsafe fn map_font_memory() -> *mut u8 {
+    write_register(SEQUENCER_INDEX_PORT, SEQUENCER_DATA_PORT, 0x02, 0x04);
+    write_register(SEQUENCER_INDEX_PORT, SEQUENCER_DATA_PORT, 0x04, 0x07);
+    write_register(GRAPHICS_INDEX_PORT, GRAPHICS_DATA_PORT, 0x04, 0x02);
+    write_register(GRAPHICS_INDEX_PORT, GRAPHICS_DATA_PORT, 0x05, 0x00);
+    write_register(GRAPHICS_INDEX_PORT, GRAPHICS_DATA_PORT, 0x06, 0x04);
+
+    (crate::mem::PHYSICAL_MEMORY_OFFSET + FONT_ADDRESS) as *mut u8
+}
+
+/// Restores the text mode mapping of planes 0 and 1 at `0xB8000`, with odd/even addressing.
+///
+/// # Safety
+///
+/// * The caller must have mapped the font memory with [`map_font_memory`].
+unsafe fn unmap_font_memory() {
+    write_register(SEQUENCER_INDEX_PORT, SEQUENCER_DATA_PORT, 0x02, 0x03);
+    write_register(SEQUENCER_INDEX_PORT, SEQUENCER_DATA_PORT, 0x04, 0x03);
+    write_register(GRAPHICS_INDEX_PORT, GRAPHICS_DATA_PORT, 0x04, 0x00);
+    write_register(GRAPHICS_INDEX_PORT, GRAPHICS_DATA_PORT, 0x05, 0x10);
+    write_register(GRAPHICS_INDEX_PORT, GRAPHICS_DATA_PORT, 0x06, 0x0E);
+}
+
+/// Reads an indexed VGA register.
+///
+/// # Arguments
+///
+/// * `index_port` - The index port of the register group.
+/// * `data_port` - The data port of the register group.
+/// * `index` - The index of the register.
+///
+/// # Returns
+///
+/// * `u8` - The value of the register.
+///
+/// # Safety
+///
+/// * The caller must make sure the ports belong to a VGA register group.
+unsafe fn read_register(index_port: u16, data_port: u16, index: u8) -> u8 {
+    use x86_64::instructions::port::Port;
+
+    Port::<u8>::new(index_port).write(index);
+    Port::<u8>::new(data_port).read()
+}
+
+/// Writes an indexed VGA register.
+///
+/// # Arguments
+///
+/// * `index_port` - The index port of the register group.
+/// * `data_port` - The data port of the register group.
+/// * `index` - The index of the register.
+/// * `value` - The value to write.
+///
+/// # Safety
+///
+/// * The caller must make sure the ports belong to a VGA register group, and that the value is valid for the register.
+unsafe fn write_register(index_port: u16, data_port: u16, index: u8, value: u8) {
+    use x86_64::instructions::port::Port;
+
+    Port::<u8>::new(index_port).write(index);
+    Port::<u8>::new(data_port).write(value);
+}
+
+#[test_case]
+fn test_println_simple() {
+    println!("test_println_simple output");
+}
+
+/// Tests that the VGA text buffer is scrolled correctly.
+#[test_case]
+fn test_println_many() {
+    for _ in 0..200 {
+        println!("test_println_many output");
     }
 }
 
@@ -294,13 +1274,76 @@ fn test_println_output() {
         writeln!(writer, "\n{s}").expect("writeln failed!");
 
         for (i, c) in s.chars().enumerate() {
-            let screen_char = writer.buffer.chars[BUFFER_HEIGHT - 2][i].read();
+            let screen_char = writer.buffer.chars[writer.height - 2][i].read();
+
+            assert_eq!(char::from(screen_char.ascii_char), c);
+        }
+    });
+}
+
+/// Tests that output printed while the writer is locked is buffered, and printed before the next output.
+///
+/// # Panics
+///
+/// * If the output is printed while the writer is locked.
+/// * If the buffered output isn't printed before the next output.
+#[test_case]
+#[allow(clippy::expect_used)]
+fn test_try_print() {
+    use core::fmt::Write;
+    use x86_64::instructions::interrupts;
+
+    // The kernel initializes it during boot, so it may already be.
+    let _ = init_pending_output(PENDING_OUTPUT_SIZE);
+
+    let s = "Printed while the writer was locked.";
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+        writeln!(writer).expect("writeln failed!");
+
+        _try_print(format_args!("{s}"));
+        let row = writer.height - 1;
+        assert_eq!(writer.buffer.chars[row][0].read().ascii_char, b' ');
+
+        drop(writer);
+        _print(format_args!("\n"));
+
+        let writer = WRITER.lock();
+        for (i, c) in s.chars().enumerate() {
+            let screen_char = writer.buffer.chars[writer.height - 2][i].read();
 
             assert_eq!(char::from(screen_char.ascii_char), c);
         }
     });
 }
 
+/// Tests that colored output uses the given foreground, and that the color is restored after.
+///
+/// # Panics
+///
+/// * If the output isn't in the given foreground on the same background.
+/// * If the writer's color isn't restored.
+#[test_case]
+fn test_print_colored() {
+    use x86_64::instructions::interrupts;
+
+    print_colored(Color::LightGreen, format_args!("\nOK"));
+
+    interrupts::without_interrupts(|| {
+        let writer = WRITER.lock();
+        let (row, column) = writer.position();
+
+        assert_eq!(
+            writer.shadow[row][column - 1].color_code,
+            writer.color_code.with_foreground(Color::LightGreen)
+        );
+        assert_eq!(
+            writer.color_code,
+            ColorCode::new(Color::White, Color::Black)
+        );
+    });
+}
+
 /// Tests that the VGA text buffer colors are set correctly.
 ///
 /// # Panics
@@ -314,17 +1357,268 @@ fn test_colors() {
     // Test printing.
     let message = "Hello, world!";
     let color_code = ColorCode::new(foreground, background);
-    let mut writer = Writer {
-        column_position: 0,
-        color_code,
-        buffer: unsafe { &mut *(0xb8000 as *mut Buffer) },
-    };
+    let height = TextMode::Text80x25.height();
+    let mut writer = Writer::new(color_code);
 
     writer.write_string(message);
 
     // Add an assertion to test the color of the first character.
     let buffer = unsafe { &*(0xb8000 as *const Buffer) };
-    let screen_char = buffer.chars[BUFFER_HEIGHT - 1][0].read();
+    let screen_char = buffer.chars[height - 1][0].read();
 
     assert_eq!(screen_char.color_code, color_code);
 }
+
+/// Tests that clearing the VGA text buffer moves the writer to the top left corner.
+///
+/// # Panics
+///
+/// * If the writer isn't at row 0, column 0 after clearing.
+/// * If the next character isn't written to the top left corner.
+#[test_case]
+fn test_clear() {
+    use x86_64::instructions::interrupts;
+
+    print!("test_clear output");
+    clear!();
+
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+
+        assert_eq!((writer.row_position, writer.column_position), (0, 0));
+
+        writer.write_string("x");
+
+        assert_eq!(writer.buffer.chars[0][0].read().ascii_char, b'x');
+    });
+}
+
+/// Tests that tabs pad up to the next tab stop, and wrap at the end of the line.
+///
+/// # Panics
+///
+/// * If a tab doesn't land on the expected column.
+/// * If a tab isn't padded with spaces.
+#[test_case]
+fn test_tab_stops() {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+
+        writer.write_string("\nab\t");
+        assert_eq!(writer.column_position, 8);
+        assert_eq!(writer.shadow[writer.row_position][2].ascii_char, b' ');
+
+        writer.set_tab_width(4);
+        writer.write_string("x\t");
+        assert_eq!(writer.column_position, 12);
+
+        // A stop past the end of the line wraps to the next line.
+        writer.set_tab_width(DEFAULT_TAB_WIDTH - 1);
+        writer.write_string("\n");
+        writer.write_bytes(&[b'x'; BUFFER_WIDTH - 1]);
+        let row = writer.row_position;
+        writer.write_string("\t");
+        assert_eq!(writer.column_position, 0);
+        assert!(writer.row_position == row + 1 || writer.row_position == writer.height - 1);
+
+        writer.set_tab_width(DEFAULT_TAB_WIDTH);
+        writer.write_string("\n");
+    });
+}
+
+/// Tests that a carriage return overwrites the line in place, and that CRLF moves down a single line.
+///
+/// # Panics
+///
+/// * If the line isn't overwritten.
+/// * If CRLF doesn't end on a blank line, right below the written one.
+#[test_case]
+fn test_carriage_return() {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+
+        writer.write_string("\nLoading 10%\rLoading 50%\r\n");
+        assert_eq!(writer.column_position, 0);
+
+        let line = &writer.shadow[writer.row_position - 1][..11];
+        assert!(line
+            .iter()
+            .map(|character| character.ascii_char)
+            .eq(*b"Loading 50%"));
+        assert_eq!(writer.shadow[writer.row_position][0].ascii_char, b' ');
+    });
+}
+
+/// Tests that output written during a batch only reaches the screen once the batch ends.
+///
+/// # Panics
+///
+/// * If the output reaches the screen before the batch ends.
+/// * If the output isn't on screen after the batch ends.
+#[test_case]
+fn test_batch() {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+        let row = writer.height - 1;
+
+        writer.write_string("\n");
+        writer.begin_batch();
+        writer.write_string("batched\nbatched");
+        assert_eq!(writer.buffer.chars[row][0].read().ascii_char, b' ');
+
+        writer.end_batch();
+        assert_eq!(writer.buffer.chars[row - 1][0].read().ascii_char, b'b');
+        assert_eq!(writer.buffer.chars[row][0].read().ascii_char, b'b');
+    });
+}
+
+/// Tests that switching to the 80x50 text mode scrolls at the 50th row.
+///
+/// # Panics
+///
+/// * If the writer doesn't use the height of the text mode.
+/// * If a full screen of lines doesn't end on the last row.
+#[test_case]
+fn test_text_mode() {
+    use x86_64::instructions::interrupts;
+
+    set_text_mode(TextMode::Text80x50);
+    for _ in 0..TextMode::Text80x50.height() * 2 {
+        println!("test_text_mode output");
+    }
+
+    interrupts::without_interrupts(|| {
+        let writer = WRITER.lock();
+
+        assert_eq!(writer.height, TextMode::Text80x50.height());
+        assert_eq!(writer.row_position, TextMode::Text80x50.height() - 1);
+        assert_eq!(
+            writer.buffer.chars[writer.height - 2][0].read().ascii_char,
+            b't'
+        );
+    });
+
+    set_text_mode(TextMode::Text80x25);
+    interrupts::without_interrupts(|| {
+        assert_eq!(WRITER.lock().height, TextMode::Text80x25.height())
+    });
+}
+
+/// Tests that `write_bytes` fills the screen like `write_byte` does, and compares their speed on 10 KiB of text.
+///
+/// # Panics
+///
+/// * If the screens differ.
+#[test_case]
+fn test_write_bytes() {
+    use alloc::vec::Vec;
+    use x86_64::instructions::interrupts;
+
+    use crate::sys::time;
+
+    let text: Vec<u8> = b"The quick brown fox jumps over the lazy dog.\n\x08\x7f"
+        .iter()
+        .copied()
+        .cycle()
+        .take(10 * 1024)
+        .collect();
+
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+
+        writer.clear();
+        let ((), bytewise) = time::measure(|| {
+            for &byte in &text {
+                writer.write_byte(match byte {
+                    0x20..=0x7e | b'\n' | 0x08 => byte,
+                    _ => 0xfe,
+                });
+            }
+        });
+        let expected: Vec<_> = (0..writer.height)
+            .flat_map(|row| writer.buffer.chars[row].iter().map(Volatile::read))
+            .collect();
+        let position = (writer.row_position, writer.column_position);
+
+        writer.clear();
+        let ((), bulk) = time::measure(|| writer.write_bytes(&text));
+        let actual: Vec<_> = (0..writer.height)
+            .flat_map(|row| writer.buffer.chars[row].iter().map(Volatile::read))
+            .collect();
+
+        assert_eq!((writer.row_position, writer.column_position), position);
+        assert!(actual == expected);
+
+        writer.clear();
+        drop(writer);
+
+        println!("[INFO]: Wrote 10 KiB in {bytewise} ns bytewise, {bulk} ns with `write_bytes`.");
+    });
+}
+
+/// Tests that characters outside ASCII are translated to code page 437, and written as such.
+///
+/// # Panics
+///
+/// * If a character isn't translated to its code page 437 byte.
+/// * If the translated bytes aren't written to the VGA text buffer.
+#[test_case]
+fn test_cp437() {
+    use core::fmt::Write;
+    use x86_64::instructions::interrupts;
+
+    assert_eq!(to_cp437('A'), b'A');
+    assert_eq!(to_cp437('é'), 0x82);
+    assert_eq!(to_cp437('░'), 0xB0);
+    assert_eq!(to_cp437('┌'), 0xDA);
+    assert_eq!(to_cp437('•'), 0xF9);
+    assert_eq!(to_cp437('€'), CP437_UNKNOWN);
+
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+        writeln!(writer, "\nCafé ┌─┐").expect("writeln failed!");
+
+        let row = &writer.buffer.chars[writer.height - 2];
+        let bytes = row[..8].iter().map(|cell| cell.read().ascii_char);
+        assert!(bytes.eq(*b"Caf\x82 \xDA\xC4\xBF"));
+    });
+}
+
+/// Tests that writing at a position leaves the writer where it was, and clips at the end of the row.
+///
+/// # Panics
+///
+/// * If the writer moves.
+/// * If the text isn't written, or wraps to the next row.
+/// * If the filled rectangle isn't blank.
+#[test_case]
+fn test_write_at() {
+    use x86_64::instructions::interrupts;
+
+    interrupts::without_interrupts(|| {
+        let mut writer = WRITER.lock();
+        let position = (writer.row_position, writer.column_position);
+
+        writer.write_at(0, BUFFER_WIDTH - 2, "abc");
+        assert_eq!(writer.char_at(0, BUFFER_WIDTH - 2), Some(b'a'));
+        assert_eq!(writer.char_at(0, BUFFER_WIDTH - 1), Some(b'b'));
+        assert_ne!(writer.char_at(1, 0), Some(b'c'));
+        assert_eq!(writer.char_at(writer.height, 0), None);
+
+        writer.fill(0, BUFFER_WIDTH - 2, 10, 1, Color::Blue);
+        assert_eq!(writer.char_at(0, BUFFER_WIDTH - 2), Some(b' '));
+        assert_eq!(
+            writer.shadow[0][BUFFER_WIDTH - 1].color_code,
+            writer.color_code.with_background(Color::Blue)
+        );
+
+        assert_eq!((writer.row_position, writer.column_position), position);
+        writer.fill(0, 0, BUFFER_WIDTH, 1, Color::Black);
+    });
+}
@@ -0,0 +1,984 @@
+#![no_std]
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::future::{self, Future};
+use core::pin::Pin;
+use core::task::Poll;
+
+use kernel::allocator;
+use kernel::dev::{ata, pci};
+use kernel::fs::fat;
+use kernel::sys::task::channel::Channel;
+use kernel::sys::task::executor::Spawner;
+use kernel::sys::task::keyboard::{self, DecodedKey, Layout};
+use kernel::sys::task::{self, primes, watchdog};
+use kernel::sys::time::rtc::RTC;
+use kernel::sys::time::{self, clock};
+use kernel::sys::{calls, cpu, rand, selftest};
+use kernel::{clear, print, println};
+use kernel::{fs, mem, tui, KERNEL_VERSION};
+use stdlib::command::{self, Command, CommandFuture, Flow};
+
+/// The prompt printed before each command.
+const PROMPT: &str = "> ";
+/// The width of the screen, in characters.
+const SCREEN_WIDTH: usize = 80;
+/// The width of a column of names `ls` prints, the longest 8.3 name plus a gap.
+const LS_COLUMN_WIDTH: usize = 14;
+/// The number of columns of names `ls` fits on the screen.
+const LS_COLUMNS: usize = SCREEN_WIDTH / LS_COLUMN_WIDTH;
+/// The width of the box `top` draws, in characters.
+const TOP_WIDTH: usize = 44;
+
+/// The shell variables, shared between the shell and the commands that change them.
+type Environment = Rc<RefCell<BTreeMap<String, String>>>;
+
+/// The builtin commands that run to completion right away.
+const BUILTINS: &[Builtin] = &[
+    Builtin {
+        name: "clear",
+        description: "Clear the screen",
+        handler: |_| clear!(),
+    },
+    Builtin {
+        name: "cpuinfo",
+        description: "Print the CPU vendor and features",
+        handler: |_| cpuinfo(),
+    },
+    Builtin {
+        name: "df",
+        description: "Print the free space of the file system",
+        handler: |_| df(),
+    },
+    Builtin {
+        name: "keymap",
+        description: "Print or switch the keyboard layout",
+        handler: keymap,
+    },
+    Builtin {
+        name: "ls",
+        description: "List the files in a directory",
+        handler: ls,
+    },
+    Builtin {
+        name: "lspci",
+        description: "List the devices on the PCI bus",
+        handler: |_| lspci(),
+    },
+    Builtin {
+        name: "meminfo",
+        description: "Print the heap and physical memory usage",
+        handler: |_| meminfo(),
+    },
+    Builtin {
+        name: "memtest",
+        description: "Test the free physical memory",
+        handler: |_| memtest(),
+    },
+    Builtin {
+        name: "random",
+        description: "Print a random number",
+        handler: |_| println!("{number}", number = rand::random_u64()),
+    },
+    Builtin {
+        name: "selftest",
+        description: "Run the kernel diagnostics",
+        handler: |_| selftest(),
+    },
+    Builtin {
+        name: "uname",
+        description: "Print the kernel version",
+        handler: uname,
+    },
+    Builtin {
+        name: "uptime",
+        description: "Print how long the system has been up",
+        handler: |_| uptime(),
+    },
+];
+
+/// A command built into the shell, which runs to completion right away.
+///
+/// # Fields
+///
+/// * `name` - The name the command is run by.
+/// * `description` - A one line description, listed by `help`.
+/// * `handler` - Runs the command with its arguments.
+#[derive(Clone, Copy)]
+struct Builtin {
+    name: &'static str,
+    description: &'static str,
+    handler: fn(&[&str]),
+}
+
+impl Command for Builtin {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn description(&self) -> &'static str {
+        self.description
+    }
+
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a> {
+        (self.handler)(args);
+
+        command::ready(Flow::Continue)
+    }
+}
+
+/// Prints its arguments, separated by spaces.
+struct Echo;
+
+impl Command for Echo {
+    fn name(&self) -> &'static str {
+        "echo"
+    }
+
+    fn description(&self) -> &'static str {
+        "Print the arguments"
+    }
+
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a> {
+        // Printed through the system call interface, so `strace echo` has something to show.
+        stdlib::print(&format!("{line}\n", line = args.join(" ")));
+
+        command::ready(Flow::Continue)
+    }
+}
+
+/// Stops the shell.
+struct Exit;
+
+impl Command for Exit {
+    fn name(&self) -> &'static str {
+        "exit"
+    }
+
+    fn description(&self) -> &'static str {
+        "Exit the shell"
+    }
+
+    fn run<'a>(&'a self, _args: &'a [&'a str]) -> CommandFuture<'a> {
+        command::ready(Flow::Exit)
+    }
+}
+
+/// Lists the commands and their descriptions.
+///
+/// # Fields
+///
+/// * `commands` - The names and descriptions of the commands, including `help` itself.
+struct Help {
+    commands: Vec<(&'static str, &'static str)>,
+}
+
+impl Command for Help {
+    fn name(&self) -> &'static str {
+        "help"
+    }
+
+    fn description(&self) -> &'static str {
+        "List the available commands"
+    }
+
+    fn run<'a>(&'a self, _args: &'a [&'a str]) -> CommandFuture<'a> {
+        let width = self
+            .commands
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or_default();
+
+        for (name, description) in &self.commands {
+            println!("{name:width$}  {description}");
+        }
+
+        command::ready(Flow::Continue)
+    }
+}
+
+/// Sets a variable, or prints all of them.
+///
+/// # Fields
+///
+/// * `environment` - The shell variables.
+struct Set {
+    environment: Environment,
+}
+
+impl Command for Set {
+    fn name(&self) -> &'static str {
+        "set"
+    }
+
+    fn description(&self) -> &'static str {
+        "Set a variable, or list all of them"
+    }
+
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a> {
+        if args.is_empty() {
+            for (name, value) in self.environment.borrow().iter() {
+                println!("{name}={value}");
+            }
+
+            return command::ready(Flow::Continue);
+        }
+
+        // The value is the rest of the line, so it may contain spaces.
+        let line = args.join(" ");
+        match line.split_once('=') {
+            Some((name, value)) if is_variable_name(name) => {
+                self.environment
+                    .borrow_mut()
+                    .insert(name.to_string(), value.to_string());
+            }
+            _ => println!("Usage: set [NAME=value]"),
+        }
+
+        command::ready(Flow::Continue)
+    }
+}
+
+/// Removes a variable.
+///
+/// # Fields
+///
+/// * `environment` - The shell variables.
+struct Unset {
+    environment: Environment,
+}
+
+impl Command for Unset {
+    fn name(&self) -> &'static str {
+        "unset"
+    }
+
+    fn description(&self) -> &'static str {
+        "Remove a variable"
+    }
+
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a> {
+        match args {
+            [name] if is_variable_name(name) => {
+                self.environment.borrow_mut().remove(*name);
+            }
+            _ => println!("Usage: unset NAME"),
+        }
+
+        command::ready(Flow::Continue)
+    }
+}
+
+/// Checks if a string is a valid variable name, made of ASCII letters, digits and underscores, not starting with a
+/// digit.
+///
+/// # Arguments
+///
+/// * `name` - The name to check.
+///
+/// # Returns
+///
+/// * `bool` - Whether the name is valid.
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Replaces the tokens of the form `$NAME` with the value of the variable, or nothing if it isn't set.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens of the command line.
+/// * `environment` - The shell variables.
+///
+/// # Returns
+///
+/// * `Vec<String>` - The expanded tokens.
+fn expand(tokens: &[&str], environment: &BTreeMap<String, String>) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|token| match token.strip_prefix('$') {
+            Some(name) if is_variable_name(name) => {
+                environment.get(name).cloned().unwrap_or_default()
+            }
+            _ => (*token).to_string(),
+        })
+        .collect()
+}
+
+/// Counts primes in a separate task.
+///
+/// # Fields
+///
+/// * `spawner` - The spawner used to run the computation.
+struct Primes {
+    spawner: Spawner,
+}
+
+impl Command for Primes {
+    fn name(&self) -> &'static str {
+        "primes"
+    }
+
+    fn description(&self) -> &'static str {
+        "Count the primes below a limit"
+    }
+
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a> {
+        Box::pin(async move {
+            primes(&self.spawner, args).await;
+
+            Flow::Continue
+        })
+    }
+}
+
+/// Prints a file, kernel memory or a disk block in hexadecimal.
+struct Hexdump;
+
+impl Command for Hexdump {
+    fn name(&self) -> &'static str {
+        "hexdump"
+    }
+
+    fn description(&self) -> &'static str {
+        "Print a file, kernel memory or a disk block in hexadecimal"
+    }
+
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a> {
+        Box::pin(async move {
+            hexdump(args).await;
+
+            Flow::Continue
+        })
+    }
+}
+
+/// Pauses the shell for a number of seconds, without blocking the other tasks.
+struct Sleep;
+
+impl Command for Sleep {
+    fn name(&self) -> &'static str {
+        "sleep"
+    }
+
+    fn description(&self) -> &'static str {
+        "Pause for a number of seconds"
+    }
+
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a> {
+        Box::pin(async move {
+            sleep(args).await;
+
+            Flow::Continue
+        })
+    }
+}
+
+/// Shows a live view of the system, until a key is pressed.
+///
+/// # Fields
+///
+/// * `keys` - The channel the pressed keys arrive through.
+struct Top {
+    keys: Arc<Channel<DecodedKey>>,
+}
+
+impl Command for Top {
+    fn name(&self) -> &'static str {
+        "top"
+    }
+
+    fn description(&self) -> &'static str {
+        "Show a live view of the tasks, memory and time"
+    }
+
+    fn run<'a>(&'a self, _args: &'a [&'a str]) -> CommandFuture<'a> {
+        Box::pin(async move {
+            top(&self.keys).await;
+
+            Flow::Continue
+        })
+    }
+}
+
+/// Collects the commands the shell can run.
+///
+/// # Arguments
+///
+/// * `spawner` - The spawner used to run commands as separate tasks.
+/// * `keys` - The channel the pressed keys arrive through.
+/// * `environment` - The shell variables.
+///
+/// # Returns
+///
+/// * `Vec<Box<dyn Command>>` - The commands, sorted by name.
+fn commands(
+    spawner: Spawner,
+    keys: &Arc<Channel<DecodedKey>>,
+    environment: &Environment,
+) -> Vec<Box<dyn Command>> {
+    let mut commands: Vec<Box<dyn Command>> = BUILTINS
+        .iter()
+        .map(|&builtin| Box::new(builtin) as Box<dyn Command>)
+        .collect();
+
+    commands.push(Box::new(Echo));
+    commands.push(Box::new(Exit));
+    commands.push(Box::new(Hexdump));
+    commands.push(Box::new(Primes {
+        spawner: spawner.clone(),
+    }));
+    commands.push(Box::new(Set {
+        environment: environment.clone(),
+    }));
+    commands.push(Box::new(Unset {
+        environment: environment.clone(),
+    }));
+    commands.push(Box::new(shutdown::Shutdown { spawner }));
+    commands.push(Box::new(Sleep));
+    commands.push(Box::new(Top { keys: keys.clone() }));
+
+    // Help lists every command, itself included, so it's registered last.
+    let mut help = Help {
+        commands: commands
+            .iter()
+            .map(|command| (command.name(), command.description()))
+            .collect(),
+    };
+    help.commands.push((help.name(), help.description()));
+    // Only calls made through the system call interface are traced. Most commands call into the kernel directly, and
+    // without processes, the calls of other tasks running meanwhile are traced too.
+    help.commands.push((
+        "strace",
+        "Log the system calls of a command to serial, like echo or uname",
+    ));
+    help.commands.sort_unstable();
+    commands.push(Box::new(help));
+
+    commands.sort_unstable_by_key(|command| command.name());
+    commands
+}
+
+/// Runs the shell.
+///
+/// Reads commands from the keyboard and executes them, until `exit` is run.
+///
+/// # Arguments
+///
+/// * `spawner` - The spawner used to run commands as separate tasks.
+/// * `keys` - The channel the keyboard task sends the pressed keys into.
+pub async fn run(spawner: Spawner, keys: Arc<Channel<DecodedKey>>) {
+    let environment = Environment::default();
+    let commands = commands(spawner, &keys, &environment);
+
+    loop {
+        print!("{PROMPT}");
+
+        // Interrupted with `Ctrl+C`, so discard the line.
+        let Some(line) = keyboard::read_line_from(&keys).await else {
+            continue;
+        };
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let tokens = expand(&tokens, &environment.borrow());
+        let args = tokens.iter().map(String::as_str).collect::<Vec<_>>();
+
+        // `strace` prefixes a command, to trace the system calls it makes.
+        let (traced, args) = match args.split_first() {
+            Some((&"strace", args)) => (true, args),
+            _ => (false, args.as_slice()),
+        };
+
+        let Some((&name, args)) = args.split_first() else {
+            if traced {
+                println!("Usage: strace <command> [args...]");
+            }
+
+            continue;
+        };
+
+        let Some(command) = commands.iter().find(|command| command.name() == name) else {
+            println!("{name}: command not found, try 'help'");
+
+            continue;
+        };
+
+        calls::set_tracing(traced);
+        let flow = command.run(args).await;
+        calls::set_tracing(false);
+
+        if flow == Flow::Exit {
+            return;
+        }
+    }
+}
+
+/// Prints the CPU vendor, brand and detected features.
+fn cpuinfo() {
+    let features = &*cpu::CPU_FEATURES;
+
+    println!("Vendor: {vendor}", vendor = features.vendor_string());
+    println!("Brand: {brand}", brand = features.brand_string());
+    println!(
+        "Features: {names}",
+        names = features.names().collect::<Vec<_>>().join(" ")
+    );
+}
+
+/// Runs the kernel diagnostics, and prints a summary.
+fn selftest() {
+    let report = selftest::run();
+
+    println!(
+        "{passed} passed, {failed} failed.",
+        passed = report.passed,
+        failed = report.failed
+    );
+}
+
+/// Prints the size, used and free space of the mounted file system.
+fn df() {
+    let (free, total) = match fs::free_space() {
+        Ok(space) => space,
+        Err(err) => {
+            println!("df: {err}");
+
+            return;
+        }
+    };
+
+    let used = total - free;
+    let percent = (used * 100).checked_div(total).unwrap_or(0);
+
+    println!("{:>10} {:>10} {:>10} {:>4}", "Size", "Used", "Free", "Use%");
+    println!("{total:>10} {used:>10} {free:>10} {percent:>3}%");
+}
+
+/// Prints a file, a range of kernel memory, or a disk block, in hexadecimal.
+///
+/// # Arguments
+///
+/// * `args` - The arguments, either a path, `-m` followed by a hexadecimal address and a length, or `-d` followed by
+///   a bus, a drive and a block.
+///
+/// # Notes
+///
+/// * Disk blocks are read without blocking the other tasks, by waiting for the drive's interrupt.
+async fn hexdump(args: &[&str]) {
+    match args {
+        ["-d", bus, drive, block] => {
+            let (Ok(bus), Ok(drive), Ok(block)) = (bus.parse(), drive.parse(), block.parse())
+            else {
+                println!("hexdump: invalid bus, drive or block");
+
+                return;
+            };
+
+            let mut buffer = [0; ata::BLOCK_SIZE];
+            match ata::read_async(bus, drive, block, &mut buffer).await {
+                Ok(()) => print_hex(0, &buffer),
+                Err(err) => println!("hexdump: {err}"),
+            }
+        }
+        [path] if !matches!(*path, "-m" | "-d") => match fs::read(path) {
+            Ok(contents) => print_hex(0, &contents),
+            Err(err) => println!("hexdump: {err}"),
+        },
+        ["-m", addr, len] => {
+            let addr = u64::from_str_radix(addr.trim_start_matches("0x"), 16);
+            let (Ok(addr), Ok(len)) = (addr, len.parse::<usize>()) else {
+                println!("hexdump: invalid address or length");
+
+                return;
+            };
+
+            let (Ok(start), Ok(size)) = (usize::try_from(addr), u64::try_from(len)) else {
+                println!("hexdump: invalid address or length");
+
+                return;
+            };
+
+            if !mem::is_mapped(addr, size) {
+                println!("hexdump: {addr:#X} (+{len} bytes) isn't mapped");
+
+                return;
+            }
+
+            // The whole range was just checked to be mapped.
+            let bytes = unsafe { core::slice::from_raw_parts(start as *const u8, len) };
+            print_hex(start, bytes);
+        }
+        _ => println!("Usage: hexdump <path> | -m <hex-addr> <len> | -d <bus> <drive> <block>"),
+    }
+}
+
+/// Prints bytes 16 per line, as an offset followed by the bytes in hexadecimal and ASCII.
+///
+/// # Arguments
+///
+/// * `offset` - The offset of the first byte.
+/// * `bytes` - The bytes to print.
+fn print_hex(offset: usize, bytes: &[u8]) {
+    for (index, line) in bytes.chunks(16).enumerate() {
+        print!("{offset:08X}  ", offset = offset + index * 16);
+
+        for column in 0..16 {
+            match line.get(column) {
+                Some(byte) => print!("{byte:02x} "),
+                None => print!("   "),
+            }
+
+            // Split the bytes into two groups of eight.
+            if column == 7 {
+                print!(" ");
+            }
+        }
+
+        // Non-printable bytes are shown as dots.
+        let ascii = line
+            .iter()
+            .map(|&byte| {
+                if byte.is_ascii_graphic() || byte == b' ' {
+                    char::from(byte)
+                } else {
+                    '.'
+                }
+            })
+            .collect::<String>();
+
+        println!("|{ascii}|");
+    }
+}
+
+/// Prints or switches the active keyboard layout.
+///
+/// # Arguments
+///
+/// * `args` - The arguments, either empty or the name of the layout to switch to.
+fn keymap(args: &[&str]) {
+    match args {
+        [] => println!("{name}", name = keyboard::layout().name()),
+        [name] => match Layout::from_name(name) {
+            Some(layout) => keyboard::set_layout(layout),
+            None => println!("keymap: unknown layout '{name}'"),
+        },
+        _ => {
+            let names = Layout::ALL.map(Layout::name);
+
+            println!("Usage: keymap [{names}]", names = names.join("|"));
+        }
+    }
+}
+
+/// Lists a directory, as names in columns, or one entry per line with `-l`.
+///
+/// # Arguments
+///
+/// * `args` - The arguments, an optional `-l` followed by an optional path.
+fn ls(args: &[&str]) {
+    let (long, path) = match args {
+        [] => (false, "/"),
+        ["-l"] => (true, "/"),
+        ["-l", path] => (true, *path),
+        [path] if !path.starts_with('-') => (false, *path),
+        _ => {
+            println!("Usage: ls [-l] [path]");
+
+            return;
+        }
+    };
+
+    // Print the entries as they're read, rather than collecting the whole directory on the heap first.
+    let mut count = 0;
+    let result = fs::for_each_entry(path, |entry| {
+        if long {
+            println!(
+                "{flags} {size:>10} {modified} {name}",
+                flags = attribute_flags(entry.attributes),
+                size = entry.size,
+                modified = entry.modified,
+                name = entry.name,
+            );
+        } else if (count + 1) % LS_COLUMNS == 0 {
+            println!("{name}", name = entry.name);
+        } else {
+            print!("{name:LS_COLUMN_WIDTH$}", name = entry.name);
+        }
+
+        count += 1;
+    });
+
+    // End a partly filled row of names.
+    if !long && count % LS_COLUMNS != 0 {
+        println!();
+    }
+
+    if let Err(err) = result {
+        println!("ls: {err}");
+    }
+}
+
+/// Formats the attributes of a directory entry as flags, like `----A` for an archive.
+///
+/// # Arguments
+///
+/// * `attributes` - The attributes.
+///
+/// # Returns
+///
+/// * `String` - A letter per set attribute, in the order `RHSDA`, and `-` for every unset one.
+fn attribute_flags(attributes: u8) -> String {
+    [
+        (fat::READ_ONLY, 'R'),
+        (fat::HIDDEN, 'H'),
+        (fat::SYSTEM, 'S'),
+        (fat::DIRECTORY, 'D'),
+        (fat::ARCHIVE, 'A'),
+    ]
+    .iter()
+    .map(|&(flag, letter)| if attributes & flag == 0 { '-' } else { letter })
+    .collect()
+}
+
+/// Prints the usage of the heap and the physical frames.
+fn meminfo() {
+    let heap = allocator::heap_stats();
+    println!(
+        "Heap: {used} / {size} bytes in use",
+        used = heap.used,
+        size = heap.size,
+    );
+
+    match mem::frame_stats() {
+        Some(frames) => println!(
+            "Frames: {allocated} / {total} allocated",
+            allocated = frames.allocated,
+            total = frames.total,
+        ),
+        None => println!("Frames: unknown"),
+    }
+
+    let offset = unsafe { mem::PHYSICAL_MEMORY_OFFSET };
+    println!("Physical memory offset: {offset:#X}");
+}
+
+/// Tests the free physical frames, and prints the address of every bad one.
+fn memtest() {
+    let result = mem::test_free_frames(|tested, total| {
+        // Long enough that the watchdog would warn, so keep feeding it.
+        watchdog::pet();
+
+        if tested % 256 == 0 || tested == total {
+            print!("\rTesting frame {tested} / {total}...");
+        }
+    });
+    println!();
+
+    match result {
+        Ok(bad) if bad.is_empty() => println!("No errors found."),
+        Ok(bad) => {
+            for address in &bad {
+                println!("Mismatch at {address:#X}", address = address.as_u64());
+            }
+
+            println!("{count} bad frame(s) found.", count = bad.len());
+        }
+        Err(err) => println!("memtest: {err}"),
+    }
+}
+
+/// Counts the primes below a limit in a separate task, and prints how long it took.
+///
+/// # Arguments
+///
+/// * `spawner` - The spawner used to run the computation.
+/// * `args` - The arguments, which must be the limit.
+async fn primes(spawner: &Spawner, args: &[&str]) {
+    let [limit] = args else {
+        println!("Usage: primes <n>");
+
+        return;
+    };
+
+    let Ok(limit) = limit.parse::<usize>() else {
+        println!("primes: invalid limit '{limit}'");
+
+        return;
+    };
+
+    // Run the sieve in chunks, so the keyboard stays responsive, and only time the chunks themselves.
+    let mut sieve = primes::PrimeSieve::new(limit);
+    let mut elapsed = 0;
+    let task = spawner.spawn_chunked(primes::SIEVE_CHUNK_SIZE, move |budget| {
+        let (poll, ns) = time::measure(|| sieve.step(budget));
+        elapsed += ns;
+
+        poll.map(|count| (count, elapsed))
+    });
+    match task {
+        Ok(handle) => {
+            let (count, elapsed) = handle.await;
+
+            println!(
+                "{count} primes below {limit} ({ms}.{us:03} ms)",
+                ms = elapsed / 1_000_000,
+                us = elapsed / 1_000 % 1_000
+            );
+        }
+        Err(err) => println!("primes: {err}"),
+    }
+}
+
+/// Prints the devices found on the PCI bus.
+fn lspci() {
+    for device in pci::devices() {
+        println!(
+            "{bus:02X}:{dev:02X}.{function} {vendor:04X}:{id:04X} (Class: {class:02X}, Subclass: {subclass:02X})",
+            bus = device.bus,
+            dev = device.device,
+            function = device.function,
+            vendor = device.vendor_id,
+            id = device.device_id,
+            class = device.class,
+            subclass = device.subclass,
+        );
+    }
+}
+
+/// Pauses the shell for a number of seconds.
+///
+/// # Arguments
+///
+/// * `args` - The arguments, which must be the number of seconds.
+async fn sleep(args: &[&str]) {
+    let [seconds] = args else {
+        println!("Usage: sleep <seconds>");
+
+        return;
+    };
+
+    let seconds = match seconds.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => seconds,
+        _ => {
+            println!("sleep: invalid time '{seconds}'");
+            println!("Usage: sleep <seconds>");
+
+            return;
+        }
+    };
+
+    task::timer::sleep(seconds).await;
+}
+
+/// Shows the uptime, the time, the number of tasks and the memory usage, redrawn every second until a key is pressed.
+///
+/// # Arguments
+///
+/// * `keys` - The channel the pressed keys arrive through.
+async fn top(keys: &Channel<DecodedKey>) {
+    // Drop keys typed before, so only a key pressed from now on quits.
+    while keys.try_recv().is_some() {}
+
+    clear!();
+    tui::draw_box(0, 0, TOP_WIDTH, 9);
+    tui::write_at(0, 2, " top - press any key to quit ");
+
+    loop {
+        draw_top();
+
+        // Wait for a key or the next redraw, whichever comes first.
+        let mut key = keys.recv();
+        let mut redraw = task::timer::sleep(1.0);
+        let pressed = future::poll_fn(|context| {
+            if Pin::new(&mut key).poll(context).is_ready() {
+                return Poll::Ready(true);
+            }
+
+            Pin::new(&mut redraw).poll(context).map(|()| false)
+        })
+        .await;
+
+        if pressed {
+            break;
+        }
+    }
+
+    clear!();
+}
+
+/// Draws the values shown by `top` inside its box.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn draw_top() {
+    let seconds = clock::uptime() as u64;
+    let (century, year, month, day, hours, minutes, rtc_seconds) = RTC::new().date_time();
+    let heap = allocator::heap_stats();
+    let frames = mem::frame_stats().map_or_else(
+        || "unknown".to_string(),
+        |frames| format!("{} / {} allocated", frames.allocated, frames.total),
+    );
+
+    let lines = [
+        format!(
+            "Uptime: {days}d {hours:02}:{minutes:02}:{seconds:02}",
+            days = seconds / 86_400,
+            hours = seconds % 86_400 / 3_600,
+            minutes = seconds % 3_600 / 60,
+            seconds = seconds % 60,
+        ),
+        format!("Time:   {century:02}{year:02}-{month:02}-{day:02} {hours:02}:{minutes:02}:{rtc_seconds:02}"),
+        format!("Tasks:  {count}", count = task::task_count()),
+        format!("Heap:   {used} / {size} bytes", used = heap.used, size = heap.size),
+        format!("Frames: {frames}"),
+    ];
+
+    // Padded to the inside of the box, so shorter values overwrite longer ones.
+    for (row, line) in lines.iter().enumerate() {
+        tui::write_at(row + 2, 2, &format!("{line:width$}", width = TOP_WIDTH - 4));
+    }
+}
+
+/// Prints the kernel version and build information.
+///
+/// # Arguments
+///
+/// * `args` - The arguments, where `-r` prints only the version number.
+///
+/// # Notes
+///
+/// * The version string is asked for through the `Version` system call, like any program would.
+fn uname(args: &[&str]) {
+    match args {
+        [] | ["-a"] => println!("{version}", version = stdlib::version()),
+        ["-r"] => println!("{KERNEL_VERSION}"),
+        _ => println!("Usage: uname [-a|-r]"),
+    }
+}
+
+/// Prints how long the system has been up, and the number of timer ticks since boot.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn uptime() {
+    // Sub-second values are rounded down.
+    let seconds = clock::uptime() as u64;
+
+    println!(
+        "up {days}d {hours}h {minutes}m {seconds}s",
+        days = seconds / 86_400,
+        hours = seconds % 86_400 / 3_600,
+        minutes = seconds % 3_600 / 60,
+        seconds = seconds % 60,
+    );
+    println!("{ticks} ticks", ticks = time::tick());
+}
@@ -0,0 +1,222 @@
+#![no_std]
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use kernel::sys::task::executor::Spawner;
+use kernel::sys::task::timer;
+use kernel::{fs, println};
+use stdlib::command::{self, CommandFuture, Flow};
+
+/// The usage message, printed when the arguments are invalid.
+const USAGE: &str = "Usage: shutdown [-r|-s] [-t <seconds>] | shutdown -c";
+
+/// The ID of the pending countdown, or `0` if there's none.
+///
+/// Clearing this makes the pending countdown stop at its next tick, and a countdown started after it won't be
+/// mistaken for the cancelled one.
+static PENDING: AtomicU64 = AtomicU64::new(0);
+/// The ID of the next countdown.
+static NEXT_COUNTDOWN: AtomicU64 = AtomicU64::new(1);
+
+/// What to do once the countdown ends.
+///
+/// # Variants
+///
+/// * `Shutdown` - Shut down the machine.
+/// * `Reboot` - Reboot the machine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Shutdown,
+    Reboot,
+}
+
+/// A parsed command line.
+///
+/// # Variants
+///
+/// * `Cancel` - Cancel the pending countdown.
+/// * `Schedule` - Perform the action after the delay, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Cancel,
+    Schedule { action: Action, delay: u64 },
+}
+
+/// Parses the given arguments.
+///
+/// # Arguments
+///
+/// * `args` - The arguments.
+///
+/// # Returns
+///
+/// * `Option<Command>` - The command, or `None` if the arguments are invalid.
+fn parse(args: &[&str]) -> Option<Command> {
+    let mut action = None;
+    let mut delay = 0;
+    let mut cancel = false;
+
+    let mut args = args.iter();
+    while let Some(&arg) = args.next() {
+        match arg {
+            "-c" => cancel = true,
+            "-r" | "-s" => {
+                let new_action = if arg == "-r" {
+                    Action::Reboot
+                } else {
+                    Action::Shutdown
+                };
+
+                // Conflicting actions.
+                if action.is_some_and(|action| action != new_action) {
+                    return None;
+                }
+
+                action = Some(new_action);
+            }
+            "-t" => delay = args.next()?.parse().ok()?,
+            _ => return None,
+        }
+    }
+
+    if cancel {
+        // Cancelling doesn't take any other flags.
+        return (action.is_none() && delay == 0).then_some(Command::Cancel);
+    }
+
+    Some(Command::Schedule {
+        action: action.unwrap_or(Action::Shutdown),
+        delay,
+    })
+}
+
+/// Runs the shutdown program.
+///
+/// # Arguments
+///
+/// * `spawner` - The spawner used to run the countdown.
+/// * `args` - The arguments, see [`USAGE`].
+///
+/// # Notes
+///
+/// * The countdown runs as a separate task, so this returns right away and `-c` can cancel it.
+pub fn run(spawner: &Spawner, args: &[&str]) {
+    let Some(command) = parse(args) else {
+        println!("{USAGE}");
+
+        return;
+    };
+
+    let (action, delay) = match command {
+        Command::Cancel => {
+            if PENDING.swap(0, Ordering::SeqCst) == 0 {
+                println!("shutdown: nothing to cancel");
+            } else {
+                println!("shutdown: cancelled");
+            }
+
+            return;
+        }
+        Command::Schedule { action, delay } => (action, delay),
+    };
+
+    if delay == 0 {
+        perform(action);
+    }
+
+    let id = NEXT_COUNTDOWN.fetch_add(1, Ordering::Relaxed);
+    if PENDING
+        .compare_exchange(0, id, Ordering::SeqCst, Ordering::SeqCst)
+        .is_err()
+    {
+        println!("shutdown: already pending, cancel it with 'shutdown -c'");
+
+        return;
+    }
+
+    if let Err(err) = spawner.spawn(countdown(id, action, delay)) {
+        PENDING.store(0, Ordering::SeqCst);
+        println!("shutdown: {err}");
+    }
+}
+
+/// Counts down, then performs the action, unless the countdown is cancelled first.
+///
+/// # Arguments
+///
+/// * `id` - The ID of the countdown.
+/// * `action` - What to do once the countdown ends.
+/// * `delay` - The length of the countdown, in seconds.
+async fn countdown(id: u64, action: Action, delay: u64) {
+    for remaining in (1..=delay).rev() {
+        println!("{verb} in {remaining} second(s)...", verb = verb(action));
+
+        timer::sleep(1.0).await;
+
+        if PENDING.load(Ordering::SeqCst) != id {
+            return;
+        }
+    }
+
+    perform(action);
+}
+
+/// Describes the given action, for the messages printed before it's performed.
+///
+/// # Arguments
+///
+/// * `action` - The action.
+///
+/// # Returns
+///
+/// * `&'static str` - The description.
+const fn verb(action: Action) -> &'static str {
+    match action {
+        Action::Shutdown => "Shutting down",
+        Action::Reboot => "Rebooting",
+    }
+}
+
+/// Syncs the file system and performs the given action, through its system call.
+///
+/// # Arguments
+///
+/// * `action` - The action.
+fn perform(action: Action) -> ! {
+    println!("{verb}...", verb = verb(action));
+
+    // Don't lose buffered writes, but don't refuse to power off over them either.
+    if let Err(err) = fs::sync() {
+        println!("shutdown: failed to sync the file system: {err}");
+    }
+
+    match action {
+        Action::Shutdown => stdlib::shutdown(),
+        Action::Reboot => stdlib::reboot(),
+    }
+}
+
+/// The shutdown program, as a shell command.
+///
+/// # Fields
+///
+/// * `spawner` - The spawner used to run the countdown.
+pub struct Shutdown {
+    pub spawner: Spawner,
+}
+
+impl command::Command for Shutdown {
+    fn name(&self) -> &'static str {
+        "shutdown"
+    }
+
+    fn description(&self) -> &'static str {
+        "Shut down or reboot the machine"
+    }
+
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a> {
+        run(&self.spawner, args);
+
+        command::ready(Flow::Continue)
+    }
+}
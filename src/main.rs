@@ -7,12 +7,19 @@ extern crate alloc;
 
 use core::panic::PanicInfo;
 
+use alloc::sync::Arc;
 use bootloader::{entry_point, BootInfo};
+
 use kernel::println;
+use kernel::sys::task::channel::Channel;
+use kernel::sys::task::{keyboard, Task};
 
 /// The version of the operating sys.
 pub const OS_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// The number of decoded keys buffered between the keyboard task and the shell.
+const KEY_CHANNEL_SIZE: usize = 32;
+
 entry_point!(kernel_main);
 
 /// The kernel main function.
@@ -36,6 +43,27 @@ fn kernel_main(boot_info: &'static BootInfo) -> ! {
 
     println!("[INFO]: Rust OS v{OS_VERSION} initialized successfully!");
 
+    // The keyboard task decodes the keys, and the shell consumes them.
+    let keys = Arc::new(Channel::new(KEY_CHANNEL_SIZE));
+    if let Err(why) = executor.spawn(Task::new(keyboard::forward_keys(keys.clone()))) {
+        println!(
+            "[ERROR]: Failed to start the keyboard task: {err:#?}",
+            err = why
+        );
+        kernel::hlt_loop();
+    }
+
+    // The shell is the only program to start for now.
+    let init = kernel::boot_args::get().init;
+    if init != "shell" {
+        println!("[WARN]: Unknown init program `{init}`, starting the shell instead!");
+    }
+
+    if let Err(why) = executor.spawn(Task::new(shell::run(executor.spawner(), keys))) {
+        println!("[ERROR]: Failed to start the shell: {err:#?}", err = why);
+        kernel::hlt_loop();
+    }
+
     executor.run();
 }
 
@@ -51,6 +79,8 @@ fn kernel_main(boot_info: &'static BootInfo) -> ! {
 #[cfg(not(test))]
 #[panic_handler]
 pub fn panic(info: &PanicInfo) -> ! {
+    // Show whatever was printed during an unfinished batch, along with the panic.
+    kernel::vga_buffer::end_batch();
     println!("[ERROR]: {info}");
 
     kernel::hlt_loop();
@@ -0,0 +1,62 @@
+use alloc::boxed::Box;
+use core::future::Future;
+use core::pin::Pin;
+
+/// What the shell does once a command is done.
+///
+/// # Variants
+///
+/// * `Continue` - Read the next command.
+/// * `Exit` - Stop the shell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Exit,
+}
+
+/// The future of a running command, borrowing the command and its arguments.
+pub type CommandFuture<'a> = Pin<Box<dyn Future<Output = Flow> + 'a>>;
+
+/// A command the shell can run by name.
+///
+/// Programs implement this to be registered with the shell at startup.
+pub trait Command {
+    /// Gets the name the command is run by.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The name.
+    fn name(&self) -> &'static str;
+
+    /// Gets a one line description of the command, listed by `help`.
+    ///
+    /// # Returns
+    ///
+    /// * `&'static str` - The description.
+    fn description(&self) -> &'static str;
+
+    /// Runs the command.
+    ///
+    /// # Arguments
+    ///
+    /// * `args` - The arguments, without the name of the command.
+    ///
+    /// # Returns
+    ///
+    /// * `CommandFuture` - A future that resolves to what the shell does next, once the command is done.
+    fn run<'a>(&'a self, args: &'a [&'a str]) -> CommandFuture<'a>;
+}
+
+/// Wraps the outcome of a command that already ran to completion.
+///
+/// # Arguments
+///
+/// * `flow` - What the shell does next.
+///
+/// # Returns
+///
+/// * `CommandFuture` - A future that resolves right away.
+#[must_use]
+pub fn ready<'a>(flow: Flow) -> CommandFuture<'a> {
+    Box::pin(core::future::ready(flow))
+}
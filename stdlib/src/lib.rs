@@ -1,6 +1,169 @@
 #![no_std]
 #![feature(c_variadic)]
+extern crate alloc;
 
-pub extern "C" fn printf(format: *const u8) -> i32 {
-    0
+use alloc::string::String;
+use core::ffi::CStr;
+
+use kernel::fs::fd::{STDIN, STDOUT};
+use kernel::sys::calls::{self, Call};
+
+pub mod command;
+
+/// The most bytes [`read_line`] reads in one go, including the trailing newline.
+const LINE_CAPACITY: usize = 256;
+
+/// Prints a C string to the standard output, through the [`Call::Write`] system call.
+///
+/// # Arguments
+///
+/// * `format` - The string to print, terminated by a NUL byte.
+///
+/// # Returns
+///
+/// * `i32` - The number of bytes printed, or -1 if writing failed.
+///
+/// # Safety
+///
+/// * `format` must point to a valid NUL terminated string.
+///
+/// # Notes
+///
+/// * Conversion specifiers aren't expanded yet, so the string is printed as is.
+pub unsafe extern "C" fn printf(format: *const u8) -> i32 {
+    let bytes = unsafe { CStr::from_ptr(format.cast()) }.to_bytes();
+
+    write(STDOUT, bytes)
+        .and_then(|count| i32::try_from(count).ok())
+        .unwrap_or(-1)
+}
+
+/// Writes bytes to a file descriptor, through the [`Call::Write`] system call.
+///
+/// # Arguments
+///
+/// * `fd` - The file descriptor.
+/// * `bytes` - The bytes to write.
+///
+/// # Returns
+///
+/// * `Option<usize>` - The number of bytes written, or `None` if writing failed.
+#[must_use]
+pub fn write(fd: usize, bytes: &[u8]) -> Option<usize> {
+    // The slice is valid for reads.
+    unsafe { calls::dispatch(&Call::Write, &[fd, bytes.as_ptr() as usize, bytes.len()]) }
+}
+
+/// Prints text to the standard output.
+///
+/// # Arguments
+///
+/// * `text` - The text to print.
+///
+/// # Notes
+///
+/// * The text goes wherever file descriptor 1 refers to, so it follows any redirection of the standard output.
+pub fn print(text: &str) {
+    let _ = write(STDOUT, text.as_bytes());
+}
+
+/// Reads a line from the standard input, through the [`Call::Read`] system call.
+///
+/// # Returns
+///
+/// * `Option<String>` - The line, without the trailing newline, or `None` if nothing was read.
+///
+/// # Notes
+///
+/// * While the standard input is the keyboard, this blocks until a line is entered.
+/// * Lines longer than 256 bytes are cut short.
+#[must_use]
+pub fn read_line() -> Option<String> {
+    let mut buffer = [0; LINE_CAPACITY];
+    // The buffer is valid for writes.
+    let count = unsafe {
+        calls::dispatch(
+            &Call::Read,
+            &[STDIN, buffer.as_mut_ptr() as usize, buffer.len()],
+        )
+    }?;
+
+    // Interrupted, or at the end of the input.
+    if count == 0 {
+        return None;
+    }
+
+    let line = String::from_utf8_lossy(&buffer[..count]);
+
+    Some(line.strip_suffix('\n').unwrap_or(&line).into())
+}
+
+/// Shuts down the machine through the [`Call::Shutdown`] system call.
+///
+/// # Returns
+///
+/// * `!` - Never.
+pub extern "C" fn shutdown() -> ! {
+    // The system call never returns.
+    unsafe { syscall_noreturn(Call::Shutdown) }
+}
+
+/// Reboots the machine through the [`Call::Reboot`] system call.
+///
+/// # Returns
+///
+/// * `!` - Never.
+pub extern "C" fn reboot() -> ! {
+    // The system call never returns.
+    unsafe { syscall_noreturn(Call::Reboot) }
+}
+
+/// Makes a system call that never returns with the `syscall` instruction, like a program in user mode would.
+///
+/// # Arguments
+///
+/// * `call` - The system call, which takes no arguments.
+///
+/// # Returns
+///
+/// * `!` - Never.
+///
+/// # Safety
+///
+/// * The system call must never return. The programs still run in ring 0, and `sysret` always returns to ring 3.
+/// * The `syscall` instruction must be set up, see [`kernel::sys::calls::syscall::init`].
+unsafe fn syscall_noreturn(call: Call) -> ! {
+    unsafe {
+        core::arch::asm!("syscall", in("rax") call as usize, options(noreturn));
+    }
+}
+
+/// Gets the version string of the kernel through the [`Call::Version`] system call.
+///
+/// # Returns
+///
+/// * `&'static str` - The version string, with the build information.
+#[must_use]
+pub fn version() -> &'static str {
+    let mut len = 0;
+    // The length is valid for writes.
+    let Some(ptr) =
+        (unsafe { calls::dispatch(&Call::Version, &[core::ptr::addr_of_mut!(len) as usize]) })
+    else {
+        return "";
+    };
+
+    // The kernel hands out a pointer to a static string.
+    let bytes = unsafe { core::slice::from_raw_parts(ptr as *const u8, len) };
+
+    core::str::from_utf8(bytes).unwrap_or_default()
+}
+
+/// Gives the other tasks a turn, resuming once the executor gets back to this one.
+///
+/// # Notes
+///
+/// * Programs run as tasks of the kernel's executor, so yielding is an `.await` rather than a system call.
+pub async fn yield_now() {
+    kernel::sys::task::yield_now().await;
 }